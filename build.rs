@@ -1,15 +1,94 @@
-use std::process::Command;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::{Command, Output};
 
 fn main() {
-    let _ = Command::new("make").args(["-C", "c", "clean"]).status();
-    let _ = Command::new("make").args(["-C", "c", "distclean"]).status();
-    Command::new(std::fs::canonicalize("c/configure").unwrap())
-        .current_dir("c")
-        .status()
-        .unwrap();
-    Command::new("make").args(["-C", "c"]).status().unwrap();
+    if std::env::var_os("CARGO_FEATURE_NO_NATIVE").is_some() {
+        return;
+    }
+    let configure = Path::new("c/configure");
+    if !configure.exists() {
+        panic!(
+            "c/configure is not found. Generate it by running `autoreconf -i` in the `c` directory \
+             (autoconf and automake are required)."
+        );
+    }
+    if !tool_exists("make") {
+        panic!("`make` is not found. Install make to build the C part of rust-locale.");
+    }
+    // `CC` may contain a wrapper and options, e.g. `ccache gcc` or `gcc -m32`, so only the first word is probed.
+    let cc = std::env::var("CC").unwrap_or_default();
+    match cc.split_whitespace().next() {
+        Some(program) if !tool_exists(program) => {
+            panic!("the C compiler `{}` specified by CC is not found.", program)
+        }
+        Some(_) => {}
+        None if !tool_exists("cc") && !tool_exists("gcc") => {
+            panic!("no C compiler is found. Install a C compiler or specify one by CC.")
+        }
+        None => {}
+    }
+
+    // `c/Makefile` exists only if `c/configure` has been run by a previous build.
+    if Path::new("c/Makefile").exists() {
+        let output = run(
+            Command::new("make").args(["-C", "c", "distclean"]),
+            "make distclean",
+        );
+        if !output.status.success() {
+            println!(
+                "cargo:warning=make distclean failed. status={}",
+                output.status
+            );
+        }
+    }
+    let output = run(
+        Command::new(std::fs::canonicalize(configure).unwrap()).current_dir("c"),
+        "c/configure",
+    );
+    check(&output, "c/configure", "See c/config.log for details.");
+    let output = run(Command::new("make").args(["-C", "c"]), "make");
+    check(&output, "make", "");
+
     println!(r"cargo:rustc-link-search=c/src");
     println!(r"cargo:rustc-link-search=c/lib");
     println!(r"cargo:rustc-link-lib=static=gnu");
     println!(r"cargo:rerun-if-changed=c/src/ctype.c");
+    println!(r"cargo:rerun-if-changed=c/src/collate.c");
+    println!(r"cargo:rerun-if-changed=c/src/numeric.c");
+    println!(r"cargo:rerun-if-changed=c/src/monetary.c");
+    println!(r"cargo:rerun-if-changed=c/src/langinfo.c");
+    println!(r"cargo:rerun-if-changed=c/src/width.c");
+    println!(r"cargo:rerun-if-changed=c/src/time.c");
+    println!(r"cargo:rerun-if-changed=c/src/encoding.c");
+    println!(r"cargo:rerun-if-changed=c/src/utf8.c");
+    println!(r"cargo:rerun-if-changed=c/src/utf8.h");
+}
+
+/// Checks if `program` can be executed.
+fn tool_exists(program: &str) -> bool {
+    match Command::new(program).arg("--version").output() {
+        Ok(_) => true,
+        Err(e) => e.kind() != ErrorKind::NotFound,
+    }
+}
+
+fn run(command: &mut Command, name: &str) -> Output {
+    match command.output() {
+        Ok(output) => output,
+        Err(e) => panic!("failed to run {}. error={}", name, e),
+    }
+}
+
+fn check(output: &Output, name: &str, hint: &str) {
+    if !output.status.success() {
+        panic!(
+            "{} failed. status={} {}\n--- stdout\n{}\n--- stderr\n{}",
+            name,
+            output.status,
+            hint,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
 }
@@ -0,0 +1,92 @@
+#include <config.h>
+
+#include <inttypes.h>
+#include <locale.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <wchar.h>
+
+#include "utf8.h"
+
+int_fast8_t
+wcscoll_native(int* result, locale_t l, const char* a, size_t a_length, const char* b, size_t b_length)
+{
+    const locale_t u = utf8_locale();
+    if (!u) {
+        return 0x1;
+    }
+    const locale_t previous = uselocale(u);
+
+    wchar_t* const wa = utf8towcs(a, a_length);
+    wchar_t* const wb = utf8towcs(b, b_length);
+
+    (void)uselocale(previous);
+    freelocale(u);
+
+    int_fast8_t ret = 0;
+    if (wa && wb) {
+        *result = wcscoll_l(wa, wb, l);
+    } else {
+        ret = 0x2;
+    }
+
+    free(wa);
+    free(wb);
+    return ret;
+}
+
+int
+strcoll_native(locale_t l, const char* a, const char* b)
+{
+    return strcoll_l(a, b, l);
+}
+
+int_fast8_t
+wcscasecmp_native(int* result, locale_t l, const char* a, size_t a_length, const char* b, size_t b_length)
+{
+    const locale_t u = utf8_locale();
+    if (!u) {
+        return 0x1;
+    }
+    const locale_t previous = uselocale(u);
+
+    wchar_t* const wa = utf8towcs(a, a_length);
+    wchar_t* const wb = utf8towcs(b, b_length);
+
+    (void)uselocale(previous);
+    freelocale(u);
+
+    int_fast8_t ret = 0;
+    if (wa && wb) {
+        *result = wcscasecmp_l(wa, wb, l);
+    } else {
+        ret = 0x2;
+    }
+
+    free(wa);
+    free(wb);
+    return ret;
+}
+
+ssize_t
+wcsxfrm_native(wchar_t* dest, size_t n, locale_t l, const char* s, size_t length)
+{
+    const locale_t u = utf8_locale();
+    if (!u) {
+        return -0x1;
+    }
+    const locale_t previous = uselocale(u);
+
+    wchar_t* const ws = utf8towcs(s, length);
+
+    (void)uselocale(previous);
+    freelocale(u);
+
+    if (!ws) {
+        return -0x2;
+    }
+    const ssize_t ret = wcsxfrm_l(dest, ws, n, l);
+    free(ws);
+    return ret;
+}
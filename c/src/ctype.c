@@ -1,5 +1,6 @@
 #include <config.h>
 
+#include <ctype.h>
 #include <inttypes.h>
 #include <locale.h>
 #include <string.h>
@@ -7,20 +8,12 @@
 #include <wchar.h>
 #include <wctype.h>
 
+#include "utf8.h"
+
 /*
  * なぜか uselocale() を C の中でやらないといけない
  */
 
-static inline locale_t
-utf8_locale(void)
-{
-    locale_t l = newlocale(LC_CTYPE_MASK, "C.UTF-8", 0);
-    if (!l) {
-        l = newlocale(LC_CTYPE_MASK, "en_US.UTF-8", 0);
-    }
-    return l;
-}
-
 uint_fast8_t
 utf8towc(wchar_t* wcbuf, const char* utf8_bytes, size_t length)
 {
@@ -28,21 +21,30 @@ utf8towc(wchar_t* wcbuf, const char* utf8_bytes, size_t length)
     if (!l) {
         return 0x1;
     }
-    (void)uselocale(l);
+    const locale_t previous = uselocale(l);
 
     uint_fast8_t ret = 0;
 
     mbstate_t state;
     (void)memset(&state, 0, sizeof state);
-    if (mbrtowc(wcbuf, utf8_bytes, length, &state) != length) {
+    const size_t n = mbrtowc(wcbuf, utf8_bytes, length, &state);
+    if (n == (size_t)-2) {
+        /* 途中で途切れている */
+        ret = 0x3;
+    } else if (n != length && !(n == 0 && length == 1)) {
+        /* 不正なバイトを含むか、1 文字より長い。NUL 文字の場合 mbrtowc は 0 を返す */
         ret = 0x2;
     }
 
-    (void)uselocale(LC_GLOBAL_LOCALE);
+    (void)uselocale(previous);
     freelocale(l);
     return ret;
 }
 
+/*
+ * utf8_bytes には MB_LEN_MAX バイト以上必要。
+ * Unicode のスカラー値なら 4 バイト以下だが、U+10FFFF を超える値では最大 6 バイト書き込まれる。
+ */
 ssize_t
 wctoutf8(char* utf8_bytes, wchar_t wc)
 {
@@ -50,7 +52,7 @@ wctoutf8(char* utf8_bytes, wchar_t wc)
     if (!l) {
         return -0x1;
     }
-    (void)uselocale(l);
+    const locale_t previous = uselocale(l);
 
     ssize_t ret = 0;
 
@@ -61,7 +63,7 @@ wctoutf8(char* utf8_bytes, wchar_t wc)
         ret = -0x2;
     }
 
-    (void)uselocale(LC_GLOBAL_LOCALE);
+    (void)uselocale(previous);
     freelocale(l);
     return ret;
 }
@@ -73,37 +75,203 @@ iswspace_native(wint_t ch)
     if (!l) {
         return -1;
     }
-    (void)uselocale(l);
+    const locale_t previous = uselocale(l);
 
     const int ret = iswspace(ch);
 
-    (void)uselocale(LC_GLOBAL_LOCALE);
+    (void)uselocale(previous);
     freelocale(l);
     return ret ? 1 : 0;
 }
 
-int
+int_fast8_t
 iswblank_native(wint_t ch)
 {
     const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
-    (void)uselocale(l);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
 
     const int ret = iswblank(ch);
 
-    (void)uselocale(LC_GLOBAL_LOCALE);
+    (void)uselocale(previous);
     freelocale(l);
-    return ret;
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswalpha_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswalpha(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswdigit_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswdigit(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswalnum_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswalnum(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswpunct_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswpunct(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswcntrl_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswcntrl(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswprint_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswprint(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswgraph_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswgraph(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswxdigit_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswxdigit(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswlower_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswlower(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+iswupper_native(wint_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswupper(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
 }
 
 wint_t
 towupper_native(wint_t ch)
 {
     const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
-    (void)uselocale(l);
+    if (!l) {
+        return WEOF;
+    }
+    const locale_t previous = uselocale(l);
 
     const wint_t ret = towupper(ch);
 
-    (void)uselocale(LC_GLOBAL_LOCALE);
+    (void)uselocale(previous);
     freelocale(l);
     return ret;
 }
@@ -112,11 +280,318 @@ wint_t
 towlower_native(wint_t ch)
 {
     const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
-    (void)uselocale(l);
+    if (!l) {
+        return WEOF;
+    }
+    const locale_t previous = uselocale(l);
 
     const wint_t ret = towlower(ch);
 
-    (void)uselocale(LC_GLOBAL_LOCALE);
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret;
+}
+
+int_fast8_t
+wctype_native(uintptr_t* desc, const char* name)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    *desc = (uintptr_t)wctype(name);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return 0;
+}
+
+int_fast8_t
+iswctype_native(wint_t ch, uintptr_t desc)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = iswctype(ch, (wctype_t)desc);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret ? 1 : 0;
+}
+
+int_fast8_t
+wctrans_native(uintptr_t* desc, const char* name)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+    const locale_t previous = uselocale(l);
+
+    *desc = (uintptr_t)wctrans(name);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return 0;
+}
+
+wint_t
+towctrans_native(wint_t ch, uintptr_t desc)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return WEOF;
+    }
+    const locale_t previous = uselocale(l);
+
+    const wint_t ret = towctrans(ch, (wctrans_t)desc);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret;
+}
+
+int_fast8_t
+iswspace_l_native(wint_t ch, locale_t l)
+{
+    return iswspace_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswblank_l_native(wint_t ch, locale_t l)
+{
+    return iswblank_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswalpha_l_native(wint_t ch, locale_t l)
+{
+    return iswalpha_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswdigit_l_native(wint_t ch, locale_t l)
+{
+    return iswdigit_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswalnum_l_native(wint_t ch, locale_t l)
+{
+    return iswalnum_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswpunct_l_native(wint_t ch, locale_t l)
+{
+    return iswpunct_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswcntrl_l_native(wint_t ch, locale_t l)
+{
+    return iswcntrl_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswprint_l_native(wint_t ch, locale_t l)
+{
+    return iswprint_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswgraph_l_native(wint_t ch, locale_t l)
+{
+    return iswgraph_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswxdigit_l_native(wint_t ch, locale_t l)
+{
+    return iswxdigit_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswlower_l_native(wint_t ch, locale_t l)
+{
+    return iswlower_l(ch, l) ? 1 : 0;
+}
+
+int_fast8_t
+iswupper_l_native(wint_t ch, locale_t l)
+{
+    return iswupper_l(ch, l) ? 1 : 0;
+}
+
+wint_t
+towupper_l_native(wint_t ch, locale_t l)
+{
+    return towupper_l(ch, l);
+}
+
+wint_t
+towlower_l_native(wint_t ch, locale_t l)
+{
+    return towlower_l(ch, l);
+}
+
+uintptr_t
+wctype_l_native(const char* name, locale_t l)
+{
+    return (uintptr_t)wctype_l(name, l);
+}
+
+int_fast8_t
+iswctype_l_native(wint_t ch, uintptr_t desc, locale_t l)
+{
+    return iswctype_l(ch, (wctype_t)desc, l) ? 1 : 0;
+}
+
+/*
+ * CharFlags のビットと一致させること
+ */
+enum {
+    CLASS_SPACE = 1 << 0,
+    CLASS_BLANK = 1 << 1,
+    CLASS_ALPHA = 1 << 2,
+    CLASS_DIGIT = 1 << 3,
+    CLASS_ALNUM = 1 << 4,
+    CLASS_PUNCT = 1 << 5,
+    CLASS_CNTRL = 1 << 6,
+    CLASS_PRINT = 1 << 7,
+    CLASS_GRAPH = 1 << 8,
+    CLASS_XDIGIT = 1 << 9,
+    CLASS_LOWER = 1 << 10,
+    CLASS_UPPER = 1 << 11,
+};
+
+static uint16_t
+classify_l(wint_t ch, locale_t l)
+{
+    uint16_t flags = 0;
+    flags |= iswspace_l(ch, l) ? CLASS_SPACE : 0;
+    flags |= iswblank_l(ch, l) ? CLASS_BLANK : 0;
+    flags |= iswalpha_l(ch, l) ? CLASS_ALPHA : 0;
+    flags |= iswdigit_l(ch, l) ? CLASS_DIGIT : 0;
+    flags |= iswalnum_l(ch, l) ? CLASS_ALNUM : 0;
+    flags |= iswpunct_l(ch, l) ? CLASS_PUNCT : 0;
+    flags |= iswcntrl_l(ch, l) ? CLASS_CNTRL : 0;
+    flags |= iswprint_l(ch, l) ? CLASS_PRINT : 0;
+    flags |= iswgraph_l(ch, l) ? CLASS_GRAPH : 0;
+    flags |= iswxdigit_l(ch, l) ? CLASS_XDIGIT : 0;
+    flags |= iswlower_l(ch, l) ? CLASS_LOWER : 0;
+    flags |= iswupper_l(ch, l) ? CLASS_UPPER : 0;
+    return flags;
+}
+
+/*
+ * UTF-8 のバイト列の各文字を分類して flags に書き込む。
+ * flags には文字数以上の長さが必要。
+ */
+int_fast8_t
+classify_native(uint16_t* flags, const char* utf8_bytes, size_t length)
+{
+    const locale_t u = utf8_locale();
+    if (!u) {
+        return 0x1;
+    }
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        freelocale(u);
+        return -0x1;
+    }
+    const locale_t previous = uselocale(u);
+
+    int_fast8_t ret = 0;
+
+    mbstate_t state;
+    (void)memset(&state, 0, sizeof state);
+    while (length > 0) {
+        wchar_t wc;
+        size_t n = mbrtowc(&wc, utf8_bytes, length, &state);
+        if (n == (size_t)-1 || n == (size_t)-2) {
+            ret = 0x2;
+            break;
+        }
+        if (n == 0) {
+            n = 1;
+        }
+        *flags++ = classify_l(wc, l);
+        utf8_bytes += n;
+        length -= n;
+    }
+
+    (void)uselocale(previous);
+    freelocale(l);
+    freelocale(u);
+    return ret;
+}
+
+/*
+ * 1 バイトの文字 ch を isX_l() で分類した CLASS_* のビットを返す。
+ * 失敗したら -1。
+ */
+int32_t
+classify_byte_native(unsigned char ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+
+    int32_t flags = 0;
+    flags |= isspace_l(ch, l) ? CLASS_SPACE : 0;
+    flags |= isblank_l(ch, l) ? CLASS_BLANK : 0;
+    flags |= isalpha_l(ch, l) ? CLASS_ALPHA : 0;
+    flags |= isdigit_l(ch, l) ? CLASS_DIGIT : 0;
+    flags |= isalnum_l(ch, l) ? CLASS_ALNUM : 0;
+    flags |= ispunct_l(ch, l) ? CLASS_PUNCT : 0;
+    flags |= iscntrl_l(ch, l) ? CLASS_CNTRL : 0;
+    flags |= isprint_l(ch, l) ? CLASS_PRINT : 0;
+    flags |= isgraph_l(ch, l) ? CLASS_GRAPH : 0;
+    flags |= isxdigit_l(ch, l) ? CLASS_XDIGIT : 0;
+    flags |= islower_l(ch, l) ? CLASS_LOWER : 0;
+    flags |= isupper_l(ch, l) ? CLASS_UPPER : 0;
+
+    freelocale(l);
+    return flags;
+}
+
+/*
+ * 失敗したら -1。
+ */
+int
+toupper_byte_native(unsigned char ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+
+    const int ret = toupper_l(ch, l);
+
+    freelocale(l);
+    return ret;
+}
+
+/*
+ * 失敗したら -1。
+ */
+int
+tolower_byte_native(unsigned char ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+
+    const int ret = tolower_l(ch, l);
+
     freelocale(l);
     return ret;
 }
@@ -0,0 +1,107 @@
+#include <config.h>
+
+#include <inttypes.h>
+#include <locale.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <wchar.h>
+
+ssize_t
+mbstowcs_native(wchar_t* dest, size_t n, const char* src)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -0x1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const size_t ret = mbstowcs(dest, src, n);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret == (size_t)-1 ? -0x2 : (ssize_t)ret;
+}
+
+ssize_t
+wcstombs_native(char* dest, size_t n, const wchar_t* src)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -0x1;
+    }
+    const locale_t previous = uselocale(l);
+
+    const size_t ret = wcstombs(dest, src, n);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret == (size_t)-1 ? -0x2 : (ssize_t)ret;
+}
+
+mbstate_t*
+mbstate_new(void)
+{
+    return calloc(1, sizeof(mbstate_t));
+}
+
+void
+mbstate_free(mbstate_t* state)
+{
+    free(state);
+}
+
+void
+mbstate_reset(mbstate_t* state)
+{
+    (void)memset(state, 0, sizeof *state);
+}
+
+int8_t
+mbsinit_native(const mbstate_t* state)
+{
+    return mbsinit(state) ? 1 : 0;
+}
+
+/*
+ * 戻り値は mbrtowc() と同じく、消費したバイト数、不正なバイト列なら -0x1、
+ * 途中で途切れていれば -0x2 (n バイトとも state に取り込まれる)。
+ * NUL 文字の場合も 1 バイト消費したとして 1 を返す。
+ */
+ssize_t
+mbrtowc_native(wchar_t* wc, const char* s, size_t n, mbstate_t* state, locale_t l)
+{
+    const locale_t previous = uselocale(l);
+
+    const size_t ret = mbrtowc(wc, s, n, state);
+
+    (void)uselocale(previous);
+    if (ret == (size_t)-1) {
+        return -0x1;
+    }
+    if (ret == (size_t)-2) {
+        return -0x2;
+    }
+    return ret == 0 ? 1 : (ssize_t)ret;
+}
+
+/*
+ * dest には MB_LEN_MAX バイト以上必要。
+ * 戻り値は書き込んだバイト数、wc がロケールの文字コードで表せなければ -0x1。
+ * 失敗した場合の state は規定されていないので、呼び出し前の状態に戻す。
+ */
+ssize_t
+wcrtomb_native(char* dest, wchar_t wc, mbstate_t* state, locale_t l)
+{
+    const mbstate_t saved = *state;
+    const locale_t previous = uselocale(l);
+
+    const size_t ret = wcrtomb(dest, wc, state);
+
+    (void)uselocale(previous);
+    if (ret == (size_t)-1) {
+        *state = saved;
+        return -0x1;
+    }
+    return (ssize_t)ret;
+}
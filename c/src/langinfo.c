@@ -0,0 +1,110 @@
+#include <config.h>
+
+#include <inttypes.h>
+#include <langinfo.h>
+#include <locale.h>
+#include <string.h>
+#include <unistd.h>
+#if defined(__APPLE__) || defined(__FreeBSD__)
+#include <xlocale.h>
+#endif
+
+#include "utf8.h"
+
+ssize_t
+nl_langinfo_native(char* dest, size_t n, nl_item item)
+{
+    const locale_t l = newlocale(LC_ALL_MASK, "", 0);
+    if (!l) {
+        return -0x1;
+    }
+
+    const ssize_t ret = mbstoutf8(dest, n, l, nl_langinfo_l(item, l));
+
+    freelocale(l);
+    return ret;
+}
+
+/*
+ * ALT_DIGITS の各文字列を UTF-8 に変換し、NUL で区切って dest に書き込む。
+ * ALT_DIGITS は NUL 終端の文字列の並びで、100 個に満たなければ空文字列で終わる。
+ * 戻り値は最後の NUL を除いたバイト数で、n がそれ以下なら dest の内容は不定。
+ */
+ssize_t
+alt_digits_native(char* dest, size_t n)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK | LC_TIME_MASK, "", 0);
+    if (!l) {
+        return -0x1;
+    }
+
+    const char* s = nl_langinfo_l(ALT_DIGITS, l);
+    ssize_t ret = 0;
+    size_t offset = 0;
+    for (int i = 0; i < 100 && s[0] != '\0'; i++) {
+        char* const d = offset < n ? dest + offset : NULL;
+        const ssize_t length = mbstoutf8(d, d ? n - offset : 0, l, s);
+        if (length < 0) {
+            ret = length;
+            break;
+        }
+        ret = (ssize_t)offset + length;
+        offset += (size_t)length + 1;
+        s += strlen(s) + 1;
+    }
+
+    freelocale(l);
+    return ret;
+}
+
+#ifdef __GLIBC__
+const char*
+locale_name_native(int category, locale_t l)
+{
+    return nl_langinfo_l(_NL_LOCALE_NAME(category), l);
+}
+#elif defined(__APPLE__) || defined(__FreeBSD__)
+/* querylocale() はカテゴリではなくマスクを取る */
+const char*
+locale_name_native(int mask, locale_t l)
+{
+    return querylocale(mask, l);
+}
+#endif
+
+#ifdef __GLIBC__
+/*
+ * workday が 0 なら週の最初の日 (_NL_TIME_FIRST_WEEKDAY)、それ以外なら最初の就業日 (_NL_TIME_FIRST_WORKDAY) の
+ * 曜日を 1 (日曜日) から 7 で返す。
+ * 値は _NL_TIME_WEEK_1STDAY の日付 (YYYYMMDD) を 1 とした番号なので、その日付の曜日から求める。
+ */
+int_fast8_t
+week_day_native(int_fast8_t workday)
+{
+    const locale_t l = newlocale(LC_TIME_MASK, "", 0);
+    if (!l) {
+        return -1;
+    }
+
+    /* 値は 32 ビットで、ポインタの上位ビットは不定 */
+    const unsigned long date = (uint32_t)(uintptr_t)nl_langinfo_l(_NL_TIME_WEEK_1STDAY, l);
+    const int n = nl_langinfo_l(workday ? _NL_TIME_FIRST_WORKDAY : _NL_TIME_FIRST_WEEKDAY, l)[0];
+
+    freelocale(l);
+
+    /* Sakamoto の方法で曜日を求める (0 が日曜日) */
+    static const int offsets[] = { 0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4 };
+    unsigned long year = date / 10000;
+    const unsigned long month = date / 100 % 100;
+    const unsigned long day = date % 100;
+    if (month < 1 || month > 12) {
+        return 1;
+    }
+    if (month < 3) {
+        year--;
+    }
+    const unsigned long weekday =
+        (year + year / 4 - year / 100 + year / 400 + offsets[month - 1] + day) % 7;
+    return (int_fast8_t)((weekday + (unsigned long)(n > 0 ? n - 1 : 0)) % 7 + 1);
+}
+#endif
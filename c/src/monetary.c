@@ -0,0 +1,39 @@
+#include <config.h>
+
+#include <errno.h>
+#include <locale.h>
+#include <monetary.h>
+#include <stdlib.h>
+#include <unistd.h>
+
+#include "utf8.h"
+
+ssize_t
+strfmon_native(char* dest, size_t n, const char* format, double amount)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK | LC_MONETARY_MASK, "", 0);
+    if (!l) {
+        return -0x1;
+    }
+
+    ssize_t ret = -0x2;
+    char* buf = NULL;
+    for (size_t size = 64; size <= 0x10000; size *= 2) {
+        char* const b = realloc(buf, size);
+        if (!b) {
+            break;
+        }
+        buf = b;
+        if (strfmon_l(buf, size, l, format, amount) >= 0) {
+            ret = mbstoutf8(dest, n, l, buf);
+            break;
+        }
+        if (errno != E2BIG) {
+            break;
+        }
+    }
+
+    free(buf);
+    freelocale(l);
+    return ret;
+}
@@ -0,0 +1,74 @@
+#include <config.h>
+
+#include <limits.h>
+#include <locale.h>
+#ifdef __GLIBC__
+#include <langinfo.h>
+#endif
+
+#ifdef __GLIBC__
+/* localeconv() と同じく、最初のグループが CHAR_MAX ならグループ分けしないので空にする */
+static char*
+grouping_native(nl_item item, locale_t l)
+{
+    char* const grouping = nl_langinfo_l(item, l);
+    return grouping[0] == '\177' || grouping[0] == '\377' ? (char*)"" : grouping;
+}
+
+/* localeconv() と同じく、値がない '\377' は CHAR_MAX にする */
+static char
+char_native(nl_item item, locale_t l)
+{
+    const char c = nl_langinfo_l(item, l)[0];
+    return c == '\377' ? CHAR_MAX : c;
+}
+#endif
+
+locale_t
+localeconv_native(struct lconv* lc)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK | LC_NUMERIC_MASK | LC_MONETARY_MASK, "", 0);
+    if (!l) {
+        return 0;
+    }
+
+#ifdef __GLIBC__
+    /*
+     * localeconv() は全スレッドで共有する静的な領域に書き込むので、
+     * 同じ値を nl_langinfo_l() で l から読む。
+     * 文字列は l が解放されるまで有効
+     */
+    lc->decimal_point = nl_langinfo_l(DECIMAL_POINT, l);
+    lc->thousands_sep = nl_langinfo_l(THOUSANDS_SEP, l);
+    lc->grouping = grouping_native(GROUPING, l);
+    lc->int_curr_symbol = nl_langinfo_l(INT_CURR_SYMBOL, l);
+    lc->currency_symbol = nl_langinfo_l(CURRENCY_SYMBOL, l);
+    lc->mon_decimal_point = nl_langinfo_l(MON_DECIMAL_POINT, l);
+    lc->mon_thousands_sep = nl_langinfo_l(MON_THOUSANDS_SEP, l);
+    lc->mon_grouping = grouping_native(MON_GROUPING, l);
+    lc->positive_sign = nl_langinfo_l(POSITIVE_SIGN, l);
+    lc->negative_sign = nl_langinfo_l(NEGATIVE_SIGN, l);
+    lc->int_frac_digits = char_native(INT_FRAC_DIGITS, l);
+    lc->frac_digits = char_native(FRAC_DIGITS, l);
+    lc->p_cs_precedes = char_native(P_CS_PRECEDES, l);
+    lc->p_sep_by_space = char_native(P_SEP_BY_SPACE, l);
+    lc->n_cs_precedes = char_native(N_CS_PRECEDES, l);
+    lc->n_sep_by_space = char_native(N_SEP_BY_SPACE, l);
+    lc->p_sign_posn = char_native(P_SIGN_POSN, l);
+    lc->n_sign_posn = char_native(N_SIGN_POSN, l);
+    lc->int_p_cs_precedes = char_native(INT_P_CS_PRECEDES, l);
+    lc->int_p_sep_by_space = char_native(INT_P_SEP_BY_SPACE, l);
+    lc->int_n_cs_precedes = char_native(INT_N_CS_PRECEDES, l);
+    lc->int_n_sep_by_space = char_native(INT_N_SEP_BY_SPACE, l);
+    lc->int_p_sign_posn = char_native(INT_P_SIGN_POSN, l);
+    lc->int_n_sign_posn = char_native(INT_N_SIGN_POSN, l);
+#else
+    const locale_t previous = uselocale(l);
+
+    /* 文字列は l が解放されるまで有効 */
+    *lc = *localeconv();
+
+    (void)uselocale(previous);
+#endif
+    return l;
+}
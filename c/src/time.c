@@ -0,0 +1,55 @@
+#include <config.h>
+
+#include <locale.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "utf8.h"
+
+ssize_t
+strftime_native(char* dest, size_t n, const char* format, const struct tm* tm)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK | LC_TIME_MASK, "", 0);
+    if (!l) {
+        return -0x1;
+    }
+
+    /*
+     * strftime() はバッファが足りないときも結果が空のときも 0 を返すので、
+     * format の末尾に空白を付けて結果が空にならないようにし、後で取り除く。
+     * 上限の 0x10000 バイトまで広げても足りなければ -0x3 を返す。
+     */
+    const size_t length = strlen(format);
+    char* const f = malloc(length + 2);
+    if (!f) {
+        freelocale(l);
+        return -0x2;
+    }
+    (void)memcpy(f, format, length);
+    f[length] = ' ';
+    f[length + 1] = '\0';
+
+    ssize_t ret = -0x3;
+    char* buf = NULL;
+    for (size_t size = 64; size <= 0x10000; size *= 2) {
+        char* const b = realloc(buf, size);
+        if (!b) {
+            ret = -0x2;
+            break;
+        }
+        buf = b;
+        const size_t written = strftime_l(buf, size, f, tm, l);
+        if (written > 0) {
+            buf[written - 1] = '\0';
+            ret = mbstoutf8(dest, n, l, buf);
+            break;
+        }
+    }
+
+    free(f);
+    free(buf);
+    freelocale(l);
+    return ret;
+}
@@ -0,0 +1,82 @@
+#include <config.h>
+
+#include <stdlib.h>
+#include <string.h>
+
+#include "utf8.h"
+
+wchar_t*
+utf8towcs(const char* utf8_bytes, size_t length)
+{
+    wchar_t* wcs = malloc((length + 1) * sizeof(wchar_t));
+    if (!wcs) {
+        return NULL;
+    }
+
+    mbstate_t state;
+    (void)memset(&state, 0, sizeof state);
+    size_t n = 0;
+    while (length > 0) {
+        const size_t ret = mbrtowc(&wcs[n], utf8_bytes, length, &state);
+        if (ret == 0) {
+            break;
+        }
+        if (ret == (size_t)-1 || ret == (size_t)-2) {
+            free(wcs);
+            return NULL;
+        }
+        utf8_bytes += ret;
+        length -= ret;
+        n++;
+    }
+    wcs[n] = L'\0';
+    return wcs;
+}
+
+ssize_t
+mbstoutf8(char* dest, size_t n, locale_t l, const char* src)
+{
+    const locale_t u = utf8_locale();
+    if (!u) {
+        return -0x1;
+    }
+
+    ssize_t ret = -0x2;
+    wchar_t* ws = NULL;
+
+    const locale_t previous = uselocale(l);
+    mbstate_t state;
+    (void)memset(&state, 0, sizeof state);
+    const char* p = src;
+    const size_t wlength = mbsrtowcs(NULL, &p, 0, &state);
+    if (wlength == (size_t)-1) {
+        goto finally;
+    }
+    ws = malloc((wlength + 1) * sizeof(wchar_t));
+    if (!ws) {
+        goto finally;
+    }
+    (void)memset(&state, 0, sizeof state);
+    p = src;
+    (void)mbsrtowcs(ws, &p, wlength + 1, &state);
+
+    (void)uselocale(u);
+    (void)memset(&state, 0, sizeof state);
+    const wchar_t* wp = ws;
+    const size_t length = wcsrtombs(NULL, &wp, 0, &state);
+    if (length == (size_t)-1) {
+        goto finally;
+    }
+    if (length < n) {
+        (void)memset(&state, 0, sizeof state);
+        wp = ws;
+        (void)wcsrtombs(dest, &wp, n, &state);
+    }
+    ret = length;
+
+finally:
+    (void)uselocale(previous);
+    freelocale(u);
+    free(ws);
+    return ret;
+}
@@ -0,0 +1,33 @@
+#ifndef RUST_LOCALE_UTF8_H
+#define RUST_LOCALE_UTF8_H
+
+#include <locale.h>
+#include <stddef.h>
+#include <unistd.h>
+#include <wchar.h>
+
+static inline locale_t
+utf8_locale(void)
+{
+    locale_t l = newlocale(LC_CTYPE_MASK, "C.UTF-8", 0);
+    if (!l) {
+        l = newlocale(LC_CTYPE_MASK, "en_US.UTF-8", 0);
+    }
+    return l;
+}
+
+/*
+ * UTF-8 のバイト列を NUL 終端のワイド文字列に変換する。
+ * 戻り値は free() で解放すること。
+ * 呼び出し側で UTF-8 ロケールを uselocale() しておく必要がある。
+ */
+wchar_t* utf8towcs(const char* utf8_bytes, size_t length);
+
+/*
+ * ロケール l のマルチバイト文字列 src を UTF-8 に変換して dest に書き込む。
+ * NUL を除いたバイト数を返し、n がそれ以下なら dest には何も書き込まない。
+ * l には LC_CTYPE が含まれている必要がある。
+ */
+ssize_t mbstoutf8(char* dest, size_t n, locale_t l, const char* src);
+
+#endif
@@ -0,0 +1,53 @@
+#include <config.h>
+
+#include <locale.h>
+#include <stdlib.h>
+#include <wchar.h>
+
+#include "utf8.h"
+
+int
+wcwidth_native(wchar_t ch)
+{
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (!l) {
+        return -2;
+    }
+    const locale_t previous = uselocale(l);
+
+    const int ret = wcwidth(ch);
+
+    (void)uselocale(previous);
+    freelocale(l);
+    return ret < 0 ? -1 : ret;
+}
+
+int
+wcswidth_native(const char* utf8_bytes, size_t length)
+{
+    const locale_t u = utf8_locale();
+    if (!u) {
+        return -2;
+    }
+    const locale_t previous = uselocale(u);
+    wchar_t* const ws = utf8towcs(utf8_bytes, length);
+    (void)uselocale(previous);
+    freelocale(u);
+    if (!ws) {
+        return -2;
+    }
+
+    int ret = -2;
+    const locale_t l = newlocale(LC_CTYPE_MASK, "", 0);
+    if (l) {
+        (void)uselocale(l);
+        ret = wcswidth(ws, wcslen(ws));
+        (void)uselocale(previous);
+        freelocale(l);
+        if (ret < 0) {
+            ret = -1;
+        }
+    }
+    free(ws);
+    return ret;
+}
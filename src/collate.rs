@@ -0,0 +1,1214 @@
+//! String collation dependent on `LC_COLLATE`.
+
+use std::cmp::Ordering;
+use std::ffi::CString;
+use std::fmt;
+
+use libc::{c_char, wchar_t};
+
+use errno::errno;
+
+use crate::locale::{Category, Locale, LocaleName};
+use crate::LocaleError;
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        pub fn wcscoll_native(
+            result: *mut libc::c_int,
+            locale: libc::locale_t,
+            a: *const libc::c_char,
+            a_length: libc::size_t,
+            b: *const libc::c_char,
+            b_length: libc::size_t,
+        ) -> i8;
+        pub fn strcoll_native(
+            locale: libc::locale_t,
+            a: *const libc::c_char,
+            b: *const libc::c_char,
+        ) -> libc::c_int;
+        pub fn wcscasecmp_native(
+            result: *mut libc::c_int,
+            locale: libc::locale_t,
+            a: *const libc::c_char,
+            a_length: libc::size_t,
+            b: *const libc::c_char,
+            b_length: libc::size_t,
+        ) -> i8;
+        pub fn wcsxfrm_native(
+            dest: *mut libc::wchar_t,
+            n: libc::size_t,
+            locale: libc::locale_t,
+            s: *const libc::c_char,
+            length: libc::size_t,
+        ) -> libc::ssize_t;
+    }
+}
+
+/// Compares strings according to the collation order of a locale.
+///
+/// The locale is captured when the `Collator` is constructed, so changing the environment afterwards
+/// does not affect an existing `Collator`.
+///
+/// Strings are compared up to the first NUL character because the underlying C API uses NUL-terminated strings.
+/// Use [`Collator::try_compare`] to detect such strings instead.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::collate::Collator;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let collator = Collator::new();
+/// let mut items = vec!["z".to_string(), "\u{00E4}".to_string(), "a".to_string()];
+/// collator.sort(&mut items);
+/// assert_eq!(items, ["a", "\u{00E4}", "z"]);
+/// ```
+pub struct Collator {
+    locale: Locale,
+}
+
+impl Collator {
+    /// Creates a `Collator` for the `LC_COLLATE` and `LC_CTYPE` locale specified by the environment.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale cannot be loaded. See [`Collator::try_new`] for the fallible version.
+    pub fn new() -> Collator {
+        Collator::try_new().unwrap()
+    }
+
+    /// Fallible version of [`Collator::new`].
+    ///
+    /// # errors
+    ///
+    /// Returns an error if the locale specified by the environment cannot be loaded. See [`Locale::new`].
+    pub fn try_new() -> Result<Collator, LocaleError> {
+        let locale = Locale::new(&[Category::Collate, Category::Ctype], "")?;
+        Ok(Collator { locale })
+    }
+
+    /// Creates a `Collator` for the locale specified by the environment with its modifier replaced by `modifier`,
+    /// e.g. `"de_DE.UTF-8@collation=phonebook"` for `LC_ALL=de_DE.UTF-8` and `"collation=phonebook"`.
+    ///
+    /// If the locale with `modifier` is not available, this falls back to the locale specified by the environment,
+    /// so the `Collator` works the same as one created by [`Collator::new`].
+    /// Note that glibc selects a collation only by the locale name, and most locales have no modifiers.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale specified by the environment cannot be loaded.
+    /// See [`Collator::try_with_modifier`] for the fallible version.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use rust_locale::collate::Collator;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// let collator = Collator::with_modifier("collation=phonebook");
+    /// assert_eq!(collator.compare("\u{00E4}", "z"), Ordering::Less);
+    /// ```
+    pub fn with_modifier(modifier: &str) -> Collator {
+        Collator::try_with_modifier(modifier).unwrap()
+    }
+
+    /// Fallible version of [`Collator::with_modifier`].
+    ///
+    /// # errors
+    ///
+    /// Returns an error if the locale specified by the environment cannot be loaded. See [`Locale::new`].
+    pub fn try_with_modifier(modifier: &str) -> Result<Collator, LocaleError> {
+        if let Some(mut name) = LocaleName::parse(&env_collate_name()) {
+            name.modifier = Some(modifier.to_string()).filter(|m| !m.is_empty());
+            let name = name.to_string();
+            if let Ok(locale) = Locale::new(&[Category::Collate, Category::Ctype], &name) {
+                return Ok(Collator { locale });
+            }
+        }
+        Collator::try_new()
+    }
+
+    /// Creates a `Collator` for `locale`, independent of the environment and the global locale.
+    ///
+    /// `locale` should contain `LC_COLLATE`. `Collator` keeps its own copy of `locale`.
+    ///
+    /// # panics
+    ///
+    /// Panics if `locale` cannot be duplicated. See [`Collator::try_from_locale`] for the fallible version.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use rust_locale::collate::Collator;
+    /// use rust_locale::locale::{Category, Locale};
+    ///
+    /// let locale = Locale::new(&[Category::Collate, Category::Ctype], "en_US").unwrap();
+    /// let collator = Collator::from_locale(&locale);
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!(collator.compare("\u{00E4}", "z"), Ordering::Less);
+    /// ```
+    pub fn from_locale(locale: &Locale) -> Collator {
+        Collator::try_from_locale(locale).unwrap()
+    }
+
+    /// Fallible version of [`Collator::from_locale`].
+    ///
+    /// # errors
+    ///
+    /// Returns an error if `locale` cannot be duplicated. See [`Locale::try_clone`].
+    pub fn try_from_locale(locale: &Locale) -> Result<Collator, LocaleError> {
+        Ok(Collator {
+            locale: locale.try_clone()?,
+        })
+    }
+
+    /// Compares `a` and `b` by the collation order.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use rust_locale::collate::Collator;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!(Collator::new().compare("\u{00E4}", "z"), Ordering::Greater);
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!(Collator::new().compare("\u{00E4}", "z"), Ordering::Less);
+    /// ```
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let mut result = 0;
+        match unsafe {
+            c::wcscoll_native(
+                &mut result,
+                self.locale.as_raw(),
+                a.as_ptr() as *const c_char,
+                a.len(),
+                b.as_ptr() as *const c_char,
+                b.len(),
+            )
+        } {
+            0 => result.cmp(&0),
+            s => panic!("wcscoll_native failed. status={}, error={}", s, errno()),
+        }
+    }
+
+    /// Compares `a` and `b` by the collation order, failing if either contains a NUL character.
+    ///
+    /// [`Collator::compare`] silently ignores everything after the first NUL character,
+    /// so e.g. `"a\0b"` and `"a\0c"` are equal.
+    /// To compare strings with NUL bytes in order, pass them to [`Collator::compare_bytes`].
+    ///
+    /// # errors
+    ///
+    /// Returns [`LocaleError::EmbeddedNul`] with the position of the first NUL character in `a`, or in `b` if `a` has
+    /// none.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use rust_locale::collate::Collator;
+    /// use rust_locale::LocaleError;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// let collator = Collator::new();
+    /// assert_eq!(collator.try_compare("a", "b"), Ok(Ordering::Less));
+    /// assert_eq!(
+    ///     collator.try_compare("a\0b", "a\0c"),
+    ///     Err(LocaleError::EmbeddedNul { position: 1 })
+    /// );
+    /// ```
+    pub fn try_compare(&self, a: &str, b: &str) -> Result<Ordering, LocaleError> {
+        for s in [a, b] {
+            if let Some(position) = s.find('\0') {
+                return Err(LocaleError::EmbeddedNul { position });
+            }
+        }
+        Ok(self.compare(a, b))
+    }
+
+    /// Checks if `a` and `b` are equivalent in the collation order, i.e. [`Collator::compare`] returns `Equal`.
+    ///
+    /// Strings of different characters can be equivalent, e.g. strings which differ only after the first NUL character.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::collate::Collator;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US.UTF-8");
+    /// let collator = Collator::new();
+    /// assert!(collator.equal("a", "a"));
+    /// assert!(!collator.equal("a", "A"));
+    /// ```
+    pub fn equal(&self, a: &str, b: &str) -> bool {
+        self.compare(a, b) == Ordering::Equal
+    }
+
+    /// Compares the multibyte strings `a` and `b` in the codeset of the locale by `strcoll`.
+    ///
+    /// Unlike [`Collator::compare`], the bytes are passed to `strcoll` as they are without being converted from UTF-8,
+    /// e.g. ISO-8859-1 for `en_US`.
+    /// Since `strcoll` takes NUL-terminated strings, the parts separated by NUL bytes are compared in order,
+    /// and a string with fewer parts is less if all its parts are equal to the other's.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::collate::Collator;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// let collator = Collator::new();
+    /// let mut items: Vec<&[u8]> = vec![b"z", b"\xE4", b"a"];
+    /// items.sort_by(|a, b| collator.compare_bytes(a, b));
+    /// assert_eq!(items, [b"a", b"\xE4", b"z"]);
+    /// ```
+    pub fn compare_bytes(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let mut a_parts = a.split(|&byte| byte == 0);
+        let mut b_parts = b.split(|&byte| byte == 0);
+        loop {
+            match (a_parts.next(), b_parts.next()) {
+                (Some(a), Some(b)) => {
+                    let a = CString::new(a).unwrap();
+                    let b = CString::new(b).unwrap();
+                    let result =
+                        unsafe { c::strcoll_native(self.locale.as_raw(), a.as_ptr(), b.as_ptr()) };
+                    if result != 0 {
+                        return result.cmp(&0);
+                    }
+                }
+                (Some(_), None) => return Ordering::Greater,
+                (None, Some(_)) => return Ordering::Less,
+                (None, None) => return Ordering::Equal,
+            }
+        }
+    }
+
+    /// Compares `a` and `b` ignoring case by `wcscasecmp`.
+    ///
+    /// The characters are compared after being converted to lowercase by the `LC_CTYPE` locale,
+    /// so the order is that of the wide characters, not the collation order.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use rust_locale::collate::Collator;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!(Collator::new().compare_ignore_case("\u{00C4}", "\u{00E4}"), Ordering::Less);
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!(Collator::new().compare_ignore_case("\u{00C4}", "\u{00E4}"), Ordering::Equal);
+    /// ```
+    pub fn compare_ignore_case(&self, a: &str, b: &str) -> Ordering {
+        let mut result = 0;
+        match unsafe {
+            c::wcscasecmp_native(
+                &mut result,
+                self.locale.as_raw(),
+                a.as_ptr() as *const c_char,
+                a.len(),
+                b.as_ptr() as *const c_char,
+                b.len(),
+            )
+        } {
+            0 => result.cmp(&0),
+            s => panic!("wcscasecmp_native failed. status={}, error={}", s, errno()),
+        }
+    }
+
+    /// Transforms `s` into a collation key.
+    ///
+    /// Comparing two keys as byte slices gives the same result as [`Collator::compare`] on the original strings,
+    /// so keys can be computed once and reused when sorting many strings.
+    /// Like `compare`, only the part of `s` before the first NUL character is taken into account.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::collate::Collator;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// let collator = Collator::new();
+    /// let mut items = vec!["z", "\u{00E4}", "a"];
+    /// items.sort_by_key(|s| collator.transform(s));
+    /// assert_eq!(items, ["a", "\u{00E4}", "z"]);
+    /// ```
+    pub fn transform(&self, s: &str) -> Vec<u8> {
+        let mut buf: Vec<wchar_t> = vec![0; s.len() * 4 + 1];
+        loop {
+            let length = unsafe {
+                c::wcsxfrm_native(
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                    self.locale.as_raw(),
+                    s.as_ptr() as *const c_char,
+                    s.len(),
+                )
+            };
+            if length < 0 {
+                panic!(
+                    "wcsxfrm_native failed. status={}, error={}",
+                    length,
+                    errno()
+                );
+            }
+            let length = length as usize;
+            if length < buf.len() {
+                buf.truncate(length);
+                break;
+            }
+            buf.resize(length + 1, 0);
+        }
+        buf.iter()
+            .flat_map(|wc| (*wc as u32).to_be_bytes())
+            .collect()
+    }
+
+    /// Returns a key of `s` for grouping collation-equivalent strings, e.g. as a key of `HashMap`.
+    ///
+    /// Equal keys imply that [`Collator::compare`] returns `Equal`, e.g. for strings which differ only after the first
+    /// NUL character, where the comparison stops.
+    /// This is the same as [`Collator::transform`].
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use rust_locale::collate::Collator;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US.UTF-8");
+    /// let collator = Collator::new();
+    /// let mut groups: HashMap<Vec<u8>, Vec<&str>> = HashMap::new();
+    /// for s in ["a", "b", "a\0c"] {
+    ///     groups.entry(collator.hash_key(s)).or_default().push(s);
+    /// }
+    /// assert_eq!(groups[&collator.hash_key("a")], ["a", "a\0c"]);
+    /// ```
+    pub fn hash_key(&self, s: &str) -> Vec<u8> {
+        self.transform(s)
+    }
+
+    /// Sorts `items` in place by the collation order.
+    pub fn sort(&self, items: &mut [String]) {
+        items.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+impl Default for Collator {
+    fn default() -> Self {
+        Collator::new()
+    }
+}
+
+/// Sorts `items` in place by the collation order of the current `LC_COLLATE` locale.
+///
+/// This is a shorthand for [`Collator::sort`] with a [`Collator`] for the locale specified by the environment,
+/// except that the collation key of each string is computed only once.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::collate::sort_strings;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let mut items = vec!["z".to_string(), "\u{00E4}".to_string(), "a".to_string()];
+/// sort_strings(&mut items);
+/// assert_eq!(items, ["a", "\u{00E4}", "z"]);
+/// ```
+pub fn sort_strings(items: &mut [String]) {
+    let collator = Collator::new();
+    items.sort_by_cached_key(|s| collator.transform(s));
+}
+
+/// Transforms `s` into a collation key in the current `LC_COLLATE` locale.
+///
+/// This is a shorthand for [`Collator::transform`] with a [`Collator`] for the locale specified by the environment
+/// at the time of the call, and can be passed directly to `slice::sort_by_cached_key`.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::collate::collation_key;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let mut items = vec!["z", "\u{00E4}", "a"];
+/// items.sort_by_cached_key(|s| collation_key(s));
+/// assert_eq!(items, ["a", "\u{00E4}", "z"]);
+/// ```
+pub fn collation_key(s: &str) -> Vec<u8> {
+    Collator::new().transform(s)
+}
+
+/// A `String` ordered by the collation order, for use in `BTreeSet`, `BTreeMap` and `BinaryHeap`.
+///
+/// The collation key of the string is computed by [`Collator::transform`] when the `CollatedString` is constructed,
+/// so changing the locale afterwards does not affect the order of existing `CollatedString`s.
+/// Strings with equal keys, which are equivalent in the collation order, are ordered by their bytes
+/// so that the order is consistent with `Eq`.
+///
+/// # examples
+///
+/// ```
+/// use std::collections::BTreeSet;
+/// use rust_locale::collate::CollatedString;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let set: BTreeSet<_> = ["z", "\u{00E4}", "a"].into_iter().map(CollatedString::new).collect();
+/// let items: Vec<&str> = set.iter().map(CollatedString::as_str).collect();
+/// assert_eq!(items, ["a", "\u{00E4}", "z"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CollatedString {
+    // The fields are compared in this order by the derived `Ord`.
+    key: Vec<u8>,
+    string: String,
+}
+
+impl CollatedString {
+    /// Creates a `CollatedString` with the key in the current `LC_COLLATE` locale.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale specified by the environment cannot be loaded.
+    pub fn new(s: impl Into<String>) -> CollatedString {
+        CollatedString::with_collator(&Collator::new(), s)
+    }
+
+    /// Creates a `CollatedString` with the key of `collator`.
+    ///
+    /// This avoids loading the locale for each string when constructing many `CollatedString`s.
+    pub fn with_collator(collator: &Collator, s: impl Into<String>) -> CollatedString {
+        let string = s.into();
+        CollatedString {
+            key: collator.transform(&string),
+            string,
+        }
+    }
+
+    /// Returns the string.
+    pub fn as_str(&self) -> &str {
+        &self.string
+    }
+
+    /// Returns the collation key of the string.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Converts into the string, discarding the key.
+    pub fn into_string(self) -> String {
+        self.string
+    }
+}
+
+impl fmt::Display for CollatedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.string)
+    }
+}
+
+/// Returns the first item of `items` in the collation order of the current `LC_COLLATE` locale.
+///
+/// This compares each item once without sorting `items`.
+/// If several items are equally minimum, the first one is returned. Returns `None` if `items` is empty.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::collate::min_by_collation;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let items = vec!["z".to_string(), "\u{00E4}".to_string(), "b".to_string()];
+/// assert_eq!(min_by_collation(&items).unwrap(), "\u{00E4}");
+/// ```
+pub fn min_by_collation(items: &[String]) -> Option<&String> {
+    let collator = Collator::new();
+    items.iter().min_by(|a, b| collator.compare(a, b))
+}
+
+/// Returns the last item of `items` in the collation order of the current `LC_COLLATE` locale.
+///
+/// This compares each item once without sorting `items`.
+/// If several items are equally maximum, the last one is returned. Returns `None` if `items` is empty.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::collate::max_by_collation;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// let items = vec!["z".to_string(), "\u{00E4}".to_string(), "b".to_string()];
+/// assert_eq!(max_by_collation(&items).unwrap(), "\u{00E4}");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(max_by_collation(&items).unwrap(), "z");
+/// ```
+pub fn max_by_collation(items: &[String]) -> Option<&String> {
+    let collator = Collator::new();
+    items.iter().max_by(|a, b| collator.compare(a, b))
+}
+
+/// Returns the name of the `LC_COLLATE` locale specified by the environment as `newlocale` resolves `""`.
+fn env_collate_name() -> String {
+    ["LC_ALL", "LC_COLLATE", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| "C".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, BinaryHeap};
+
+    use super::*;
+    use crate::util::{with_env_locale, with_env_vars};
+
+    #[test]
+    fn compare() {
+        with_env_locale("POSIX", || {
+            let collator = Collator::new();
+            assert_eq!(collator.compare("a", "b"), Ordering::Less);
+            assert_eq!(collator.compare("b", "a"), Ordering::Greater);
+            assert_eq!(collator.compare("abc", "abc"), Ordering::Equal);
+            assert_eq!(collator.compare("", "a"), Ordering::Less);
+        });
+    }
+
+    #[test]
+    fn compare_i18n() {
+        with_env_locale("POSIX", || {
+            let collator = Collator::new();
+            assert_eq!(collator.compare("B", "a"), Ordering::Less);
+            assert_eq!(collator.compare("\u{00E4}", "z"), Ordering::Greater);
+        });
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            assert_eq!(collator.compare("B", "a"), Ordering::Greater);
+            assert_eq!(collator.compare("\u{00E4}", "z"), Ordering::Less);
+        });
+    }
+
+    #[test]
+    fn with_modifier() {
+        with_env_locale("en_US", || {
+            let default = Collator::new();
+            for modifier in ["collation=phonebook", "", "bogus\0"] {
+                let collator = Collator::with_modifier(modifier);
+                for (a, b) in [("B", "a"), ("\u{00E4}", "z"), ("a\u{00E4}", "ae")] {
+                    assert_eq!(collator.compare(a, b), default.compare(a, b));
+                }
+                assert_eq!(
+                    collator.transform("\u{00E4}"),
+                    default.transform("\u{00E4}")
+                );
+            }
+        });
+        with_env_locale("POSIX", || {
+            let collator = Collator::with_modifier("collation=phonebook");
+            assert_eq!(collator.compare("\u{00E4}", "z"), Ordering::Greater);
+        });
+    }
+
+    #[test]
+    fn env_collate_name() {
+        with_env_locale("en_US", || {
+            assert_eq!(super::env_collate_name(), "en_US");
+        });
+        with_env_vars(
+            &[("LC_ALL", Some("")), ("LC_COLLATE", Some("POSIX"))],
+            || {
+                assert_eq!(super::env_collate_name(), "POSIX");
+            },
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn with_modifier_special() {
+        with_env_locale("de_DE.UTF-8", || {
+            let default = Collator::new();
+            let phonebook = Collator::with_modifier("collation=phonebook");
+            // `de_DE.UTF-8@collation=phonebook` is not provided by glibc.
+            assert_eq!(default.compare("\u{00C4}rger", "Affe"), Ordering::Greater);
+            assert_eq!(
+                phonebook.compare("\u{00C4}rger", "Affe"),
+                default.compare("\u{00C4}rger", "Affe")
+            );
+            assert_eq!(phonebook.compare("\u{00E4}", "z"), Ordering::Less);
+        });
+    }
+
+    #[test]
+    fn try_compare() {
+        with_env_locale("POSIX", || {
+            let collator = Collator::new();
+            assert_eq!(collator.try_compare("a", "b"), Ok(Ordering::Less));
+            assert_eq!(collator.try_compare("", ""), Ok(Ordering::Equal));
+            assert_eq!(
+                collator.try_compare("a\0b", "a\0c"),
+                Err(LocaleError::EmbeddedNul { position: 1 })
+            );
+            assert_eq!(
+                collator.try_compare("ab", "abc\0"),
+                Err(LocaleError::EmbeddedNul { position: 3 })
+            );
+            assert_eq!(
+                collator.try_compare("\0", "a"),
+                Err(LocaleError::EmbeddedNul { position: 0 })
+            );
+            // `compare` truncates at the first NUL character.
+            assert_eq!(collator.compare("a\0b", "a\0c"), Ordering::Equal);
+        });
+    }
+
+    #[test]
+    fn try_compare_i18n() {
+        with_env_locale("POSIX", || {
+            let collator = Collator::new();
+            assert_eq!(collator.try_compare("\u{00E4}", "z"), Ok(Ordering::Greater));
+        });
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            assert_eq!(collator.try_compare("\u{00E4}", "z"), Ok(Ordering::Less));
+            assert_eq!(
+                collator.try_compare("\u{00E4}\0", "z"),
+                Err(LocaleError::EmbeddedNul { position: 2 })
+            );
+        });
+    }
+
+    #[test]
+    fn collated_string() {
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            let s = CollatedString::with_collator(&collator, "\u{00E4}");
+            assert_eq!(s.as_str(), "\u{00E4}");
+            assert_eq!(s.key(), collator.transform("\u{00E4}"));
+            assert_eq!(s.to_string(), "\u{00E4}");
+            assert_eq!(CollatedString::new("\u{00E4}"), s);
+            assert!(s < CollatedString::new("b"));
+            assert_eq!(s.into_string(), "\u{00E4}");
+
+            let mut heap: BinaryHeap<_> = ["b", "\u{00E4}", "z", "a"]
+                .into_iter()
+                .map(CollatedString::new)
+                .collect();
+            assert_eq!(heap.pop().unwrap().as_str(), "z");
+            assert_eq!(heap.pop().unwrap().as_str(), "b");
+            assert_eq!(heap.pop().unwrap().as_str(), "\u{00E4}");
+        });
+    }
+
+    #[test]
+    fn collated_string_i18n() {
+        let words = ["z", "\u{00E4}", "B", "a"];
+        with_env_locale("POSIX", || {
+            let posix: BTreeSet<_> = words.into_iter().map(CollatedString::new).collect();
+            with_env_locale("en_US", || {
+                let en_us: BTreeSet<_> = words.into_iter().map(CollatedString::new).collect();
+                // The keys are not recomputed for the new locale.
+                let posix: Vec<&str> = posix.iter().map(CollatedString::as_str).collect();
+                let en_us: Vec<&str> = en_us.iter().map(CollatedString::as_str).collect();
+                assert_eq!(posix, ["B", "a", "z", "\u{00E4}"]);
+                assert_eq!(en_us, ["a", "\u{00E4}", "B", "z"]);
+            });
+        });
+    }
+
+    #[test]
+    fn collated_string_equivalent() {
+        with_env_locale("en_US.UTF-8", || {
+            // The keys stop at the first NUL character, as the comparison does.
+            let a = CollatedString::new("x\0a");
+            let b = CollatedString::new("x\0b");
+            assert_eq!(a.key(), b.key());
+            assert_ne!(a, b);
+            assert_eq!(
+                [b.clone(), a.clone()]
+                    .into_iter()
+                    .collect::<BTreeSet<_>>()
+                    .len(),
+                2
+            );
+            assert!(a < b);
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn collated_string_special() {
+        with_env_locale("fr_FR", || {
+            let names = [
+                "Zo\u{00E9}",
+                "\u{00E9}mile",
+                "Eric",
+                "\u{00C9}lo\u{00EF}se",
+                "andr\u{00E9}",
+                "c\u{00F4}te",
+                "cote",
+                "cot\u{00E9}",
+                "c\u{00F4}t\u{00E9}",
+                "Bernard",
+            ];
+            let set: BTreeSet<_> = names.into_iter().map(CollatedString::new).collect();
+            let sorted: Vec<&str> = set.iter().map(CollatedString::as_str).collect();
+            assert_eq!(
+                sorted,
+                [
+                    "andr\u{00E9}",
+                    "Bernard",
+                    "cote",
+                    "cot\u{00E9}",
+                    "c\u{00F4}te",
+                    "c\u{00F4}t\u{00E9}",
+                    "\u{00C9}lo\u{00EF}se",
+                    "\u{00E9}mile",
+                    "Eric",
+                    "Zo\u{00E9}",
+                ]
+            );
+            let mut items: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+            Collator::new().sort(&mut items);
+            assert_eq!(items, sorted);
+        });
+    }
+
+    #[test]
+    fn compare_bytes() {
+        with_env_locale("POSIX", || {
+            let collator = Collator::new();
+            assert_eq!(collator.compare_bytes(b"a", b"b"), Ordering::Less);
+            assert_eq!(collator.compare_bytes(b"abc", b"abc"), Ordering::Equal);
+            assert_eq!(collator.compare_bytes(b"", b"a"), Ordering::Less);
+            assert_eq!(collator.compare_bytes(b"\xE4", b"z"), Ordering::Greater);
+            assert_eq!(collator.compare_bytes(b"a\0b", b"a"), Ordering::Greater);
+            assert_eq!(collator.compare_bytes(b"a\0b", b"a\0c"), Ordering::Less);
+            assert_eq!(collator.compare_bytes(b"a\0", b"a"), Ordering::Greater);
+            assert_eq!(collator.compare_bytes(b"b\0a", b"a\0b"), Ordering::Greater);
+        });
+    }
+
+    #[test]
+    fn compare_bytes_i18n() {
+        let mut items: Vec<&[u8]> = vec![b"z", b"\xE4", b"B", b"a"];
+        with_env_locale("POSIX", || {
+            let collator = Collator::new();
+            items.sort_by(|a, b| collator.compare_bytes(a, b));
+            assert_eq!(items, [&b"B"[..], b"a", b"z", b"\xE4"]);
+        });
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            items.sort_by(|a, b| collator.compare_bytes(a, b));
+            assert_eq!(items, [&b"a"[..], b"\xE4", b"B", b"z"]);
+        });
+    }
+
+    #[test]
+    fn compare_ignore_case() {
+        with_env_locale("POSIX", || {
+            let collator = Collator::new();
+            assert_eq!(
+                collator.compare_ignore_case("Hello", "hELLO"),
+                Ordering::Equal
+            );
+            assert_eq!(collator.compare_ignore_case("a", "B"), Ordering::Less);
+            assert_eq!(collator.compare_ignore_case("B", "a"), Ordering::Greater);
+            assert_eq!(collator.compare_ignore_case("", ""), Ordering::Equal);
+            assert_eq!(collator.compare_ignore_case("ab", "A"), Ordering::Greater);
+            assert_ne!(
+                collator.compare_ignore_case("\u{00C4}", "\u{00E4}"),
+                Ordering::Equal
+            );
+        });
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            assert_eq!(
+                collator.compare_ignore_case("\u{00C4}", "\u{00E4}"),
+                Ordering::Equal
+            );
+            assert_eq!(
+                collator.compare_ignore_case("Istanbul", "istanbul"),
+                Ordering::Equal
+            );
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn compare_ignore_case_special() {
+        with_env_locale("en_US", || {
+            assert_eq!(
+                Collator::new().compare_ignore_case("Istanbul", "istanbul"),
+                Ordering::Equal
+            );
+        });
+        with_env_locale("tr_TR", || {
+            let collator = Collator::new();
+            assert_ne!(
+                collator.compare_ignore_case("Istanbul", "istanbul"),
+                Ordering::Equal
+            );
+            assert_eq!(
+                collator.compare_ignore_case("Istanbul", "\u{0131}stanbul"),
+                Ordering::Equal
+            );
+            assert_eq!(
+                collator.compare_ignore_case("\u{0130}stanbul", "istanbul"),
+                Ordering::Equal
+            );
+        });
+    }
+
+    #[test]
+    fn captures_locale() {
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            with_env_locale("POSIX", || {
+                assert_eq!(collator.compare("\u{00E4}", "z"), Ordering::Less);
+            });
+        });
+    }
+
+    #[test]
+    fn try_new() {
+        with_env_locale("xx_XX.UTF-8", || {
+            assert!(matches!(
+                Collator::try_new(),
+                Err(LocaleError::LocaleNotFound { .. })
+            ));
+        });
+    }
+
+    #[test]
+    fn sort() {
+        with_env_locale("en_US", || {
+            let mut items = vec![
+                "z".to_string(),
+                "\u{00E4}".to_string(),
+                "b".to_string(),
+                "a".to_string(),
+            ];
+            Collator::new().sort(&mut items);
+            assert_eq!(items, ["a", "\u{00E4}", "b", "z"]);
+        });
+    }
+
+    #[test]
+    fn sort_strings() {
+        with_env_locale("POSIX", || {
+            let items = vec![
+                "z".to_string(),
+                "\u{00E4}".to_string(),
+                "B".to_string(),
+                "b".to_string(),
+                "a".to_string(),
+            ];
+            let mut posix = items.clone();
+            super::sort_strings(&mut posix);
+            assert_eq!(posix, ["B", "a", "b", "z", "\u{00E4}"]);
+            with_env_locale("en_US", || {
+                let mut english = items.clone();
+                super::sort_strings(&mut english);
+                let mut by_collator = items;
+                Collator::new().sort(&mut by_collator);
+                assert_eq!(english, by_collator);
+                assert_eq!(english, ["a", "\u{00E4}", "b", "B", "z"]);
+                let mut empty: Vec<String> = vec![];
+                super::sort_strings(&mut empty);
+                assert!(empty.is_empty());
+            });
+        });
+    }
+
+    #[test]
+    fn collation_key() {
+        with_env_locale("POSIX", || {
+            let posix = super::collation_key("\u{00E4}");
+            assert!(posix > super::collation_key("z"));
+            with_env_locale("en_US", || {
+                let english = super::collation_key("\u{00E4}");
+                assert!(english < super::collation_key("z"));
+                assert_eq!(english, Collator::new().transform("\u{00E4}"));
+                assert_ne!(english, posix);
+            });
+        });
+    }
+
+    #[test]
+    fn equal() {
+        with_env_locale("POSIX", || {
+            let collator = Collator::new();
+            assert!(collator.equal("", ""));
+            assert!(collator.equal("abc", "abc"));
+            assert!(!collator.equal("abc", "abd"));
+            assert!(!collator.equal("\u{0378}", "\u{0379}"));
+            assert!(collator.equal("a\0b", "a\0c"));
+        });
+    }
+
+    #[test]
+    fn equal_i18n() {
+        with_env_locale("en_US.UTF-8", || {
+            let collator = Collator::new();
+            assert!(!collator.equal("a", "A"));
+            assert!(!collator.equal("\u{00E9}", "e\u{0301}"));
+            assert!(collator.equal("x\0a", "x\0b"));
+            assert!(!collator.equal("x\0a", "y\0a"));
+        });
+    }
+
+    #[test]
+    fn hash_key() {
+        use std::collections::HashMap;
+
+        with_env_locale("en_US.UTF-8", || {
+            let collator = Collator::new();
+            assert_eq!(collator.hash_key("x\0a"), collator.hash_key("x\0b"));
+            assert_ne!(collator.hash_key("a"), collator.hash_key("A"));
+            let words = ["x\0a", "a", "x\0b", "A", "\u{00E9}", "e\u{0301}", "x"];
+            let mut groups: HashMap<Vec<u8>, Vec<&str>> = HashMap::new();
+            for s in words {
+                groups.entry(collator.hash_key(s)).or_default().push(s);
+            }
+            assert_eq!(groups.len(), 5);
+            assert_eq!(groups[&collator.hash_key("x")], ["x\0a", "x\0b", "x"]);
+            for a in words {
+                for b in words {
+                    if collator.hash_key(a) == collator.hash_key(b) {
+                        assert_eq!(collator.compare(a, b), Ordering::Equal, "{:?} {:?}", a, b);
+                    }
+                }
+            }
+        });
+        with_env_locale("POSIX", || {
+            let posix = Collator::new();
+            assert_ne!(posix.hash_key("\u{0378}"), posix.hash_key("\u{0379}"));
+        });
+    }
+
+    #[test]
+    fn min_max_by_collation() {
+        let items = vec![
+            "Zebra".to_string(),
+            "\u{00E4}pfel".to_string(),
+            "Banane".to_string(),
+        ];
+        with_env_locale("POSIX", || {
+            assert_eq!(super::min_by_collation(&items).unwrap(), "Banane");
+            assert_eq!(super::max_by_collation(&items).unwrap(), "\u{00E4}pfel");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::min_by_collation(&items).unwrap(), "\u{00E4}pfel");
+            assert_eq!(super::max_by_collation(&items).unwrap(), "Zebra");
+            assert_eq!(super::min_by_collation(&[]), None);
+            assert_eq!(super::max_by_collation(&[]), None);
+            let same = vec!["a".to_string(), "a".to_string()];
+            assert!(std::ptr::eq(
+                super::min_by_collation(&same).unwrap(),
+                &same[0]
+            ));
+            assert!(std::ptr::eq(
+                super::max_by_collation(&same).unwrap(),
+                &same[1]
+            ));
+        });
+    }
+
+    #[test]
+    fn from_locale() {
+        let locale = Locale::new(&[Category::Collate, Category::Ctype], "POSIX").unwrap();
+        let collator = Collator::from_locale(&locale);
+        drop(locale);
+        with_env_locale("en_US", || {
+            crate::locale::with_locale(Category::All, "en_US", || {
+                assert_eq!(collator.compare("\u{00E4}", "z"), Ordering::Greater);
+                let mut items = vec!["z".to_string(), "\u{00E4}".to_string(), "a".to_string()];
+                collator.sort(&mut items);
+                assert_eq!(items, ["a", "z", "\u{00E4}"]);
+                assert_eq!(Collator::new().compare("\u{00E4}", "z"), Ordering::Less);
+            });
+            assert!(Collator::try_from_locale(
+                &Locale::new(&[Category::Collate], "en_US").unwrap()
+            )
+            .is_ok());
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn from_locale_special() {
+        let german = Locale::new(&[Category::Collate, Category::Ctype], "de_DE.UTF-8").unwrap();
+        let swedish = Locale::new(&[Category::Collate, Category::Ctype], "sv_SE.UTF-8").unwrap();
+        let german = Collator::from_locale(&german);
+        let swedish = Collator::from_locale(&swedish);
+        with_env_locale("en_US", || {
+            crate::locale::with_locale(Category::All, "en_US", || {
+                let items = vec![
+                    "z".to_string(),
+                    "\u{00E4}".to_string(),
+                    "b".to_string(),
+                    "a".to_string(),
+                ];
+                let mut by_german = items.clone();
+                german.sort(&mut by_german);
+                assert_eq!(by_german, ["a", "\u{00E4}", "b", "z"]);
+                let mut by_swedish = items;
+                swedish.sort(&mut by_swedish);
+                assert_eq!(by_swedish, ["a", "b", "z", "\u{00E4}"]);
+            });
+            let handles: Vec<_> = [(german, Ordering::Less), (swedish, Ordering::Greater)]
+                .into_iter()
+                .map(|(collator, expected)| {
+                    std::thread::spawn(move || {
+                        for _ in 0..100 {
+                            assert_eq!(collator.compare("\u{00E4}", "z"), expected);
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn transform() {
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            let mut by_compare = vec![
+                "z".to_string(),
+                "\u{00E4}".to_string(),
+                "B".to_string(),
+                "a".to_string(),
+                "".to_string(),
+                "ab".to_string(),
+                "\u{00C4}b".to_string(),
+            ];
+            let mut by_key = by_compare.clone();
+            collator.sort(&mut by_compare);
+            by_key.sort_by_key(|s| collator.transform(s));
+            assert_eq!(by_key, by_compare);
+        });
+    }
+
+    #[test]
+    fn transform_empty() {
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            assert!(collator.transform("").is_empty());
+            assert!(collator.transform("") < collator.transform("a"));
+        });
+    }
+
+    #[test]
+    fn transform_nul() {
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            assert_eq!(collator.transform("a\0b"), collator.transform("a"));
+            assert_eq!(collator.compare("a\0b", "a"), Ordering::Equal);
+        });
+    }
+
+    #[test]
+    fn transform_long() {
+        with_env_locale("en_US", || {
+            let collator = Collator::new();
+            let s = "\u{00E4}".repeat(100);
+            assert_eq!(
+                collator.transform(&s).cmp(&collator.transform("\u{00E4}")),
+                collator.compare(&s, "\u{00E4}")
+            );
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn sort_special() {
+        let items = vec![
+            "z".to_string(),
+            "\u{00E4}".to_string(),
+            "b".to_string(),
+            "a".to_string(),
+        ];
+        with_env_locale("de_DE", || {
+            let mut german = items.clone();
+            Collator::new().sort(&mut german);
+            assert_eq!(german, ["a", "\u{00E4}", "b", "z"]);
+        });
+        with_env_locale("sv_SE", || {
+            let mut swedish = items;
+            Collator::new().sort(&mut swedish);
+            assert_eq!(swedish, ["a", "b", "z", "\u{00E4}"]);
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn try_clone_special() {
+        let locale = Locale::new(&[Category::Collate], "de_DE").unwrap();
+        let copy = locale.try_clone().unwrap();
+        drop(locale);
+        let collator = Collator { locale: copy };
+        assert_eq!(collator.compare("\u{00E4}", "z"), Ordering::Less);
+        assert_eq!(collator.compare("\u{00E4}", "a"), Ordering::Greater);
+    }
+
+    #[test]
+    #[ignore]
+    fn sort_strings_special() {
+        with_env_locale("de_DE", || {
+            let mut items = vec!["z".to_string(), "\u{00E4}".to_string(), "a".to_string()];
+            super::sort_strings(&mut items);
+            assert_eq!(items, ["a", "\u{00E4}", "z"]);
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn collation_key_special() {
+        with_env_locale("de_DE", || {
+            let mut items = vec!["\u{00C4}pfel", "Zebra", "Apfel"];
+            items.sort_by_cached_key(|s| super::collation_key(s));
+            assert_eq!(items, ["Apfel", "\u{00C4}pfel", "Zebra"]);
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn min_max_by_collation_special() {
+        let items = vec![
+            "Zebra".to_string(),
+            "\u{00E4}pfel".to_string(),
+            "Banane".to_string(),
+        ];
+        with_env_locale("de_DE", || {
+            assert_eq!(super::min_by_collation(&items).unwrap(), "\u{00E4}pfel");
+            assert_eq!(super::max_by_collation(&items).unwrap(), "Zebra");
+        });
+        with_env_locale("sv_SE", || {
+            assert_eq!(super::min_by_collation(&items).unwrap(), "Banane");
+            assert_eq!(super::max_by_collation(&items).unwrap(), "\u{00E4}pfel");
+        });
+    }
+}
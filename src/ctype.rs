@@ -1,7 +1,14 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use libc::{c_char, wchar_t};
 
 use errno::errno;
 
+use crate::locale::{newlocale_error, Category, Locale};
+use crate::LocaleError;
+
 mod c {
     #[allow(non_camel_case_types)]
     type wint_t = i64;
@@ -13,14 +20,712 @@ mod c {
             multibytes: *const libc::c_char,
             byte_length: libc::size_t,
         ) -> u8;
-        pub fn wctoutf8(utf8_bytes: *mut libc::c_char, wc: libc::wchar_t) -> libc::ssize_t;
         pub fn iswspace_native(ch: wint_t) -> i8;
-        pub fn iswblank_native(ch: wint_t) -> libc::c_int;
+        pub fn iswblank_native(ch: wint_t) -> i8;
+        pub fn iswalpha_native(ch: wint_t) -> i8;
+        pub fn iswdigit_native(ch: wint_t) -> i8;
+        pub fn iswalnum_native(ch: wint_t) -> i8;
+        pub fn iswpunct_native(ch: wint_t) -> i8;
+        pub fn iswcntrl_native(ch: wint_t) -> i8;
+        pub fn iswprint_native(ch: wint_t) -> i8;
+        pub fn iswgraph_native(ch: wint_t) -> i8;
+        pub fn iswxdigit_native(ch: wint_t) -> i8;
+        pub fn iswlower_native(ch: wint_t) -> i8;
+        pub fn iswupper_native(ch: wint_t) -> i8;
         pub fn towupper_native(ch: wint_t) -> wint_t;
         pub fn towlower_native(ch: wint_t) -> wint_t;
+        pub fn wctype_native(desc: *mut libc::uintptr_t, name: *const libc::c_char) -> i8;
+        pub fn iswctype_native(ch: wint_t, desc: libc::uintptr_t) -> i8;
+        pub fn wctrans_native(desc: *mut libc::uintptr_t, name: *const libc::c_char) -> i8;
+        pub fn towctrans_native(ch: wint_t, desc: libc::uintptr_t) -> wint_t;
+        pub fn classify_native(
+            flags: *mut u16,
+            utf8_bytes: *const libc::c_char,
+            length: libc::size_t,
+        ) -> i8;
+        pub fn iswspace_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswblank_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswalpha_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswdigit_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswalnum_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswpunct_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswcntrl_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswprint_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswgraph_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswxdigit_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswlower_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn iswupper_l_native(ch: wint_t, locale: libc::locale_t) -> i8;
+        pub fn towupper_l_native(ch: wint_t, locale: libc::locale_t) -> wint_t;
+        pub fn towlower_l_native(ch: wint_t, locale: libc::locale_t) -> wint_t;
+        pub fn wctype_l_native(
+            name: *const libc::c_char,
+            locale: libc::locale_t,
+        ) -> libc::uintptr_t;
+        pub fn iswctype_l_native(ch: wint_t, desc: libc::uintptr_t, locale: libc::locale_t) -> i8;
+        pub fn classify_byte_native(ch: u8) -> i32;
+        pub fn toupper_byte_native(ch: u8) -> libc::c_int;
+        pub fn tolower_byte_native(ch: u8) -> libc::c_int;
+    }
+}
+
+/// A character class defined by a locale, obtained by [`char_class`].
+///
+/// The class is looked up in the locale current at the time of [`char_class`],
+/// so it should be used while the same locale is current.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharClass(libc::uintptr_t);
+
+/// Looks up the character class named `name` in the current locale.
+///
+/// Besides the standard classes (`"alnum"`, `"alpha"`, `"blank"`, `"cntrl"`, `"digit"`, `"graph"`,
+/// `"lower"`, `"print"`, `"punct"`, `"space"`, `"upper"`, `"xdigit"`), a locale may define its own classes,
+/// e.g. `"jkata"` for katakana in Japanese locales.
+/// Returns `None` if the class is not defined in the current locale.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::char_class;
+/// use rust_locale::CType;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let alpha = char_class("alpha").unwrap();
+/// assert!('\u{00E4}'.is_class(alpha));
+/// assert!(!'1'.is_class(alpha));
+/// assert_eq!(char_class("jkata"), None);
+/// ```
+pub fn char_class(name: &str) -> Option<CharClass> {
+    let name = std::ffi::CString::new(name).ok()?;
+    let mut desc = 0;
+    match unsafe { c::wctype_native(&mut desc, name.as_ptr()) } {
+        0 if desc == 0 => None,
+        0 => Some(CharClass(desc)),
+        _ => panic!("wctype_native failed. error={}", errno()),
+    }
+}
+
+/// The standard character classes, which are defined in every locale.
+const STANDARD_CLASSES: [&str; 12] = [
+    "alnum", "alpha", "blank", "cntrl", "digit", "graph", "lower", "print", "punct", "space",
+    "upper", "xdigit",
+];
+
+/// The names of the classes defined by some locales of glibc.
+const EXTRA_CLASSES: [&str; 8] = [
+    "combining",
+    "combining_level3",
+    "hanzi",
+    "jdigit",
+    "jhira",
+    "jkanji",
+    "jkata",
+    "jspace",
+];
+
+/// Returns the names of the character classes available in the current locale.
+///
+/// The result starts with the 12 standard classes, followed by those of the classes known to be defined by some
+/// locales, e.g. `"jkata"` in Japanese locales, which are defined in the current locale.
+/// A locale may define classes that are not listed, so this is a best effort; [`char_class`] can look up any name.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::known_classes;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(known_classes().len(), 12);
+/// assert!(known_classes().contains(&"alpha"));
+/// assert!(!known_classes().contains(&"jkata"));
+/// ```
+pub fn known_classes() -> Vec<&'static str> {
+    let mut classes = STANDARD_CLASSES.to_vec();
+    classes.extend(defined_classes(&EXTRA_CLASSES));
+    classes
+}
+
+/// Returns the names in `names` which are defined as character classes in the current locale.
+fn defined_classes(names: &[&'static str]) -> Vec<&'static str> {
+    names
+        .iter()
+        .copied()
+        .filter(|name| char_class(name).is_some())
+        .collect()
+}
+
+/// Checks characters against character classes looked up by name, memoizing the lookups.
+///
+/// The `LC_CTYPE` locale is captured when the `ClassifierCache` is constructed,
+/// so changing the environment afterwards does not affect an existing `ClassifierCache`.
+/// Each class name is looked up only once, which is useful for checking many characters against a few classes.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::ClassifierCache;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let cache = ClassifierCache::new();
+/// assert!("stra\u{00DF}e".chars().all(|c| cache.is("alpha", c)));
+/// assert!(!cache.is("digit", 'a'));
+/// assert!(!cache.is("jkata", '\u{30AB}'));
+/// ```
+#[derive(Debug)]
+pub struct ClassifierCache {
+    locale: Locale,
+    classes: RefCell<HashMap<String, libc::uintptr_t>>,
+}
+
+impl ClassifierCache {
+    /// Creates a `ClassifierCache` for the `LC_CTYPE` locale specified by the environment.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale cannot be loaded. See [`ClassifierCache::try_new`] for the fallible version.
+    pub fn new() -> ClassifierCache {
+        ClassifierCache::try_new().unwrap()
+    }
+
+    /// Fallible version of [`ClassifierCache::new`].
+    ///
+    /// # errors
+    ///
+    /// Returns an error if the locale specified by the environment cannot be loaded. See [`Locale::new`].
+    pub fn try_new() -> Result<ClassifierCache, LocaleError> {
+        let locale = Locale::new(&[Category::Ctype], "")?;
+        Ok(ClassifierCache {
+            locale,
+            classes: Default::default(),
+        })
+    }
+
+    /// Checks if `c` belongs to the character class named `name`.
+    ///
+    /// Returns `false` if the class is not defined in the locale. See [`char_class`] for the class names.
+    pub fn is(&self, name: &str, c: char) -> bool {
+        let desc = self.class(name);
+        if desc == 0 {
+            return false;
+        }
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswctype_l_native(wc.into(), desc, self.locale.as_raw()) != 0 }
+    }
+
+    /// Returns the handle of the class named `name`, or 0 if it is not defined.
+    fn class(&self, name: &str) -> libc::uintptr_t {
+        if let Some(&desc) = self.classes.borrow().get(name) {
+            return desc;
+        }
+        let desc = match std::ffi::CString::new(name) {
+            Ok(name) => unsafe { c::wctype_l_native(name.as_ptr(), self.locale.as_raw()) },
+            Err(_) => 0,
+        };
+        self.classes.borrow_mut().insert(name.to_string(), desc);
+        desc
+    }
+}
+
+impl Default for ClassifierCache {
+    fn default() -> Self {
+        ClassifierCache::new()
+    }
+}
+
+/// A character mapping defined by a locale, obtained by [`char_trans`].
+///
+/// The mapping is looked up in the locale current at the time of [`char_trans`],
+/// so it should be used while the same locale is current.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharTrans(libc::uintptr_t);
+
+/// Looks up the character mapping named `name` in the current locale.
+///
+/// Besides the standard mappings (`"tolower"`, `"toupper"`), a locale may define its own mappings,
+/// e.g. `"tojhira"` and `"tojkata"` between hiragana and katakana in Japanese locales.
+/// Returns `None` if the mapping is not defined in the current locale.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::char_trans;
+/// use rust_locale::CType;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let toupper = char_trans("toupper").unwrap();
+/// assert_eq!('\u{00E4}'.transform_char(toupper), '\u{00C4}');
+/// assert_eq!(char_trans("tojkata"), None);
+/// ```
+pub fn char_trans(name: &str) -> Option<CharTrans> {
+    let name = std::ffi::CString::new(name).ok()?;
+    let mut desc = 0;
+    match unsafe { c::wctrans_native(&mut desc, name.as_ptr()) } {
+        0 if desc == 0 => None,
+        0 => Some(CharTrans(desc)),
+        _ => panic!("wctrans_native failed. error={}", errno()),
+    }
+}
+
+/// A set of the character classes a character belongs to, returned by [`classify_all`].
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::CharFlags;
+///
+/// let flags = CharFlags::ALPHA | CharFlags::UPPER;
+/// assert!(flags.contains(CharFlags::ALPHA));
+/// assert!(!flags.contains(CharFlags::ALPHA | CharFlags::DIGIT));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CharFlags(u16);
+
+impl CharFlags {
+    /// See [`CType::is_space`].
+    pub const SPACE: CharFlags = CharFlags(1 << 0);
+    /// See [`CType::is_blank`].
+    pub const BLANK: CharFlags = CharFlags(1 << 1);
+    /// See [`CType::is_alpha`].
+    pub const ALPHA: CharFlags = CharFlags(1 << 2);
+    /// See [`CType::is_digit`].
+    pub const DIGIT: CharFlags = CharFlags(1 << 3);
+    /// See [`CType::is_alnum`].
+    pub const ALNUM: CharFlags = CharFlags(1 << 4);
+    /// See [`CType::is_punct`].
+    pub const PUNCT: CharFlags = CharFlags(1 << 5);
+    /// See [`CType::is_cntrl`].
+    pub const CNTRL: CharFlags = CharFlags(1 << 6);
+    /// See [`CType::is_print`].
+    pub const PRINT: CharFlags = CharFlags(1 << 7);
+    /// See [`CType::is_graph`].
+    pub const GRAPH: CharFlags = CharFlags(1 << 8);
+    /// See [`CType::is_xdigit`].
+    pub const XDIGIT: CharFlags = CharFlags(1 << 9);
+    /// See [`CType::is_lower`].
+    pub const LOWER: CharFlags = CharFlags(1 << 10);
+    /// See [`CType::is_upper`].
+    pub const UPPER: CharFlags = CharFlags(1 << 11);
+
+    /// Returns the empty set.
+    pub const fn empty() -> CharFlags {
+        CharFlags(0)
+    }
+
+    /// Returns the raw bits of the set.
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Checks if the set contains no classes.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Checks if the set contains all the classes in `other`.
+    pub const fn contains(self, other: CharFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for CharFlags {
+    type Output = CharFlags;
+
+    fn bitor(self, rhs: CharFlags) -> CharFlags {
+        CharFlags(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for CharFlags {
+    fn bitor_assign(&mut self, rhs: CharFlags) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl std::ops::BitAnd for CharFlags {
+    type Output = CharFlags;
+
+    fn bitand(self, rhs: CharFlags) -> CharFlags {
+        CharFlags(self.0 & rhs.0)
+    }
+}
+
+/// Classifies each character of `s` in the current locale.
+///
+/// The result has one element per character of `s`.
+/// This is equivalent to calling all the predicates of [`CType`] on each character,
+/// but loads the locale only once and therefore is much faster for long strings.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::{classify_all, CharFlags};
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let flags = classify_all("a1 \u{00C4}");
+/// assert!(flags[0].contains(CharFlags::ALPHA | CharFlags::LOWER));
+/// assert!(flags[1].contains(CharFlags::DIGIT | CharFlags::XDIGIT));
+/// assert!(flags[2].contains(CharFlags::SPACE | CharFlags::BLANK));
+/// assert!(flags[3].contains(CharFlags::ALPHA | CharFlags::UPPER));
+/// ```
+pub fn classify_all(s: &str) -> Vec<CharFlags> {
+    let mut flags = vec![0; s.chars().count()];
+    match unsafe { c::classify_native(flags.as_mut_ptr(), s.as_ptr() as *const c_char, s.len()) } {
+        0 => flags.into_iter().map(CharFlags).collect(),
+        status => panic!(
+            "classify_native failed. status={}, error={}",
+            status,
+            errno()
+        ),
+    }
+}
+
+/// Returns an iterator over the characters of `s` converted to uppercase in the current locale.
+///
+/// Each character is converted by [`CType::to_uppercase`] when the iterator yields it.
+/// Unlike [`CTypeStr::to_uppercase_str`], a character is always converted to a single character.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::uppercase_chars;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(uppercase_chars("caf\u{00E9}").collect::<String>(), "CAF\u{00C9}");
+/// assert_eq!(uppercase_chars("abc").next(), Some('A'));
+/// ```
+pub fn uppercase_chars(s: &str) -> UppercaseChars<'_> {
+    UppercaseChars { chars: s.chars() }
+}
+
+/// An iterator returned by [`uppercase_chars`].
+#[derive(Debug, Clone)]
+pub struct UppercaseChars<'a> {
+    chars: std::str::Chars<'a>,
+}
+
+impl Iterator for UppercaseChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        self.chars.next().map(|c| CType::to_uppercase(&c))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chars.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for UppercaseChars<'_> {
+    fn next_back(&mut self) -> Option<char> {
+        self.chars.next_back().map(|c| CType::to_uppercase(&c))
+    }
+}
+
+impl std::iter::FusedIterator for UppercaseChars<'_> {}
+
+/// Returns an iterator over the words of `s` separated by whitespace in the current locale.
+///
+/// Unlike [`str::split_whitespace`], whitespace is determined by [`CType::is_space`] instead of Unicode.
+/// The locale is loaded when this function is called.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::split_words_locale;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(split_words_locale(" a\u{3000}b  c ").collect::<Vec<_>>(), ["a\u{3000}b", "c"]);
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert_eq!(split_words_locale(" a\u{3000}b  c ").collect::<Vec<_>>(), ["a", "b", "c"]);
+/// ```
+pub fn split_words_locale(s: &str) -> SplitWordsLocale<'_> {
+    SplitWordsLocale {
+        locale: Locale::new(&[Category::Ctype], "").unwrap(),
+        rest: s,
+    }
+}
+
+/// An iterator returned by [`split_words_locale`].
+#[derive(Debug)]
+pub struct SplitWordsLocale<'a> {
+    locale: Locale,
+    rest: &'a str,
+}
+
+impl<'a> Iterator for SplitWordsLocale<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let locale = &self.locale;
+        let rest = self.rest.trim_start_matches(|c| locale.is_space(c));
+        if rest.is_empty() {
+            self.rest = rest;
+            return None;
+        }
+        let end = rest.find(|c| locale.is_space(c)).unwrap_or(rest.len());
+        let (word, rest) = rest.split_at(end);
+        self.rest = rest;
+        Some(word)
+    }
+}
+
+impl std::iter::FusedIterator for SplitWordsLocale<'_> {}
+
+/// Returns an iterator over the characters of `s` which belong to the character class named `name` in the current
+/// locale.
+///
+/// The locale is loaded and the class is looked up once when this function is called.
+/// See [`char_class`] for the class names. If the class is not defined in the locale, the iterator is empty.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::chars_with_class;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(chars_with_class("a1, b2; c3.", "digit").collect::<String>(), "123");
+/// assert_eq!(chars_with_class("a1, b2; c3.", "punct").collect::<String>(), ",;.");
+/// assert_eq!(chars_with_class("a1, b2; c3.", "bogus").next(), None);
+/// ```
+pub fn chars_with_class<'a>(s: &'a str, name: &str) -> CharsWithClass<'a> {
+    let locale = Locale::new(&[Category::Ctype], "").unwrap();
+    let desc = match std::ffi::CString::new(name) {
+        Ok(name) => unsafe { c::wctype_l_native(name.as_ptr(), locale.as_raw()) },
+        Err(_) => 0,
+    };
+    CharsWithClass {
+        locale,
+        desc,
+        chars: if desc == 0 { "" } else { s }.chars(),
+    }
+}
+
+/// An iterator returned by [`chars_with_class`].
+#[derive(Debug)]
+pub struct CharsWithClass<'a> {
+    locale: Locale,
+    desc: libc::uintptr_t,
+    chars: std::str::Chars<'a>,
+}
+
+impl Iterator for CharsWithClass<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let (desc, locale) = (self.desc, &self.locale);
+        self.chars.find(|c| {
+            let wc = utf8towc(&utf8_bytes(c)).unwrap();
+            unsafe { c::iswctype_l_native(wc.into(), desc, locale.as_raw()) != 0 }
+        })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.chars.size_hint().1)
+    }
+}
+
+impl std::iter::FusedIterator for CharsWithClass<'_> {}
+
+/// Checks if all characters of `s` are digits in the current locale as [`CType::is_digit`].
+///
+/// Returns `true` for an empty string, like [`Iterator::all`].
+/// Stops at the first character which is not a digit.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::all_digits;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert!(all_digits("12345"));
+/// assert!(!all_digits("12a45"));
+/// assert!(all_digits(""));
+/// ```
+pub fn all_digits(s: &str) -> bool {
+    s.chars().all(|c| CType::is_digit(&c))
+}
+
+/// Checks if all characters of `s` are alphabetic in the current locale as [`CType::is_alpha`].
+///
+/// Returns `true` for an empty string, like [`Iterator::all`].
+/// Stops at the first character which is not alphabetic.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::all_alpha;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert!(!all_alpha("stra\u{00DF}e"));
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert!(all_alpha("stra\u{00DF}e"));
+/// assert!(all_alpha(""));
+/// ```
+pub fn all_alpha(s: &str) -> bool {
+    s.chars().all(|c| c.is_alpha())
+}
+
+/// Returns the characters of the BMP (U+0000 to U+FFFF) which are whitespace in the current locale as [`CType::is_space`].
+///
+/// The characters are classified at once by [`classify_all`], so this is much faster than calling `is_space` on each
+/// character.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::space_chars;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(space_chars(), ['\t', '\n', '\u{0B}', '\u{0C}', '\r', ' ']);
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert!(space_chars().contains(&'\u{2003}'));
+/// ```
+pub fn space_chars() -> Vec<char> {
+    bmp_chars(CharFlags::SPACE)
+}
+
+/// Returns the characters of the BMP (U+0000 to U+FFFF) which are punctuation in the current locale as
+/// [`CType::is_punct`].
+///
+/// The characters are classified at once by [`classify_all`], so this is much faster than calling `is_punct` on each
+/// character.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::punct_chars;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(punct_chars().len(), 32);
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert!(punct_chars().contains(&'\u{00A7}'));
+/// ```
+pub fn punct_chars() -> Vec<char> {
+    bmp_chars(CharFlags::PUNCT)
+}
+
+/// Returns the characters of the BMP which belong to `class` in the current locale.
+fn bmp_chars(class: CharFlags) -> Vec<char> {
+    let chars: String = ('\0'..='\u{FFFF}').collect();
+    chars
+        .chars()
+        .zip(classify_all(&chars))
+        .filter(|(_, flags)| flags.contains(class))
+        .map(|(c, _)| c)
+        .collect()
+}
+
+static ASCII_FAST_PATH: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the ASCII fast path of the predicates of [`CType`] for `char`.
+///
+/// While enabled, [`CType::is_space`], [`CType::is_alpha`] and the other predicates
+/// backed by single-byte C functions (`isspace`, `isalpha`, ...) classify ASCII characters
+/// without converting them to wide characters, and return `false` for all non-ASCII characters.
+/// Enable it only if the data is known to be ASCII-only.
+/// The setting is shared by all threads and is disabled by default.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::set_ascii_fast_path;
+/// use rust_locale::CType;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// set_ascii_fast_path(true);
+/// assert!('a'.is_alpha());
+/// assert!(!'\u{00E4}'.is_alpha());
+/// set_ascii_fast_path(false);
+/// assert!('\u{00E4}'.is_alpha());
+/// ```
+pub fn set_ascii_fast_path(enabled: bool) {
+    ASCII_FAST_PATH.store(enabled, Ordering::Relaxed);
 }
 
+/// Classifies `c` by the single-byte function `f` if the ASCII fast path is enabled.
+fn ascii_fast_path(c: char, f: unsafe extern "C" fn(libc::c_int) -> libc::c_int) -> Option<bool> {
+    if ASCII_FAST_PATH.load(Ordering::Relaxed) {
+        Some(c.is_ascii() && unsafe { f(c as libc::c_int) } != 0)
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if the `LC_CTYPE` locale specified by the environment is `C` or `POSIX`.
+///
+/// The variables are looked up in the same order as `newlocale`, and the locale is `C` if none is set.
+/// The classes and the mappings of the `C` locale contain only ASCII characters,
+/// so this lets a non-ASCII character skip loading the locale.
+/// Looking up the environment is slower than the single-byte C functions, so check ASCII characters by them first.
+fn env_ctype_is_posix() -> bool {
+    let name = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .find(|value| !value.is_empty());
+    match name {
+        Some(name) => name == "C" || name == "POSIX",
+        None => true,
+    }
+}
+
+/// Returns `true` if `c` is one of the whitespace characters of the `C` locale, which are whitespace in every locale.
+///
+/// Unlike `char::is_ascii_whitespace`, this is true for vertical tab.
+fn is_c_space(c: &char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0b' | '\x0c' | '\r')
+}
+
+thread_local! {
+    /// The `LC_CTYPE` locales loaded by name, used by [`CType::is_space_in`].
+    static NAMED_LOCALES: RefCell<HashMap<String, Locale>> = RefCell::new(HashMap::new());
+}
+
+/// Calls `f` with the `LC_CTYPE` locale `name`, loading it if not cached yet.
+fn with_named_locale<T>(name: &str, f: impl FnOnce(&Locale) -> T) -> T {
+    NAMED_LOCALES.with(|locales| {
+        let mut locales = locales.borrow_mut();
+        if !locales.contains_key(name) {
+            let locale = Locale::new(&[Category::Ctype], name).unwrap();
+            locales.insert(name.to_string(), locale);
+        }
+        f(&locales[name])
+    })
+}
+
+/// `WEOF` truncated to `wchar_t`, returned by the `tow*_native` functions on failure.
+const WEOF: wchar_t = -1;
+
 pub trait CType {
     /// Returns `true` if `self` is a whitespace character.
     ///
@@ -43,6 +748,44 @@ pub trait CType {
     /// ```
     fn is_space(&self) -> bool;
 
+    /// Fallible version of [`CType::is_space`].
+    ///
+    /// # errors
+    ///
+    /// Returns [`LocaleError`] if `self` cannot be converted to a wide character
+    /// or the current locale cannot be loaded.
+    /// This never fails for the whitespace characters listed in [`CType::is_space`], which are checked first.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!('\u{2003}'.try_is_space(), Ok(true));
+    /// ```
+    fn try_is_space(&self) -> Result<bool, LocaleError>;
+
+    /// Checks if `self` is a whitespace character in the locale `locale`, e.g. `"en_US.UTF-8"`.
+    ///
+    /// Unlike [`CType::is_space`], neither the environment nor the global locale is used,
+    /// so this can be called from multiple threads with different locales.
+    /// The loaded locale is cached per thread for the following calls with the same name.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale `locale` cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!(!'\u{2003}'.is_space_in("POSIX"));
+    /// assert!('\u{2003}'.is_space_in("en_US.UTF-8"));
+    /// ```
+    fn is_space_in(&self, locale: &str) -> bool;
+
     /// Checks if `self` is classified as blank character (that is, a whitespace character used to separate words in a sentence) by the current locale.
     ///
     /// # examples
@@ -59,234 +802,3151 @@ pub trait CType {
     /// ```
     fn is_blank(&self) -> bool;
 
-    /// Converts `self` to uppercase listed in the current locale.
+    /// Checks if `self` is an alphabetic character in the current locale.
     ///
-    /// If no uppercase version is listed in the current locale, returns unmodified `self`.
+    /// # examples
     ///
-    /// Only 1:1 character mapping can be performed by this function, e.g. the uppercase form of 'ß' is (with some exceptions)
-    /// the two-character string "SS", which cannot be obtained.
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!('a'.is_alpha());
+    /// assert!(!'1'.is_alpha());
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!'\u{00E9}'.is_alpha());
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!('\u{00E9}'.is_alpha());
+    /// ```
+    fn is_alpha(&self) -> bool;
+
+    /// Checks if `self` is a decimal digit character in the current locale.
+    ///
+    /// POSIX allows only `'0'` to `'9'` in the digit class, so other numeric characters such as
+    /// fullwidth digits are not digits even though a locale may classify them as alphabetic.
     ///
     /// # examples
     ///
     /// ```
     /// use rust_locale::CType;
     ///
-    /// assert_eq!(CType::to_uppercase(&'a'), 'A');
-    /// assert_eq!(CType::to_uppercase(&'1'), '1');
-    /// std::env::set_var("LC_ALL", "POSIX");
-    /// assert_eq!(CType::to_uppercase(&'\u{017F}'), '\u{017F}');
+    /// assert!(CType::is_digit(&'5'));
+    /// assert!(!CType::is_digit(&'a'));
     /// std::env::set_var("LC_ALL", "en_US");
-    /// assert_eq!(CType::to_uppercase(&'\u{017F}'), 'S');
+    /// assert!(!CType::is_digit(&'\u{FF15}'));
     /// ```
-    fn to_uppercase(&self) -> Self;
+    fn is_digit(&self) -> bool;
 
-    /// Converts `self` to lowercase, if possible.
+    /// Returns the value of `self` if it is a decimal digit character in the current locale.
     ///
-    /// If no lowercase version is listed in the current locale, returns unmodified `self`.
+    /// Besides `'0'` to `'9'`, fullwidth digits are recognized if the current locale classifies them as `digit`
+    /// or `jdigit`, e.g. in Japanese locales.
     ///
-    /// Only 1:1 character mapping can be performed by this function, e.g. the Greek uppercase letter 'Σ' has two lowercase forms,
-    /// depending on the position in a word: 'σ' and 'ς'. A call to this method cannot be used to obtain the correct lowercase form in this case.
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert_eq!('7'.digit_value(), Some(7));
+    /// assert_eq!('a'.digit_value(), None);
+    /// ```
+    fn digit_value(&self) -> Option<u32>;
+
+    /// Checks if `self` is an alphanumeric character (that is, an alphabetic character or a decimal digit) in the current locale.
+    ///
+    /// This is a single check, not a combination of [`CType::is_alpha`] and [`CType::is_digit`].
     ///
     /// # examples
     ///
     /// ```
     /// use rust_locale::CType;
     ///
-    /// assert_eq!(CType::to_lowercase(&'A'), 'a');
-    /// assert_eq!(CType::to_lowercase(&'1'), '1');
+    /// assert!('a'.is_alnum());
+    /// assert!('1'.is_alnum());
+    /// assert!(!' '.is_alnum());
     /// std::env::set_var("LC_ALL", "POSIX");
-    /// assert_eq!(CType::to_lowercase(&'\u{0190}'), '\u{0190}');
+    /// assert!(!'\u{00E9}'.is_alnum());
     /// std::env::set_var("LC_ALL", "en_US");
-    /// assert_eq!(CType::to_lowercase(&'\u{0190}'), '\u{025b}');
+    /// assert!('\u{00E9}'.is_alnum());
     /// ```
-    fn to_lowercase(&self) -> Self;
-}
-
-impl CType for char {
-    fn is_space(&self) -> bool {
-        let buf = utf8_bytes(self);
-        if buf.len() == 1 {
-            unsafe { libc::isspace(buf[0].into()) != 0 }
-        } else {
-            let wc = utf8towc(&buf);
-            isspace(wc)
-        }
-    }
+    fn is_alnum(&self) -> bool;
 
-    fn is_blank(&self) -> bool {
-        let buf = utf8_bytes(self);
-        if buf.len() == 1 {
-            unsafe { libc::isblank(buf[0].into()) != 0 }
-        } else {
-            let wc = utf8towc(&buf);
-            isblank(wc)
-        }
-    }
+    /// Checks if `self` is a punctuation character in the current locale.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!('!'.is_punct());
+    /// assert!(!'a'.is_punct());
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!'\u{3002}'.is_punct());
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!('\u{3002}'.is_punct());
+    /// ```
+    fn is_punct(&self) -> bool;
 
-    fn to_uppercase(&self) -> char {
-        let bytes = utf8_bytes(self);
-        let wc = utf8towc(&bytes);
-        let upper = toupper(wc);
-        wctochar(upper)
-    }
+    /// Checks if `self` is a control character in the current locale.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!('\x07'.is_cntrl());
+    /// assert!(!'a'.is_cntrl());
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!'\u{0085}'.is_cntrl());
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!('\u{0085}'.is_cntrl());
+    /// ```
+    fn is_cntrl(&self) -> bool;
 
-    fn to_lowercase(&self) -> char {
-        let bytes = utf8_bytes(self);
-        let wc = utf8towc(&bytes);
-        let lower = tolower(wc);
-        wctochar(lower)
-    }
-}
+    /// Checks if `self` is a printable character, including space, in the current locale.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!('a'.is_print());
+    /// assert!(' '.is_print());
+    /// assert!(!'\n'.is_print());
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!'\u{00A0}'.is_print());
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!('\u{00A0}'.is_print());
+    /// ```
+    fn is_print(&self) -> bool;
 
-fn utf8_bytes(c: &char) -> Vec<u8> {
-    let length = c.len_utf8();
-    let mut buf = vec![0; length];
-    c.encode_utf8(&mut buf);
-    buf
-}
+    /// Checks if `self` is a printable character, excluding space, in the current locale.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!('a'.is_graph());
+    /// assert!(!' '.is_graph());
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!'\u{00A0}'.is_graph());
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!('\u{00A0}'.is_graph());
+    /// ```
+    fn is_graph(&self) -> bool;
 
-fn utf8towc(utf8_bytes: &Vec<u8>) -> wchar_t {
-    let mut wc = 0;
-    match unsafe {
-        c::utf8towc(
-            &mut wc as *mut wchar_t,
-            utf8_bytes.as_ptr() as *const c_char,
+    /// Checks if `self` is a hexadecimal digit character in the current locale.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!('9'.is_xdigit());
+    /// assert!('a'.is_xdigit());
+    /// assert!('F'.is_xdigit());
+    /// assert!(!'g'.is_xdigit());
+    /// ```
+    fn is_xdigit(&self) -> bool;
+
+    /// Checks if `self` is a lowercase letter in the current locale.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!('a'.is_lower());
+    /// assert!(!'A'.is_lower());
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!'\u{0131}'.is_lower());
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!('\u{0131}'.is_lower());
+    /// ```
+    fn is_lower(&self) -> bool;
+
+    /// Checks if `self` is an uppercase letter in the current locale.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!('A'.is_upper());
+    /// assert!(!'a'.is_upper());
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!'\u{0130}'.is_upper());
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!('\u{0130}'.is_upper());
+    /// ```
+    fn is_upper(&self) -> bool;
+
+    /// Checks if `self` belongs to `class` in the current locale.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::ctype::char_class;
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!'\u{00E4}'.is_class(char_class("alpha").unwrap()));
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!('\u{00E4}'.is_class(char_class("alpha").unwrap()));
+    /// ```
+    fn is_class(&self, class: CharClass) -> bool;
+
+    /// Returns `true` if `self` is an ideograph (kanji) in the current locale.
+    ///
+    /// This checks the class `"jkanji"` defined by Japanese locales.
+    /// Returns `false` if the current locale does not define the class.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale specified by the environment cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US.UTF-8");
+    /// assert!(!'\u{6F22}'.is_ideographic());
+    /// ```
+    fn is_ideographic(&self) -> bool;
+
+    /// Returns `true` if `self` is a kana (hiragana or katakana) in the current locale.
+    ///
+    /// This checks the classes `"jhira"` and `"jkata"` defined by Japanese locales.
+    /// Returns `false` if the current locale defines neither of the classes.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale specified by the environment cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US.UTF-8");
+    /// assert!(!'\u{3042}'.is_kana());
+    /// ```
+    fn is_kana(&self) -> bool;
+
+    /// Maps `self` by `trans` in the current locale.
+    ///
+    /// If `trans` lists no mapping for `self`, returns unmodified `self`.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::ctype::char_trans;
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!('\u{00E4}'.transform_char(char_trans("toupper").unwrap()), '\u{00E4}');
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!('\u{00E4}'.transform_char(char_trans("toupper").unwrap()), '\u{00C4}');
+    /// ```
+    fn transform_char(&self, trans: CharTrans) -> Self;
+
+    /// Converts `self` to uppercase listed in the current locale.
+    ///
+    /// If no uppercase version is listed in the current locale, returns unmodified `self`.
+    ///
+    /// Only 1:1 character mapping can be performed by this function, e.g. the uppercase form of 'ß' is (with some exceptions)
+    /// the two-character string "SS", which cannot be obtained. See [`CType::to_uppercase_expanded`] for that.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert_eq!(CType::to_uppercase(&'a'), 'A');
+    /// assert_eq!(CType::to_uppercase(&'1'), '1');
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!(CType::to_uppercase(&'\u{017F}'), '\u{017F}');
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!(CType::to_uppercase(&'\u{017F}'), 'S');
+    /// ```
+    fn to_uppercase(&self) -> Self;
+
+    /// Fallible version of [`CType::to_uppercase`].
+    ///
+    /// # errors
+    ///
+    /// Returns [`LocaleError`] if `self` cannot be converted to a wide character and back
+    /// or the current locale cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!('\u{017F}'.try_to_uppercase(), Ok('S'));
+    /// ```
+    fn try_to_uppercase(&self) -> Result<Self, LocaleError>
+    where
+        Self: Sized;
+
+    /// Converts `self` to uppercase in the current locale, allowing it to expand to multiple characters.
+    ///
+    /// This is the same as [`CTypeStr::to_uppercase_str`] on the string consisting of `self`:
+    /// if the current locale lists no uppercase version of a lowercase letter whose uppercase form
+    /// consists of multiple characters in Unicode, e.g. 'ß', that form is used.
+    /// Otherwise the result is the single character [`CType::to_uppercase`] returns.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale specified by the environment cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!('\u{00DF}'.to_uppercase_expanded(), "\u{00DF}");
+    /// std::env::set_var("LC_ALL", "en_US.UTF-8");
+    /// assert_eq!('\u{00DF}'.to_uppercase_expanded(), "SS");
+    /// assert_eq!('a'.to_uppercase_expanded(), "A");
+    /// ```
+    fn to_uppercase_expanded(&self) -> String;
+
+    /// Converts `self` to lowercase, if possible.
+    ///
+    /// If no lowercase version is listed in the current locale, returns unmodified `self`.
+    ///
+    /// Only 1:1 character mapping can be performed by this function, e.g. the Greek uppercase letter 'Σ' has two lowercase forms,
+    /// depending on the position in a word: 'σ' and 'ς'. A call to this method cannot be used to obtain the correct lowercase form in this case.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert_eq!(CType::to_lowercase(&'A'), 'a');
+    /// assert_eq!(CType::to_lowercase(&'1'), '1');
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!(CType::to_lowercase(&'\u{0190}'), '\u{0190}');
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!(CType::to_lowercase(&'\u{0190}'), '\u{025b}');
+    /// ```
+    fn to_lowercase(&self) -> Self;
+
+    /// Fallible version of [`CType::to_lowercase`].
+    ///
+    /// # errors
+    ///
+    /// Returns [`LocaleError`] if `self` cannot be converted to a wide character and back
+    /// or the current locale cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!('\u{0190}'.try_to_lowercase(), Ok('\u{025B}'));
+    /// ```
+    fn try_to_lowercase(&self) -> Result<Self, LocaleError>
+    where
+        Self: Sized;
+
+    /// Converts `self` to titlecase, if possible.
+    ///
+    /// The titlecase form equals the uppercase form for most characters, but differs for digraphs,
+    /// e.g. the titlecase form of 'ǆ' is 'ǅ' while the uppercase form is 'Ǆ'.
+    /// If the current locale defines no `"totitle"` mapping, this is the same as [`CType::to_uppercase`].
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert_eq!('a'.to_titlecase(), 'A');
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!('\u{01C6}'.to_titlecase(), '\u{01C6}');
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!('\u{01C6}'.to_titlecase(), '\u{01C5}');
+    /// ```
+    fn to_titlecase(&self) -> Self;
+
+    /// Compares `self` and `other` by the collation order of the current `LC_COLLATE` locale.
+    ///
+    /// Same as [`Collator::compare`](crate::Collator::compare) on the strings consisting of the characters.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale specified by the environment cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use rust_locale::CType;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!('\u{00E4}'.collate_cmp(&'z'), Ordering::Greater);
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!('\u{00E4}'.collate_cmp(&'z'), Ordering::Less);
+    /// ```
+    fn collate_cmp(&self, other: &Self) -> std::cmp::Ordering;
+}
+
+impl CType for char {
+    fn is_space(&self) -> bool {
+        self.try_is_space().unwrap()
+    }
+
+    fn try_is_space(&self) -> Result<bool, LocaleError> {
+        if is_c_space(self) {
+            return Ok(true);
+        }
+        if let Some(result) = ascii_fast_path(*self, libc::isspace) {
+            return Ok(result);
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            Ok(unsafe { libc::isspace(buf[0].into()) != 0 })
+        } else if env_ctype_is_posix() {
+            Ok(false)
+        } else {
+            let wc = utf8towc(&buf)?;
+            isspace(wc)
+        }
+    }
+
+    fn is_space_in(&self, locale: &str) -> bool {
+        with_named_locale(locale, |locale| locale.is_space(*self))
+    }
+
+    fn is_blank(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::isblank) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::isblank(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            isblank(wc)
+        }
+    }
+
+    fn is_alpha(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::isalpha) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::isalpha(buf[0].into()) != 0 }
+        } else if env_ctype_is_posix() {
+            false
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            isalpha(wc)
+        }
+    }
+
+    fn is_digit(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::isdigit) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::isdigit(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            isdigit(wc)
+        }
+    }
+
+    fn digit_value(&self) -> Option<u32> {
+        match *self {
+            '0'..='9' if CType::is_digit(self) => self.to_digit(10),
+            // glibc puts fullwidth digits only in the "jdigit" class of Japanese locales, not in "digit".
+            '\u{FF10}'..='\u{FF19}'
+                if CType::is_digit(self)
+                    || matches!(char_class("jdigit"), Some(jdigit) if self.is_class(jdigit)) =>
+            {
+                Some(*self as u32 - 0xFF10)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_alnum(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::isalnum) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::isalnum(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            isalnum(wc)
+        }
+    }
+
+    fn is_punct(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::ispunct) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::ispunct(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            ispunct(wc)
+        }
+    }
+
+    fn is_cntrl(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::iscntrl) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::iscntrl(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            iscntrl(wc)
+        }
+    }
+
+    fn is_print(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::isprint) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::isprint(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            isprint(wc)
+        }
+    }
+
+    fn is_graph(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::isgraph) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::isgraph(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            isgraph(wc)
+        }
+    }
+
+    fn is_xdigit(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::isxdigit) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::isxdigit(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            isxdigit(wc)
+        }
+    }
+
+    fn is_lower(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::islower) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::islower(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            islower(wc)
+        }
+    }
+
+    fn is_upper(&self) -> bool {
+        if let Some(result) = ascii_fast_path(*self, libc::isupper) {
+            return result;
+        }
+        let buf = utf8_bytes(self);
+        if buf.len() == 1 {
+            unsafe { libc::isupper(buf[0].into()) != 0 }
+        } else {
+            let wc = utf8towc(&buf).unwrap();
+            isupper(wc)
+        }
+    }
+
+    fn is_class(&self, class: CharClass) -> bool {
+        let wc = utf8towc(&utf8_bytes(self)).unwrap();
+        match unsafe { c::iswctype_native(wc.into(), class.0) } {
+            s if s >= 0 => s != 0,
+            _ => panic!("iswctype_native failed. error={}", errno()),
+        }
+    }
+
+    fn is_ideographic(&self) -> bool {
+        matches!(char_class("jkanji"), Some(class) if self.is_class(class))
+    }
+
+    fn is_kana(&self) -> bool {
+        ["jhira", "jkata"]
+            .iter()
+            .any(|name| matches!(char_class(name), Some(class) if self.is_class(class)))
+    }
+
+    fn transform_char(&self, trans: CharTrans) -> char {
+        let wc = utf8towc(&utf8_bytes(self)).unwrap();
+        match unsafe { c::towctrans_native(wc.into(), trans.0) as wchar_t } {
+            WEOF => panic!("towctrans_native failed. error={}", errno()),
+            mapped => wctochar(mapped).unwrap(),
+        }
+    }
+
+    fn to_uppercase(&self) -> char {
+        self.try_to_uppercase().unwrap()
+    }
+
+    fn try_to_uppercase(&self) -> Result<char, LocaleError> {
+        if !self.is_ascii() && env_ctype_is_posix() {
+            return Ok(*self);
+        }
+        let bytes = utf8_bytes(self);
+        let wc = utf8towc(&bytes)?;
+        let upper = toupper(wc)?;
+        wctochar(upper)
+    }
+
+    fn to_uppercase_expanded(&self) -> String {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        let mut upper = String::new();
+        push_uppercase(&locale, *self, &mut upper);
+        upper
+    }
+
+    fn to_lowercase(&self) -> char {
+        self.try_to_lowercase().unwrap()
+    }
+
+    fn try_to_lowercase(&self) -> Result<char, LocaleError> {
+        let bytes = utf8_bytes(self);
+        let wc = utf8towc(&bytes)?;
+        let lower = tolower(wc)?;
+        wctochar(lower)
+    }
+
+    fn to_titlecase(&self) -> char {
+        match char_trans("totitle") {
+            Some(trans) => self.transform_char(trans),
+            None => CType::to_uppercase(self),
+        }
+    }
+
+    fn collate_cmp(&self, other: &char) -> std::cmp::Ordering {
+        let mut a = [0; 4];
+        let mut b = [0; 4];
+        crate::Collator::new().compare(self.encode_utf8(&mut a), other.encode_utf8(&mut b))
+    }
+}
+
+/// Locale-dependent operations on single bytes.
+///
+/// The bytes are classified by the C `is*` functions in the codeset of the current locale, without the conversion to wide
+/// characters, e.g. `0xE9` is 'é' in a Latin-1 locale, but is not a character by itself in a UTF-8 locale.
+/// This is suitable for processing ASCII or Latin-1 byte buffers.
+///
+/// # panics
+///
+/// All the functions panic if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::ctype::ByteCType;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert!(!0xE9u8.is_alpha());
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert!(0xE9u8.is_alpha());
+/// assert_eq!(0xE9u8.to_uppercase(), 0xC9);
+/// ```
+pub trait ByteCType {
+    /// Returns `true` if `self` is a whitespace character. See [`CType::is_space`].
+    fn is_space(&self) -> bool;
+
+    /// Returns `true` if `self` is a blank character. See [`CType::is_blank`].
+    fn is_blank(&self) -> bool;
+
+    /// Returns `true` if `self` is an alphabetic character. See [`CType::is_alpha`].
+    fn is_alpha(&self) -> bool;
+
+    /// Returns `true` if `self` is a decimal digit. See [`CType::is_digit`].
+    fn is_digit(&self) -> bool;
+
+    /// Returns `true` if `self` is an alphanumeric character. See [`CType::is_alnum`].
+    fn is_alnum(&self) -> bool;
+
+    /// Returns `true` if `self` is a punctuation character. See [`CType::is_punct`].
+    fn is_punct(&self) -> bool;
+
+    /// Returns `true` if `self` is a control character. See [`CType::is_cntrl`].
+    fn is_cntrl(&self) -> bool;
+
+    /// Returns `true` if `self` is a printable character. See [`CType::is_print`].
+    fn is_print(&self) -> bool;
+
+    /// Returns `true` if `self` has a graphical representation. See [`CType::is_graph`].
+    fn is_graph(&self) -> bool;
+
+    /// Returns `true` if `self` is a hexadecimal digit. See [`CType::is_xdigit`].
+    fn is_xdigit(&self) -> bool;
+
+    /// Returns `true` if `self` is a lowercase letter. See [`CType::is_lower`].
+    fn is_lower(&self) -> bool;
+
+    /// Returns `true` if `self` is an uppercase letter. See [`CType::is_upper`].
+    fn is_upper(&self) -> bool;
+
+    /// Returns all the classes `self` belongs to.
+    fn classify(&self) -> CharFlags;
+
+    /// Converts `self` to uppercase listed in the current locale.
+    ///
+    /// If no uppercase version is listed in the current locale, or it is not a single byte, returns unmodified `self`.
+    fn to_uppercase(&self) -> Self;
+
+    /// Converts `self` to lowercase listed in the current locale.
+    ///
+    /// If no lowercase version is listed in the current locale, or it is not a single byte, returns unmodified `self`.
+    fn to_lowercase(&self) -> Self;
+}
+
+impl ByteCType for u8 {
+    fn is_space(&self) -> bool {
+        self.classify().contains(CharFlags::SPACE)
+    }
+
+    fn is_blank(&self) -> bool {
+        self.classify().contains(CharFlags::BLANK)
+    }
+
+    fn is_alpha(&self) -> bool {
+        self.classify().contains(CharFlags::ALPHA)
+    }
+
+    fn is_digit(&self) -> bool {
+        self.classify().contains(CharFlags::DIGIT)
+    }
+
+    fn is_alnum(&self) -> bool {
+        self.classify().contains(CharFlags::ALNUM)
+    }
+
+    fn is_punct(&self) -> bool {
+        self.classify().contains(CharFlags::PUNCT)
+    }
+
+    fn is_cntrl(&self) -> bool {
+        self.classify().contains(CharFlags::CNTRL)
+    }
+
+    fn is_print(&self) -> bool {
+        self.classify().contains(CharFlags::PRINT)
+    }
+
+    fn is_graph(&self) -> bool {
+        self.classify().contains(CharFlags::GRAPH)
+    }
+
+    fn is_xdigit(&self) -> bool {
+        self.classify().contains(CharFlags::XDIGIT)
+    }
+
+    fn is_lower(&self) -> bool {
+        self.classify().contains(CharFlags::LOWER)
+    }
+
+    fn is_upper(&self) -> bool {
+        self.classify().contains(CharFlags::UPPER)
+    }
+
+    fn classify(&self) -> CharFlags {
+        match unsafe { c::classify_byte_native(*self) } {
+            flags if flags >= 0 => CharFlags(flags as u16),
+            _ => panic!("classify_byte_native failed. error={}", errno()),
+        }
+    }
+
+    fn to_uppercase(&self) -> u8 {
+        match unsafe { c::toupper_byte_native(*self) } {
+            upper if upper >= 0 => u8::try_from(upper).unwrap_or(*self),
+            _ => panic!("toupper_byte_native failed. error={}", errno()),
+        }
+    }
+
+    fn to_lowercase(&self) -> u8 {
+        match unsafe { c::tolower_byte_native(*self) } {
+            lower if lower >= 0 => u8::try_from(lower).unwrap_or(*self),
+            _ => panic!("tolower_byte_native failed. error={}", errno()),
+        }
+    }
+}
+
+/// Locale-dependent operations on strings.
+pub trait CTypeStr {
+    /// Converts `self` to uppercase in the current locale.
+    ///
+    /// Each character is converted by the current locale as [`CType::to_uppercase`].
+    /// If the current locale lists no uppercase version of a lowercase letter whose uppercase form
+    /// consists of multiple characters in Unicode, e.g. 'ß', that form is used.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CTypeStr;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!("stra\u{00DF}e".to_uppercase_str(), "STRA\u{00DF}E");
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!("stra\u{00DF}e".to_uppercase_str(), "STRASSE");
+    /// ```
+    fn to_uppercase_str(&self) -> String;
+
+    /// Converts `self` to lowercase in the current locale.
+    ///
+    /// Each character is converted by the current locale as [`CType::to_lowercase`],
+    /// except that the Greek capital letter 'Σ' at the end of a word is converted to 'ς'.
+    /// If the current locale lists no lowercase version of an uppercase letter whose lowercase form
+    /// consists of multiple characters in Unicode, that form is used.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CTypeStr;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!("\u{00C9}T\u{00C9}".to_lowercase_str(), "\u{00C9}t\u{00C9}");
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!("\u{00C9}T\u{00C9}".to_lowercase_str(), "\u{00E9}t\u{00E9}");
+    /// assert_eq!("\u{039F}\u{0394}\u{039F}\u{03A3}".to_lowercase_str(), "\u{03BF}\u{03B4}\u{03BF}\u{03C2}");
+    /// ```
+    fn to_lowercase_str(&self) -> String;
+
+    /// Returns `self` with leading and trailing whitespace in the current locale removed.
+    ///
+    /// Unlike [`str::trim`], whitespace is determined by [`CType::is_space`] instead of Unicode.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CTypeStr;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!(" \u{3000}abc\u{3000} ".trim_locale(), "\u{3000}abc\u{3000}");
+    /// std::env::set_var("LC_ALL", "en_US.UTF-8");
+    /// assert_eq!(" \u{3000}abc\u{3000} ".trim_locale(), "abc");
+    /// ```
+    fn trim_locale(&self) -> &str;
+
+    /// Returns `self` with leading whitespace in the current locale removed.
+    ///
+    /// See [`CTypeStr::trim_locale`].
+    fn trim_start_locale(&self) -> &str;
+
+    /// Returns `self` with trailing whitespace in the current locale removed.
+    ///
+    /// See [`CTypeStr::trim_locale`].
+    fn trim_end_locale(&self) -> &str;
+
+    /// Checks if `self` and `other` are equal ignoring case in the current locale.
+    ///
+    /// Both strings are converted by [`CTypeStr::to_lowercase_str`] and then compared.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CTypeStr;
+    ///
+    /// assert!("Hello".eq_ignore_case_locale("hELLO"));
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert!(!"\u{00C4}".eq_ignore_case_locale("\u{00E4}"));
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert!("\u{00C4}".eq_ignore_case_locale("\u{00E4}"));
+    /// ```
+    fn eq_ignore_case_locale(&self, other: &str) -> bool;
+
+    /// Converts the first letter of `self` to uppercase and the rest to lowercase in the current locale.
+    ///
+    /// Characters before the first letter, e.g. whitespace and punctuation, are left as they are.
+    /// The first letter is converted as [`CTypeStr::to_uppercase_str`] and the rest as [`CTypeStr::to_lowercase_str`],
+    /// except that only the first character of an expanded uppercase form stays uppercase, e.g. 'ß' to "Ss",
+    /// and that a digraph is converted to its titlecase form as [`CType::to_titlecase`], e.g. 'ǆ' to 'ǅ'.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CTypeStr;
+    ///
+    /// assert_eq!("hello WORLD".to_titlecase(), "Hello world");
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_eq!("\u{00E9}COLE".to_titlecase(), "\u{00E9}Cole");
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!("\u{00E9}COLE".to_titlecase(), "\u{00C9}cole");
+    /// ```
+    fn to_titlecase(&self) -> String;
+
+    /// Converts `self` to the case-folded form in the current locale for caseless matching.
+    ///
+    /// There is no case folding function in C, so this approximates it by converting `self`
+    /// to uppercase as [`CTypeStr::to_uppercase_str`] and then each character to lowercase as [`CType::to_lowercase`].
+    /// Thus one-way mappings are folded together, e.g. 'ß' and "SS", 'ſ' and 'S',
+    /// and 'ς' and 'σ', if the current locale knows them.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CTypeStr;
+    ///
+    /// std::env::set_var("LC_ALL", "POSIX");
+    /// assert_ne!("Stra\u{00DF}e".to_casefold(), "STRASSE".to_casefold());
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!("Stra\u{00DF}e".to_casefold(), "strasse");
+    /// assert_eq!("STRASSE".to_casefold(), "strasse");
+    /// ```
+    fn to_casefold(&self) -> String;
+}
+
+impl CTypeStr for str {
+    fn to_uppercase_str(&self) -> String {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        let mut upper = String::with_capacity(self.len());
+        for c in self.chars() {
+            push_uppercase(&locale, c, &mut upper);
+        }
+        upper
+    }
+
+    fn to_lowercase_str(&self) -> String {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        let mut lower = String::with_capacity(self.len());
+        push_lowercase_str(&locale, self, 0, &mut lower);
+        lower
+    }
+
+    fn to_titlecase(&self) -> String {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        let mut title = String::with_capacity(self.len());
+        match self.char_indices().find(|(_, c)| locale.is_alpha(*c)) {
+            Some((i, c)) => {
+                title.push_str(&self[..i]);
+                push_titlecase(&locale, c, &mut title);
+                push_lowercase_str(&locale, self, i + c.len_utf8(), &mut title);
+            }
+            None => title.push_str(self),
+        }
+        title
+    }
+
+    fn to_casefold(&self) -> String {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        let mut upper = String::with_capacity(self.len());
+        for c in self.chars() {
+            push_uppercase(&locale, c, &mut upper);
+        }
+        upper.chars().map(|c| locale.to_lowercase(c)).collect()
+    }
+
+    fn trim_locale(&self) -> &str {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        self.trim_matches(|c| locale.is_space(c))
+    }
+
+    fn trim_start_locale(&self) -> &str {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        self.trim_start_matches(|c| locale.is_space(c))
+    }
+
+    fn trim_end_locale(&self) -> &str {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        self.trim_end_matches(|c| locale.is_space(c))
+    }
+
+    fn eq_ignore_case_locale(&self, other: &str) -> bool {
+        self.to_lowercase_str() == other.to_lowercase_str()
+    }
+}
+
+/// Locale-dependent operations modifying `String` in place.
+pub trait CTypeString {
+    /// Converts `self` to uppercase in the current locale in place, as [`CTypeStr::to_uppercase_str`].
+    ///
+    /// The characters are rewritten in the buffer of `self` without allocating while each converted character has
+    /// the same length in UTF-8, e.g. 'é' and 'É'.
+    /// From the first character which changes the length, the rest is converted into a new buffer and copied back,
+    /// which keeps the buffer of `self` if the result fits in its capacity.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale specified by the environment cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CTypeString;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// let mut s = String::from("stra\u{00DF}e");
+    /// s.make_uppercase();
+    /// assert_eq!(s, "STRASSE");
+    /// ```
+    fn make_uppercase(&mut self);
+
+    /// Converts `self` to lowercase in the current locale in place, as [`CTypeStr::to_lowercase_str`].
+    ///
+    /// The characters are rewritten in the buffer of `self` without allocating while each converted character has
+    /// the same length in UTF-8, e.g. 'é' and 'É'.
+    /// From the first character which changes the length, the rest is converted into a new buffer and copied back,
+    /// which keeps the buffer of `self` if the result fits in its capacity.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale specified by the environment cannot be loaded.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CTypeString;
+    ///
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// let mut s = String::from("\u{00C9}T\u{00C9}");
+    /// s.make_lowercase();
+    /// assert_eq!(s, "\u{00E9}t\u{00E9}");
+    /// ```
+    fn make_lowercase(&mut self);
+}
+
+impl CTypeString for String {
+    fn make_uppercase(&mut self) {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        let end = convert_in_place(self, |_, _, c| single_uppercase(&locale, c));
+        if end < self.len() {
+            let mut rest = String::with_capacity(self.len() - end);
+            for c in self[end..].chars() {
+                push_uppercase(&locale, c, &mut rest);
+            }
+            self.truncate(end);
+            self.push_str(&rest);
+        }
+    }
+
+    fn make_lowercase(&mut self) {
+        let locale = Locale::new(&[Category::Ctype], "").unwrap();
+        let end = convert_in_place(self, |s, i, c| {
+            if c == '\u{03A3}' && is_final_sigma(&locale, s, i) {
+                Some('\u{03C2}')
+            } else {
+                single_lowercase(&locale, c)
+            }
+        });
+        if end < self.len() {
+            let mut rest = String::with_capacity(self.len() - end);
+            push_lowercase_str(&locale, self, end, &mut rest);
+            self.truncate(end);
+            self.push_str(&rest);
+        }
+    }
+}
+
+/// Converts the characters of `s` in place while each converted character has the same length in UTF-8,
+/// and returns the byte index of the first character which does not, or the length of `s`.
+///
+/// `convert` takes `s`, the byte index of the character and the character,
+/// and returns `None` if the character is converted to multiple characters.
+fn convert_in_place(
+    s: &mut String,
+    mut convert: impl FnMut(&str, usize, char) -> Option<char>,
+) -> usize {
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        match convert(s, i, c) {
+            Some(converted) if converted.len_utf8() == c.len_utf8() => {
+                if converted != c {
+                    // Replacing with the same length moves no bytes and does not allocate.
+                    let mut buf = [0; 4];
+                    s.replace_range(i..i + c.len_utf8(), converted.encode_utf8(&mut buf));
+                }
+                i += c.len_utf8();
+            }
+            _ => return i,
+        }
+    }
+    i
+}
+
+/// Returns `c` converted to uppercase, or `None` if it is converted to multiple characters as 'ß'.
+fn single_uppercase(locale: &Locale, c: char) -> Option<char> {
+    let upper = locale.to_uppercase(c);
+    if upper == c && c.to_uppercase().len() > 1 && locale.is_lower(c) {
+        None
+    } else {
+        Some(upper)
+    }
+}
+
+/// Returns `c` converted to lowercase, or `None` if it is converted to multiple characters.
+fn single_lowercase(locale: &Locale, c: char) -> Option<char> {
+    let lower = locale.to_lowercase(c);
+    if lower == c && c.to_lowercase().len() > 1 && locale.is_upper(c) {
+        None
+    } else {
+        Some(lower)
+    }
+}
+
+fn push_uppercase(locale: &Locale, c: char, s: &mut String) {
+    match single_uppercase(locale, c) {
+        Some(upper) => s.push(upper),
+        None => s.extend(c.to_uppercase()),
+    }
+}
+
+/// Appends `c` converted to titlecase to `s`.
+///
+/// If `c` is expanded in uppercase, the characters following the first one are converted to lowercase, e.g. "Ss" for 'ß'.
+/// The `"totitle"` mapping of glibc lacks the locale-specific mappings, e.g. 'i' to 'İ' in Turkish,
+/// so it is used only if it differs from the uppercase forms of both the locale and Unicode, i.e. for digraphs.
+fn push_titlecase(locale: &Locale, c: char, s: &mut String) {
+    match single_uppercase(locale, c) {
+        Some(upper) => {
+            let title = CType::to_titlecase(&c);
+            if title != upper && !c.to_uppercase().eq(std::iter::once(title)) {
+                s.push(title);
+            } else {
+                s.push(upper);
+            }
+        }
+        None => {
+            let mut expanded = c.to_uppercase();
+            s.extend(expanded.next());
+            s.extend(expanded.map(|upper| locale.to_lowercase(upper)));
+        }
+    }
+}
+
+/// Pushes `s[start..]` converted to lowercase, taking the preceding characters into account for 'Σ'.
+fn push_lowercase_str(locale: &Locale, s: &str, start: usize, lower: &mut String) {
+    for (i, c) in s[start..].char_indices() {
+        if c == '\u{03A3}' && is_final_sigma(locale, s, start + i) {
+            lower.push('\u{03C2}');
+        } else {
+            push_lowercase(locale, c, lower);
+        }
+    }
+}
+
+fn push_lowercase(locale: &Locale, c: char, s: &mut String) {
+    match single_lowercase(locale, c) {
+        Some(lower) => s.push(lower),
+        None => s.extend(c.to_lowercase()),
+    }
+}
+
+/// Checks if 'Σ' at the byte index `i` of `s` ends a word, i.e. preceded by a letter and not followed by a letter.
+fn is_final_sigma(locale: &Locale, s: &str, i: usize) -> bool {
+    if locale.to_lowercase('\u{03A3}') != '\u{03C3}' {
+        return false;
+    }
+    let preceded = matches!(s[..i].chars().next_back(), Some(c) if locale.is_alpha(c));
+    let followed = matches!(s[i..].chars().nth(1), Some(c) if locale.is_alpha(c));
+    preceded && !followed
+}
+
+/// Character classification and case conversion in a specific locale.
+///
+/// These methods do not depend on the current locale, so they can be used concurrently with different locales.
+/// `self` must contain `LC_CTYPE`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::locale::{Category, Locale};
+///
+/// let posix = Locale::new(&[Category::Ctype], "POSIX").unwrap();
+/// let en_us = Locale::new(&[Category::Ctype], "en_US").unwrap();
+/// assert!(!posix.is_space('\u{2003}'));
+/// assert!(en_us.is_space('\u{2003}'));
+/// assert_eq!(posix.to_uppercase('\u{017F}'), '\u{017F}');
+/// assert_eq!(en_us.to_uppercase('\u{017F}'), 'S');
+/// ```
+impl Locale {
+    /// Checks if `c` is a whitespace character in `self`.
+    ///
+    /// Same as [`CType::is_space`] except that `self` is used instead of the current locale.
+    pub fn is_space(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswspace_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is a blank character in `self`.
+    ///
+    /// Same as [`CType::is_blank`] except that `self` is used instead of the current locale.
+    pub fn is_blank(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswblank_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is an alphabetic character in `self`.
+    ///
+    /// Same as [`CType::is_alpha`] except that `self` is used instead of the current locale.
+    pub fn is_alpha(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswalpha_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is a decimal digit character in `self`.
+    ///
+    /// Same as [`CType::is_digit`] except that `self` is used instead of the current locale.
+    pub fn is_digit(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswdigit_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is an alphanumeric character in `self`.
+    ///
+    /// Same as [`CType::is_alnum`] except that `self` is used instead of the current locale.
+    pub fn is_alnum(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswalnum_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is a punctuation character in `self`.
+    ///
+    /// Same as [`CType::is_punct`] except that `self` is used instead of the current locale.
+    pub fn is_punct(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswpunct_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is a control character in `self`.
+    ///
+    /// Same as [`CType::is_cntrl`] except that `self` is used instead of the current locale.
+    pub fn is_cntrl(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswcntrl_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is a printable character, including space, in `self`.
+    ///
+    /// Same as [`CType::is_print`] except that `self` is used instead of the current locale.
+    pub fn is_print(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswprint_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is a printable character, excluding space, in `self`.
+    ///
+    /// Same as [`CType::is_graph`] except that `self` is used instead of the current locale.
+    pub fn is_graph(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswgraph_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is a hexadecimal digit character in `self`.
+    ///
+    /// Same as [`CType::is_xdigit`] except that `self` is used instead of the current locale.
+    pub fn is_xdigit(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswxdigit_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is a lowercase letter in `self`.
+    ///
+    /// Same as [`CType::is_lower`] except that `self` is used instead of the current locale.
+    pub fn is_lower(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswlower_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Checks if `c` is an uppercase letter in `self`.
+    ///
+    /// Same as [`CType::is_upper`] except that `self` is used instead of the current locale.
+    pub fn is_upper(&self, c: char) -> bool {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        unsafe { c::iswupper_l_native(wc.into(), self.as_raw()) != 0 }
+    }
+
+    /// Converts `c` to uppercase listed in `self`.
+    ///
+    /// Same as [`CType::to_uppercase`] except that `self` is used instead of the current locale.
+    pub fn to_uppercase(&self, c: char) -> char {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        let upper = unsafe { c::towupper_l_native(wc.into(), self.as_raw()) as wchar_t };
+        wctochar(upper).unwrap()
+    }
+
+    /// Converts `c` to lowercase listed in `self`.
+    ///
+    /// Same as [`CType::to_lowercase`] except that `self` is used instead of the current locale.
+    pub fn to_lowercase(&self, c: char) -> char {
+        let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+        let lower = unsafe { c::towlower_l_native(wc.into(), self.as_raw()) as wchar_t };
+        wctochar(lower).unwrap()
+    }
+}
+
+pub(crate) fn utf8_bytes(c: &char) -> Vec<u8> {
+    let length = c.len_utf8();
+    let mut buf = vec![0; length];
+    c.encode_utf8(&mut buf);
+    buf
+}
+
+/// Converts the UTF-8 encoding of exactly one character to the C wide character.
+///
+/// Returns [`LocaleError::IncompleteSequence`] if `utf8_bytes` is a prefix of a valid sequence,
+/// or [`LocaleError::InvalidByte`] if it is not valid or contains more than one character.
+pub(crate) fn utf8towc(utf8_bytes: &[u8]) -> Result<wchar_t, LocaleError> {
+    let mut wc = 0;
+    match unsafe {
+        c::utf8towc(
+            &mut wc as *mut wchar_t,
+            utf8_bytes.as_ptr() as *const c_char,
             utf8_bytes.len(),
         )
     } {
-        s if s == 0 => wc,
-        s => panic!("utf8towc failed. status={}, error={}", s, errno()),
+        0 => Ok(wc),
+        2 => Err(LocaleError::InvalidByte),
+        3 => Err(LocaleError::IncompleteSequence),
+        _ => Err(LocaleError::ConversionFailed { errno: errno().0 }),
+    }
+}
+
+fn wctochar(wc: wchar_t) -> Result<char, LocaleError> {
+    crate::encoding::wchar_to_char(wc)
+}
+
+fn isspace(wc: wchar_t) -> Result<bool, LocaleError> {
+    match unsafe { c::iswspace_native(wc.into()) } {
+        s if s >= 0 => Ok(s != 0),
+        _ => Err(newlocale_error("")),
+    }
+}
+
+fn isblank(wc: wchar_t) -> bool {
+    match unsafe { c::iswblank_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswblank_native failed. error={}", errno()),
+    }
+}
+
+fn isalpha(wc: wchar_t) -> bool {
+    match unsafe { c::iswalpha_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswalpha_native failed. error={}", errno()),
+    }
+}
+
+fn isdigit(wc: wchar_t) -> bool {
+    match unsafe { c::iswdigit_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswdigit_native failed. error={}", errno()),
+    }
+}
+
+fn isalnum(wc: wchar_t) -> bool {
+    match unsafe { c::iswalnum_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswalnum_native failed. error={}", errno()),
+    }
+}
+
+fn ispunct(wc: wchar_t) -> bool {
+    match unsafe { c::iswpunct_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswpunct_native failed. error={}", errno()),
+    }
+}
+
+fn iscntrl(wc: wchar_t) -> bool {
+    match unsafe { c::iswcntrl_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswcntrl_native failed. error={}", errno()),
+    }
+}
+
+fn isprint(wc: wchar_t) -> bool {
+    match unsafe { c::iswprint_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswprint_native failed. error={}", errno()),
+    }
+}
+
+fn isgraph(wc: wchar_t) -> bool {
+    match unsafe { c::iswgraph_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswgraph_native failed. error={}", errno()),
+    }
+}
+
+fn isxdigit(wc: wchar_t) -> bool {
+    match unsafe { c::iswxdigit_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswxdigit_native failed. error={}", errno()),
+    }
+}
+
+fn islower(wc: wchar_t) -> bool {
+    match unsafe { c::iswlower_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswlower_native failed. error={}", errno()),
+    }
+}
+
+fn isupper(wc: wchar_t) -> bool {
+    match unsafe { c::iswupper_native(wc.into()) } {
+        s if s >= 0 => s != 0,
+        _ => panic!("iswupper_native failed. error={}", errno()),
+    }
+}
+
+fn toupper(wc: wchar_t) -> Result<wchar_t, LocaleError> {
+    match unsafe { c::towupper_native(wc.into()) as wchar_t } {
+        WEOF => Err(newlocale_error("")),
+        upper => Ok(upper),
+    }
+}
+
+fn tolower(wc: wchar_t) -> Result<wchar_t, LocaleError> {
+    match unsafe { c::towlower_native(wc.into()) as wchar_t } {
+        WEOF => Err(newlocale_error("")),
+        lower => Ok(lower),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::with_env_locale;
+
+    #[test]
+    fn is_always_space() {
+        assert!(' '.is_space());
+        assert!('\x0c'.is_space());
+        assert!('\n'.is_space());
+        assert!('\r'.is_space());
+        assert!('\t'.is_space());
+        assert!('\x0b'.is_space());
+    }
+
+    #[test]
+    fn is_space_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{1680}'.is_space());
+            assert!(!'\u{2000}'.is_space());
+            assert!(!'\u{2006}'.is_space());
+            assert!(!'\u{2008}'.is_space());
+            assert!(!'\u{200A}'.is_space());
+            assert!(!'\u{2028}'.is_space());
+            assert!(!'\u{2029}'.is_space());
+            assert!(!'\u{205F}'.is_space());
+            assert!(!'\u{3000}'.is_space());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{1680}'.is_space());
+            assert!('\u{2000}'.is_space());
+            assert!('\u{2006}'.is_space());
+            assert!('\u{2008}'.is_space());
+            assert!('\u{200A}'.is_space());
+            assert!('\u{2028}'.is_space());
+            assert!('\u{2029}'.is_space());
+            assert!('\u{205F}'.is_space());
+            assert!('\u{3000}'.is_space());
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn is_space_special() {
+        with_env_locale("en_US", || {
+            assert!(!'\u{1361}'.is_space());
+        });
+        with_env_locale("am_ET", || {
+            assert!('\u{1361}'.is_space());
+        });
+    }
+
+    #[test]
+    fn try_is_space() {
+        with_env_locale("en_US", || {
+            assert_eq!(' '.try_is_space(), Ok(true));
+            assert_eq!('\u{2003}'.try_is_space(), Ok(true));
+            assert_eq!('a'.try_is_space(), Ok(false));
+        });
+        with_env_locale("xx_XX.UTF-8", || {
+            assert_eq!(' '.try_is_space(), Ok(true));
+            assert!(matches!(
+                '\u{2003}'.try_is_space(),
+                Err(LocaleError::LocaleNotFound { .. })
+            ));
+        });
+    }
+
+    #[test]
+    fn is_always_blank() {
+        assert!(' '.is_blank());
+        assert!('\t'.is_blank());
+        assert!(!'\n'.is_blank());
+        assert!(!'\r'.is_blank());
+        assert!(!'\x0b'.is_blank());
+        assert!(!'\x0c'.is_blank());
+    }
+
+    #[test]
+    fn is_blank() {
+        with_env_locale("POSIX", || {
+            assert!(' '.is_blank());
+            assert!('\t'.is_blank());
+            assert!(!'\n'.is_blank());
+            assert!(!'\u{3000}'.is_blank());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{3000}'.is_blank());
+            assert!(!'\u{2028}'.is_blank());
+        });
+    }
+
+    #[test]
+    fn is_alpha() {
+        assert!('a'.is_alpha());
+        assert!('Z'.is_alpha());
+        assert!(!'1'.is_alpha());
+        assert!(!' '.is_alpha());
+    }
+
+    #[test]
+    fn is_alpha_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{00E9}'.is_alpha());
+            assert!(!'\u{00DF}'.is_alpha());
+            assert!(!'\u{03A3}'.is_alpha());
+            assert!(!'\u{4E16}'.is_alpha());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{00E9}'.is_alpha());
+            assert!('\u{00DF}'.is_alpha());
+            assert!('\u{03A3}'.is_alpha());
+            assert!('\u{4E16}'.is_alpha());
+        });
+    }
+
+    #[test]
+    fn is_digit() {
+        for ch in '0'..='9' {
+            assert!(CType::is_digit(&ch));
+        }
+        assert!(!CType::is_digit(&'a'));
+        assert!(!CType::is_digit(&'F'));
+        assert!(!CType::is_digit(&' '));
+    }
+
+    #[test]
+    fn is_digit_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(CType::is_digit(&'5'));
+            assert!(!CType::is_digit(&'\u{FF10}'));
+            assert!(!CType::is_digit(&'\u{0665}'));
+        });
+        with_env_locale("en_US", || {
+            assert!(CType::is_digit(&'5'));
+            assert!(!CType::is_digit(&'\u{FF10}'));
+            assert!(!CType::is_digit(&'\u{0665}'));
+            assert!('\u{FF10}'.is_alpha());
+        });
+    }
+
+    #[test]
+    fn is_alnum() {
+        assert!('a'.is_alnum());
+        assert!('Z'.is_alnum());
+        assert!('1'.is_alnum());
+        assert!(!' '.is_alnum());
+        assert!(!'!'.is_alnum());
+    }
+
+    #[test]
+    fn is_alnum_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{00E9}'.is_alnum());
+            assert!(!'\u{4E16}'.is_alnum());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{00E9}'.is_alnum());
+            assert!('\u{4E16}'.is_alnum());
+            assert!(!'\u{3000}'.is_alnum());
+        });
+    }
+
+    #[test]
+    fn is_always_punct() {
+        assert!('!'.is_punct());
+        assert!('.'.is_punct());
+        assert!(','.is_punct());
+        assert!('?'.is_punct());
+        assert!('~'.is_punct());
+        assert!(!'a'.is_punct());
+        assert!(!'1'.is_punct());
+        assert!(!' '.is_punct());
+    }
+
+    #[test]
+    fn is_punct_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{3002}'.is_punct());
+            assert!(!'\u{00BF}'.is_punct());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{3002}'.is_punct());
+            assert!('\u{00BF}'.is_punct());
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn is_punct_special() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{3002}'.is_punct());
+        });
+        with_env_locale("ja_JP.UTF-8", || {
+            assert!('!'.is_punct());
+            assert!('\u{3002}'.is_punct());
+        });
+    }
+
+    #[test]
+    fn is_always_cntrl() {
+        assert!('\0'.is_cntrl());
+        assert!('\x07'.is_cntrl());
+        assert!('\x1b'.is_cntrl());
+        assert!('\n'.is_cntrl());
+        assert!('\x7f'.is_cntrl());
+        assert!(!'a'.is_cntrl());
+        assert!(!' '.is_cntrl());
+    }
+
+    #[test]
+    fn is_cntrl_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{0085}'.is_cntrl());
+            assert!(!'\u{009F}'.is_cntrl());
+            assert!(!'\u{2028}'.is_cntrl());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{0085}'.is_cntrl());
+            assert!('\u{009F}'.is_cntrl());
+            assert!('\u{2028}'.is_cntrl());
+            assert!(!'\u{00E9}'.is_cntrl());
+        });
+    }
+
+    #[test]
+    fn is_print() {
+        assert!('a'.is_print());
+        assert!('~'.is_print());
+        assert!(' '.is_print());
+        assert!(!'\n'.is_print());
+        assert!(!'\x7f'.is_print());
+    }
+
+    #[test]
+    fn is_print_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{00A0}'.is_print());
+            assert!(!'\u{3000}'.is_print());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{00A0}'.is_print());
+            assert!('\u{3000}'.is_print());
+            assert!(!'\u{0085}'.is_print());
+        });
+    }
+
+    #[test]
+    fn is_graph() {
+        assert!('a'.is_graph());
+        assert!('~'.is_graph());
+        assert!(!' '.is_graph());
+        assert!(!'\n'.is_graph());
+    }
+
+    #[test]
+    fn is_graph_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{00A0}'.is_graph());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{00A0}'.is_graph());
+            assert!('\u{3000}'.is_print());
+            assert!(!'\u{3000}'.is_graph());
+        });
+    }
+
+    #[test]
+    fn is_xdigit() {
+        for ch in ('0'..='9').chain('a'..='f').chain('A'..='F') {
+            assert!(ch.is_xdigit());
+        }
+        assert!(!'g'.is_xdigit());
+        assert!(!'G'.is_xdigit());
+        assert!(!' '.is_xdigit());
+    }
+
+    #[test]
+    fn is_xdigit_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{FF21}'.is_xdigit());
+        });
+        with_env_locale("en_US", || {
+            assert!('a'.is_xdigit());
+            assert!(!'\u{FF21}'.is_xdigit());
+            assert!(!'\u{FF11}'.is_xdigit());
+        });
+    }
+
+    #[test]
+    fn is_lower() {
+        assert!('a'.is_lower());
+        assert!('z'.is_lower());
+        assert!(!'A'.is_lower());
+        assert!(!'1'.is_lower());
+    }
+
+    #[test]
+    fn is_lower_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{0131}'.is_lower());
+            assert!(!'\u{00E9}'.is_lower());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{0131}'.is_lower());
+            assert!('\u{00E9}'.is_lower());
+            assert!(!'\u{0130}'.is_lower());
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn is_lower_special() {
+        with_env_locale("tr_TR", || {
+            assert!('i'.is_lower());
+            assert!('\u{0131}'.is_lower());
+            assert!(!'I'.is_lower());
+            assert!(!'\u{0130}'.is_lower());
+        });
+    }
+
+    #[test]
+    fn is_upper() {
+        assert!('A'.is_upper());
+        assert!('Z'.is_upper());
+        assert!(!'a'.is_upper());
+        assert!(!'1'.is_upper());
+    }
+
+    #[test]
+    fn is_upper_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{0130}'.is_upper());
+            assert!(!'\u{00C9}'.is_upper());
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{0130}'.is_upper());
+            assert!('\u{00C9}'.is_upper());
+            assert!(!'\u{0131}'.is_upper());
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn is_upper_special() {
+        with_env_locale("tr_TR", || {
+            assert!('I'.is_upper());
+            assert!('\u{0130}'.is_upper());
+            assert!(!'i'.is_upper());
+            assert!(!'\u{0131}'.is_upper());
+        });
+    }
+
+    #[test]
+    fn locale() {
+        let posix = Locale::new(&[Category::Ctype], "POSIX").unwrap();
+        let en_us = Locale::new(&[Category::Ctype], "en_US").unwrap();
+        assert!(posix.is_space(' '));
+        assert!(!posix.is_space('\u{2003}'));
+        assert!(en_us.is_space('\u{2003}'));
+        assert!(!posix.is_blank('\u{3000}'));
+        assert!(en_us.is_blank('\u{3000}'));
+        assert!(!posix.is_alpha('\u{00E9}'));
+        assert!(en_us.is_alpha('\u{00E9}'));
+        assert!(en_us.is_digit('5'));
+        assert!(!en_us.is_digit('\u{FF10}'));
+        assert!(!posix.is_alnum('\u{00E9}'));
+        assert!(en_us.is_alnum('\u{00E9}'));
+        assert!(!posix.is_punct('\u{3002}'));
+        assert!(en_us.is_punct('\u{3002}'));
+        assert!(!posix.is_cntrl('\u{0085}'));
+        assert!(en_us.is_cntrl('\u{0085}'));
+        assert!(!posix.is_print('\u{00A0}'));
+        assert!(en_us.is_print('\u{00A0}'));
+        assert!(!posix.is_graph('\u{00A0}'));
+        assert!(en_us.is_graph('\u{00A0}'));
+        assert!(en_us.is_xdigit('F'));
+        assert!(!en_us.is_xdigit('g'));
+        assert!(!posix.is_lower('\u{0131}'));
+        assert!(en_us.is_lower('\u{0131}'));
+        assert!(!posix.is_upper('\u{0130}'));
+        assert!(en_us.is_upper('\u{0130}'));
+        assert_eq!(posix.to_uppercase('\u{017F}'), '\u{017F}');
+        assert_eq!(en_us.to_uppercase('\u{017F}'), 'S');
+        assert_eq!(posix.to_lowercase('\u{0190}'), '\u{0190}');
+        assert_eq!(en_us.to_lowercase('\u{0190}'), '\u{025b}');
+    }
+
+    #[test]
+    fn locale_independent_of_env() {
+        with_env_locale("POSIX", || {
+            let en_us = Locale::new(&[Category::Ctype], "en_US").unwrap();
+            assert!(!'\u{2003}'.is_space());
+            assert!(en_us.is_space('\u{2003}'));
+        });
+    }
+
+    #[test]
+    fn locale_threads() {
+        let threads: Vec<_> = [("POSIX", false), ("en_US", true)]
+            .iter()
+            .map(|&(name, expected)| {
+                std::thread::spawn(move || {
+                    let locale = Locale::new(&[Category::Ctype], name).unwrap();
+                    for _ in 0..1000 {
+                        assert_eq!(locale.is_alpha('\u{00E9}'), expected);
+                        assert_eq!(locale.to_uppercase('\u{00E9}') == '\u{00C9}', expected);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn locale_threads_special() {
+        let threads: Vec<_> = [("tr_TR", '\u{0130}'), ("en_US", 'I')]
+            .iter()
+            .map(|&(name, expected)| {
+                std::thread::spawn(move || {
+                    let locale = Locale::new(&[Category::Ctype], name).unwrap();
+                    for _ in 0..1000 {
+                        assert_eq!(locale.to_uppercase('i'), expected);
+                        assert!(locale.is_lower('i'));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn to_uppercase_str() {
+        assert_eq!("hello, world 1".to_uppercase_str(), "HELLO, WORLD 1");
+        assert_eq!("".to_uppercase_str(), "");
+        with_env_locale("POSIX", || {
+            assert_eq!("stra\u{00DF}e".to_uppercase_str(), "STRA\u{00DF}E");
+            assert_eq!("\u{00E9}t\u{00E9}".to_uppercase_str(), "\u{00E9}T\u{00E9}");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!("stra\u{00DF}e".to_uppercase_str(), "STRASSE");
+            assert_eq!("\u{00E9}t\u{00E9}".to_uppercase_str(), "\u{00C9}T\u{00C9}");
+            assert_eq!("istanbul".to_uppercase_str(), "ISTANBUL");
+            assert_eq!(String::from("\u{FB01}").to_uppercase_str(), "FI");
+        });
+    }
+
+    #[test]
+    fn make_uppercase() {
+        for locale in ["POSIX", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                for original in [
+                    "hello, world 1",
+                    "",
+                    "stra\u{00DF}e",
+                    "\u{00E9}t\u{00E9}",
+                    "\u{FB01}",
+                ] {
+                    let mut s = original.to_string();
+                    s.make_uppercase();
+                    assert_eq!(s, original.to_uppercase_str(), "{}", locale);
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn make_uppercase_capacity() {
+        with_env_locale("en_US.UTF-8", || {
+            let mut s = String::from("\u{00E9}t\u{00E9}");
+            let (ptr, capacity) = (s.as_ptr(), s.capacity());
+            s.make_uppercase();
+            assert_eq!(s, "\u{00C9}T\u{00C9}");
+            assert_eq!((s.as_ptr(), s.capacity()), (ptr, capacity));
+
+            let mut s = String::from("stra\u{00DF}e");
+            s.make_uppercase();
+            assert_eq!(s, "STRASSE");
+            assert!(s.capacity() >= "STRASSE".len());
+
+            let mut s = String::from("\u{03C3}\u{00DF}\u{00E9}");
+            s.make_uppercase();
+            assert_eq!(s, "\u{03A3}SS\u{00C9}");
+
+            let mut s = String::with_capacity(64);
+            s.push_str("stra\u{00DF}e");
+            let ptr = s.as_ptr();
+            s.make_uppercase();
+            assert_eq!(s, "STRASSE");
+            assert_eq!((s.as_ptr(), s.capacity()), (ptr, 64));
+        });
+    }
+
+    #[test]
+    fn convert_in_place() {
+        let mut s = String::from("a\u{00E9}\u{00DF}b");
+        let ptr = s.as_ptr();
+        let end = super::convert_in_place(&mut s, |_, _, c| match c {
+            '\u{00DF}' => None,
+            c => Some(c.to_ascii_uppercase()),
+        });
+        assert_eq!((s.as_str(), end), ("A\u{00E9}\u{00DF}b", 3));
+        let end = super::convert_in_place(&mut s, |_, _, c| match c {
+            '\u{00E9}' => Some('e'),
+            c => Some(c),
+        });
+        assert_eq!((s.as_str(), end), ("A\u{00E9}\u{00DF}b", 1));
+        let end = super::convert_in_place(&mut s, |_, _, c| Some(c.to_ascii_lowercase()));
+        assert_eq!((s.as_str(), end), ("a\u{00E9}\u{00DF}b", s.len()));
+        assert_eq!(s.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn make_lowercase() {
+        for locale in ["POSIX", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                for original in [
+                    "HELLO, WORLD 1",
+                    "",
+                    "\u{00C9}T\u{00C9}",
+                    "\u{039F}\u{0394}\u{039F}\u{03A3}",
+                    "\u{0130}",
+                ] {
+                    let mut s = original.to_string();
+                    let (ptr, capacity) = (s.as_ptr(), s.capacity());
+                    s.make_lowercase();
+                    assert_eq!(s, original.to_lowercase_str(), "{}", locale);
+                    if s.len() <= capacity {
+                        assert_eq!((s.as_ptr(), s.capacity()), (ptr, capacity));
+                    }
+                }
+            });
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn to_uppercase_str_special() {
+        with_env_locale("de_DE", || {
+            assert_eq!("stra\u{00DF}e".to_uppercase_str(), "STRASSE");
+        });
+        with_env_locale("tr_TR", || {
+            assert_eq!("istanbul".to_uppercase_str(), "\u{0130}STANBUL");
+            assert_eq!("\u{0131}rmak".to_uppercase_str(), "IRMAK");
+        });
+    }
+
+    #[test]
+    fn to_lowercase_str() {
+        assert_eq!("HELLO, WORLD 1".to_lowercase_str(), "hello, world 1");
+        assert_eq!("".to_lowercase_str(), "");
+        with_env_locale("POSIX", || {
+            assert_eq!("\u{00C9}T\u{00C9}".to_lowercase_str(), "\u{00C9}t\u{00C9}");
+            assert_eq!("\u{03A3}".to_lowercase_str(), "\u{03A3}");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!("\u{00C9}T\u{00C9}".to_lowercase_str(), "\u{00E9}t\u{00E9}");
+            assert_eq!("ISTANBUL".to_lowercase_str(), "istanbul");
+            assert_eq!("\u{0130}".to_lowercase_str(), "i");
+        });
+    }
+
+    #[test]
+    fn to_lowercase_str_final_sigma() {
+        with_env_locale("en_US", || {
+            assert_eq!("\u{03A3}".to_lowercase_str(), "\u{03C3}");
+            assert_eq!(
+                "\u{039F}\u{0394}\u{039F}\u{03A3}".to_lowercase_str(),
+                "\u{03BF}\u{03B4}\u{03BF}\u{03C2}"
+            );
+            assert_eq!(
+                "\u{039F}\u{03A3}\u{039F}".to_lowercase_str(),
+                "\u{03BF}\u{03C3}\u{03BF}"
+            );
+            assert_eq!(
+                "\u{039F}\u{03A3} \u{03A3}\u{039F}".to_lowercase_str(),
+                "\u{03BF}\u{03C2} \u{03C3}\u{03BF}"
+            );
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn to_lowercase_str_special() {
+        with_env_locale("en_US", || {
+            assert_eq!("I".to_lowercase_str(), "i");
+        });
+        with_env_locale("tr_TR", || {
+            assert_eq!("I".to_lowercase_str(), "\u{0131}");
+            assert_eq!("\u{0130}STANBUL".to_lowercase_str(), "istanbul");
+            assert_eq!("IRMAK".to_lowercase_str(), "\u{0131}rmak");
+        });
+    }
+
+    #[test]
+    fn trim_locale() {
+        with_env_locale("POSIX", || {
+            assert_eq!(" \tabc d\n".trim_locale(), "abc d");
+            assert_eq!(" \tabc d\n".trim_start_locale(), "abc d\n");
+            assert_eq!(" \tabc d\n".trim_end_locale(), " \tabc d");
+            assert_eq!("   ".trim_locale(), "");
+            assert_eq!("".trim_locale(), "");
+            assert_eq!("\u{3000}abc\u{3000}".trim_locale(), "\u{3000}abc\u{3000}");
+        });
+    }
+
+    #[test]
+    fn trim_locale_i18n() {
+        with_env_locale("POSIX", || {
+            assert_eq!(
+                "\u{3000}abc\u{3000}".trim_start_locale(),
+                "\u{3000}abc\u{3000}"
+            );
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!("\u{3000}abc\u{3000}".trim_locale(), "abc");
+            assert_eq!("\u{3000}abc\u{3000}".trim_start_locale(), "abc\u{3000}");
+            assert_eq!("\u{3000}abc\u{3000}".trim_end_locale(), "\u{3000}abc");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn trim_locale_special() {
+        let s = "\u{1361}\u{12A0}\u{1361}";
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(s.trim_locale(), s);
+        });
+        with_env_locale("am_ET", || {
+            assert_eq!(s.trim_locale(), "\u{12A0}");
+        });
+    }
+
+    #[test]
+    fn char_class() {
+        with_env_locale("POSIX", || {
+            let alpha = super::char_class("alpha").unwrap();
+            assert!('a'.is_class(alpha));
+            assert!(!'1'.is_class(alpha));
+            let digit = super::char_class("digit").unwrap();
+            assert!('1'.is_class(digit));
+            assert!(!'a'.is_class(digit));
+            assert_eq!(super::char_class("bogus"), None);
+            assert_eq!(super::char_class(""), None);
+            assert_eq!(super::char_class("al\0pha"), None);
+        });
+    }
+
+    #[test]
+    fn char_class_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!'\u{00E4}'.is_class(super::char_class("alpha").unwrap()));
+        });
+        with_env_locale("en_US", || {
+            assert!('\u{00E4}'.is_class(super::char_class("alpha").unwrap()));
+            assert!('\u{00E4}'.is_class(super::char_class("lower").unwrap()));
+            assert!(!'\u{00E4}'.is_class(super::char_class("upper").unwrap()));
+        });
+    }
+
+    #[test]
+    fn is_c_space() {
+        let spaces = [' ', '\t', '\n', '\x0b', '\x0c', '\r'];
+        for c in '\0'..='\u{FFFF}' {
+            assert_eq!(super::is_c_space(&c), spaces.contains(&c), "{:?}", c);
+        }
+        for locale in ["C", "POSIX", "en_US", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                for c in spaces {
+                    assert!(CType::is_space(&c), "{:?} {}", c, locale);
+                }
+            });
+        }
+        // The characters are checked before loading the locale.
+        with_env_locale("xx_XX", || {
+            for c in spaces {
+                assert_eq!(c.try_is_space(), Ok(true), "{:?}", c);
+            }
+            assert!('\u{2003}'.try_is_space().is_err());
+        });
+    }
+
+    #[test]
+    fn posix_fast_path() {
+        let samples = (0..0x80u8).map(char::from).chain([
+            '\u{80}',
+            '\u{85}',
+            '\u{A0}',
+            '\u{DF}',
+            '\u{E4}',
+            '\u{FF}',
+            '\u{131}',
+            '\u{3B1}',
+            '\u{2003}',
+            '\u{3000}',
+            '\u{FF21}',
+            '\u{10400}',
+            '\u{10FFFF}',
+        ]);
+        for locale in ["POSIX", "C"] {
+            with_env_locale(locale, || {
+                assert!(super::env_ctype_is_posix());
+                for c in samples.clone() {
+                    let wc = super::utf8towc(&utf8_bytes(&c)).unwrap();
+                    assert_eq!(CType::is_space(&c), isspace(wc).unwrap(), "{:?}", c);
+                    assert_eq!(CType::is_alpha(&c), isalpha(wc), "{:?}", c);
+                    assert_eq!(
+                        CType::to_uppercase(&c),
+                        wctochar(toupper(wc).unwrap()).unwrap(),
+                        "{:?}",
+                        c
+                    );
+                }
+            });
+        }
+        with_env_locale("en_US.UTF-8", || {
+            assert!(!super::env_ctype_is_posix());
+            assert!(CType::is_alpha(&'\u{E4}'));
+            assert_eq!(CType::to_uppercase(&'\u{E4}'), '\u{C4}');
+        });
+    }
+
+    #[test]
+    fn chars_with_class() {
+        with_env_locale("en_US", || {
+            let s = "Order #42: 3 items, \u{00E9}t\u{00E9} 2024!";
+            assert_eq!(
+                super::chars_with_class(s, "digit").collect::<String>(),
+                "4232024"
+            );
+            assert_eq!(
+                super::chars_with_class(s, "punct").collect::<String>(),
+                "#:,!"
+            );
+            assert_eq!(super::chars_with_class(s, "upper").collect::<String>(), "O");
+            assert_eq!(super::chars_with_class(s, "bogus").next(), None);
+            assert_eq!(super::chars_with_class(s, "dig\0it").next(), None);
+            assert_eq!(super::chars_with_class("", "digit").next(), None);
+            assert_eq!(super::chars_with_class("abc", "digit").next(), None);
+        });
+    }
+
+    #[test]
+    fn chars_with_class_i18n() {
+        let s = "a\u{00E4}1\u{00DF}";
+        let posix = with_env_locale("POSIX", || {
+            super::chars_with_class(s, "alpha").collect::<String>()
+        });
+        let en_us = with_env_locale("en_US", || {
+            super::chars_with_class(s, "alpha").collect::<String>()
+        });
+        assert_eq!(posix, "a");
+        assert_eq!(en_us, "a\u{00E4}\u{00DF}");
+    }
+
+    #[test]
+    fn known_classes() {
+        for locale in ["POSIX", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                let classes = super::known_classes();
+                assert_eq!(classes[..12], super::STANDARD_CLASSES, "{}", locale);
+                assert!(classes.iter().all(|name| super::char_class(name).is_some()));
+                assert_eq!(
+                    super::defined_classes(&["alpha", "bogus", "jkata", "digit"]),
+                    ["alpha", "digit"]
+                );
+            });
+        }
+        with_env_locale("POSIX", || {
+            assert_eq!(super::known_classes(), super::STANDARD_CLASSES);
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert!(super::known_classes().contains(&"combining"));
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn known_classes_special() {
+        with_env_locale("ja_JP.UTF-8", || {
+            let classes = super::known_classes();
+            for name in ["jhira", "jkata", "jkanji"] {
+                assert!(classes.contains(&name), "{}", name);
+            }
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn char_class_special() {
+        with_env_locale("en_US", || {
+            assert_eq!(super::char_class("jkata"), None);
+        });
+        with_env_locale("ja_JP.UTF-8", || {
+            let katakana = super::char_class("jkata").unwrap();
+            let hiragana = super::char_class("jhira").unwrap();
+            assert!('\u{30AB}'.is_class(katakana));
+            assert!(!'\u{30AB}'.is_class(hiragana));
+            assert!('\u{304B}'.is_class(hiragana));
+            assert!(!'\u{304B}'.is_class(katakana));
+        });
+    }
+
+    #[test]
+    fn all_digits() {
+        with_env_locale("en_US.UTF-8", || {
+            assert!(super::all_digits("12345"));
+            assert!(!super::all_digits("12a45"));
+            assert!(!super::all_digits("1 2"));
+            assert!(super::all_digits(""));
+        });
     }
-}
 
-fn wctochar(wc: wchar_t) -> char {
-    let mut buf = [0; 4];
-    match unsafe { c::wctoutf8(buf.as_mut_ptr(), wc) } {
-        length if length > 0 => {
-            let length = length as usize;
-            String::from_utf8(buf[..length].iter().map(|c| *c as u8).collect())
-                .unwrap()
+    #[test]
+    fn all_alpha() {
+        with_env_locale("en_US.UTF-8", || {
+            assert!(super::all_alpha("abcDEF"));
+            assert!(!super::all_alpha("12a45"));
+            assert!(!super::all_alpha("ab1"));
+            assert!(super::all_alpha(""));
+        });
+    }
+
+    #[test]
+    fn all_alpha_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!super::all_alpha("caf\u{00E9}"));
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert!(super::all_alpha("caf\u{00E9}"));
+        });
+    }
+
+    #[test]
+    fn classifier_cache() {
+        with_env_locale("POSIX", || {
+            let cache = ClassifierCache::new();
+            assert!(cache.is("alpha", 'a'));
+            assert!(!cache.is("alpha", '1'));
+            assert!(cache.is("digit", '1'));
+            assert!(!cache.is("bogus", 'a'));
+            assert!(!cache.is("", 'a'));
+            assert!(!cache.is("al\0pha", 'a'));
+        });
+    }
+
+    #[test]
+    fn classifier_cache_reuse() {
+        with_env_locale("en_US.UTF-8", || {
+            let cache = ClassifierCache::new();
+            let alpha = cache.class("alpha");
+            let digit = cache.class("digit");
+            let s = "abc123\u{00E4}\u{4E16}".repeat(10000);
+            let count = s
                 .chars()
-                .next()
-                .unwrap()
+                .filter(|&c| cache.is("alpha", c) || cache.is("digit", c))
+                .count();
+            assert_eq!(count, 80000);
+            assert_eq!(cache.classes.borrow().len(), 2);
+            assert_eq!(cache.class("alpha"), alpha);
+            assert_eq!(cache.class("digit"), digit);
+        });
+    }
+
+    #[test]
+    fn classifier_cache_i18n() {
+        let posix = with_env_locale("POSIX", ClassifierCache::new);
+        let en_us = with_env_locale("en_US", || {
+            let en_us = ClassifierCache::new();
+            assert!(!posix.is("alpha", '\u{00E4}'));
+            assert!(en_us.is("alpha", '\u{00E4}'));
+            en_us
+        });
+        with_env_locale("POSIX", || {
+            assert!(en_us.is("alpha", '\u{00E4}'));
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn classifier_cache_special() {
+        let ja_jp = with_env_locale("ja_JP.UTF-8", ClassifierCache::new);
+        with_env_locale("en_US", || {
+            let en_us = ClassifierCache::new();
+            assert!(ja_jp.is("jkata", '\u{30AB}'));
+            assert!(!ja_jp.is("jkata", '\u{304B}'));
+            assert!(ja_jp.is("jhira", '\u{304B}'));
+            assert!(!en_us.is("jkata", '\u{30AB}'));
+        });
+    }
+
+    #[test]
+    fn char_trans() {
+        with_env_locale("POSIX", || {
+            let toupper = super::char_trans("toupper").unwrap();
+            let tolower = super::char_trans("tolower").unwrap();
+            assert_eq!('a'.transform_char(toupper), 'A');
+            assert_eq!('A'.transform_char(tolower), 'a');
+            assert_eq!('1'.transform_char(toupper), '1');
+            assert_eq!(super::char_trans("bogus"), None);
+            assert_eq!(super::char_trans("to\0upper"), None);
+        });
+    }
+
+    #[test]
+    fn char_trans_i18n() {
+        for locale in ["POSIX", "en_US"] {
+            with_env_locale(locale, || {
+                let toupper = super::char_trans("toupper").unwrap();
+                let tolower = super::char_trans("tolower").unwrap();
+                for c in ['a', '\u{00E4}', '\u{017F}', '\u{0131}', '\u{00DF}'] {
+                    assert_eq!(c.transform_char(toupper), CType::to_uppercase(&c));
+                }
+                for c in ['A', '\u{00C4}', '\u{0190}', '\u{0130}'] {
+                    assert_eq!(c.transform_char(tolower), CType::to_lowercase(&c));
+                }
+            });
         }
-        status => panic!("wctochar failed. status={}, error={}", status, errno()),
     }
-}
 
-fn isspace(wc: wchar_t) -> bool {
-    match unsafe { c::iswspace_native(wc.into()) } {
-        s if s >= 0 => s != 0,
-        _ => panic!("iswspace_native failed. error={}", errno()),
+    #[test]
+    #[ignore]
+    fn char_trans_special() {
+        with_env_locale("en_US", || {
+            assert_eq!(super::char_trans("tojkata"), None);
+        });
+        with_env_locale("ja_JP.UTF-8", || {
+            let tojkata = super::char_trans("tojkata").unwrap();
+            let tojhira = super::char_trans("tojhira").unwrap();
+            assert_eq!('\u{304B}'.transform_char(tojkata), '\u{30AB}');
+            assert_eq!('\u{30AB}'.transform_char(tojhira), '\u{304B}');
+            assert_eq!('a'.transform_char(tojkata), 'a');
+        });
     }
-}
 
-fn isblank(wc: wchar_t) -> bool {
-    unsafe { c::iswblank_native(wc.into()) != 0 }
-}
+    #[test]
+    fn eq_ignore_case_locale() {
+        with_env_locale("POSIX", || {
+            assert!("Hello, World".eq_ignore_case_locale("hello, world"));
+            assert!("".eq_ignore_case_locale(""));
+            assert!(!"Hello".eq_ignore_case_locale("Hell"));
+            assert!(!"a".eq_ignore_case_locale("b"));
+            assert!(!"\u{00C4}".eq_ignore_case_locale("\u{00E4}"));
+        });
+        with_env_locale("en_US", || {
+            assert!("\u{00C4}".eq_ignore_case_locale("\u{00E4}"));
+            assert!("I".eq_ignore_case_locale("i"));
+            assert!("\u{039F}\u{03A3}".eq_ignore_case_locale("\u{03BF}\u{03C2}"));
+        });
+    }
 
-fn toupper(wc: wchar_t) -> wchar_t {
-    unsafe { c::towupper_native(wc.into()) as wchar_t }
-}
+    #[test]
+    #[ignore]
+    fn eq_ignore_case_locale_special() {
+        with_env_locale("en_US", || {
+            assert!("I".eq_ignore_case_locale("i"));
+            assert!(!"I".eq_ignore_case_locale("\u{0131}"));
+        });
+        with_env_locale("tr_TR", || {
+            assert!(!"I".eq_ignore_case_locale("i"));
+            assert!("I".eq_ignore_case_locale("\u{0131}"));
+            assert!("\u{0130}".eq_ignore_case_locale("i"));
+        });
+    }
 
-fn tolower(wc: wchar_t) -> wchar_t {
-    unsafe { c::towlower_native(wc.into()) as wchar_t }
-}
+    #[test]
+    fn byte_ctype() {
+        with_env_locale("POSIX", || {
+            assert!(b' '.is_space());
+            assert!(b'\t'.is_blank());
+            assert!(b'a'.is_alpha());
+            assert!(b'7'.is_digit());
+            assert!(b'z'.is_alnum());
+            assert!(b'!'.is_punct());
+            assert!(0x7Fu8.is_cntrl());
+            assert!(b' '.is_print());
+            assert!(!b' '.is_graph());
+            assert!(b'F'.is_xdigit());
+            assert!(b'q'.is_lower());
+            assert!(b'Q'.is_upper());
+            assert_eq!(b'a'.classify(), super::classify_all("a")[0]);
+            assert_eq!(ByteCType::to_uppercase(&b'a'), b'A');
+            assert_eq!(ByteCType::to_lowercase(&b'A'), b'a');
+            assert_eq!(ByteCType::to_uppercase(&b'1'), b'1');
+            for b in 0x80u8..=0xFF {
+                assert!(b.classify().is_empty());
+                assert_eq!(ByteCType::to_uppercase(&b), b);
+            }
+        });
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn byte_ctype_i18n() {
+        with_env_locale("POSIX", || {
+            assert!(!0xE9u8.is_alpha());
+            assert!(!0xE9u8.is_lower());
+            assert!(!0xA0u8.is_space());
+            assert_eq!(ByteCType::to_uppercase(&0xE9u8), 0xE9);
+        });
+        with_env_locale("en_US", || {
+            assert!(0xE9u8.is_alpha());
+            assert!(0xE9u8.is_lower());
+            assert!(0xC9u8.is_upper());
+            assert!(!0xE9u8.is_digit());
+            assert!(0xD7u8.is_punct());
+            assert_eq!(ByteCType::to_uppercase(&0xE9u8), 0xC9);
+            assert_eq!(ByteCType::to_lowercase(&0xC9u8), 0xE9);
+            assert_eq!(ByteCType::to_uppercase(&0xFFu8), 0xFF);
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert!(!0xE9u8.is_alpha());
+            assert!(b'a'.is_alpha());
+        });
+    }
 
     #[test]
-    fn is_always_space() {
-        assert!(' '.is_space());
-        assert!('\x0c'.is_space());
-        assert!('\n'.is_space());
-        assert!('\r'.is_space());
-        assert!('\t'.is_space());
-        assert!('\x0b'.is_space());
+    fn is_ideographic() {
+        for locale in ["POSIX", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                assert!(!'\u{6F22}'.is_ideographic());
+                assert!(!'a'.is_ideographic());
+                assert!(!'\u{3042}'.is_kana());
+                assert!(!'\u{30A2}'.is_kana());
+            });
+        }
     }
 
     #[test]
-    fn is_space_i18n() {
-        std::env::set_var("LC_ALL", "POSIX");
-        assert!(!'\u{1680}'.is_space());
-        assert!(!'\u{2000}'.is_space());
-        assert!(!'\u{2006}'.is_space());
-        assert!(!'\u{2008}'.is_space());
-        assert!(!'\u{200A}'.is_space());
-        assert!(!'\u{2028}'.is_space());
-        assert!(!'\u{2029}'.is_space());
-        assert!(!'\u{205F}'.is_space());
-        assert!(!'\u{3000}'.is_space());
-        std::env::set_var("LC_ALL", "en_US");
-        assert!('\u{1680}'.is_space());
-        assert!('\u{2000}'.is_space());
-        assert!('\u{2006}'.is_space());
-        assert!('\u{2008}'.is_space());
-        assert!('\u{200A}'.is_space());
-        assert!('\u{2028}'.is_space());
-        assert!('\u{2029}'.is_space());
-        assert!('\u{205F}'.is_space());
-        assert!('\u{3000}'.is_space());
+    #[ignore]
+    fn is_ideographic_special() {
+        with_env_locale("ja_JP.UTF-8", || {
+            assert!('\u{6F22}'.is_ideographic());
+            assert!(!'\u{3042}'.is_ideographic());
+            assert!(!'a'.is_ideographic());
+            assert!('\u{3042}'.is_kana());
+            assert!('\u{30A2}'.is_kana());
+            assert!(!'\u{6F22}'.is_kana());
+            assert!(!'a'.is_kana());
+        });
+        with_env_locale("ja_JP", || {
+            assert!('\u{6F22}'.is_ideographic());
+            assert!('\u{30A2}'.is_kana());
+        });
+    }
+
+    #[test]
+    fn space_chars() {
+        let posix = with_env_locale("POSIX", super::space_chars);
+        assert_eq!(posix, ['\t', '\n', '\u{0B}', '\u{0C}', '\r', ' ']);
+        with_env_locale("en_US.UTF-8", || {
+            let english = super::space_chars();
+            assert!(english.contains(&' '));
+            assert!(english.contains(&'\u{2003}'));
+            assert_ne!(posix, english);
+            assert!(english.iter().all(|c| c.is_space()));
+        });
+    }
+
+    #[test]
+    fn punct_chars() {
+        let posix = with_env_locale("POSIX", super::punct_chars);
+        let ascii: Vec<_> = ('\0'..='\u{7F}')
+            .filter(|c| c.is_ascii_punctuation())
+            .collect();
+        assert_eq!(posix, ascii);
+        with_env_locale("en_US.UTF-8", || {
+            let english = super::punct_chars();
+            assert!(english.contains(&'!'));
+            assert!(english.contains(&'\u{00A7}'));
+            assert!(!english.contains(&'a'));
+            assert!(english.len() > posix.len());
+            assert!(english.iter().all(|c| c.is_punct()));
+        });
+    }
+
+    #[test]
+    fn to_uppercase_expanded() {
+        with_env_locale("POSIX", || {
+            assert_eq!('a'.to_uppercase_expanded(), "A");
+            assert_eq!('1'.to_uppercase_expanded(), "1");
+            assert_eq!('\u{00DF}'.to_uppercase_expanded(), "\u{00DF}");
+            assert_eq!('\u{FB01}'.to_uppercase_expanded(), "\u{FB01}");
+        });
+    }
+
+    #[test]
+    fn to_uppercase_expanded_i18n() {
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!('a'.to_uppercase_expanded(), "A");
+            assert_eq!('\u{00E4}'.to_uppercase_expanded(), "\u{00C4}");
+            assert_eq!('\u{00DF}'.to_uppercase_expanded(), "SS");
+            assert_eq!('\u{FB01}'.to_uppercase_expanded(), "FI");
+            assert_eq!('i'.to_uppercase_expanded(), "I");
+            assert_eq!('A'.to_uppercase_expanded(), "A");
+            for c in ['a', '\u{00DF}', '\u{0149}', '\u{03C3}'] {
+                assert_eq!(c.to_uppercase_expanded(), c.to_string().to_uppercase_str());
+            }
+        });
     }
 
     #[test]
     #[ignore]
-    fn is_space_special() {
-        std::env::set_var("LC_ALL", "en_US");
-        assert!(!'\u{1361}'.is_space());
-        std::env::set_var("LC_ALL", "am_ET");
-        assert!('\u{1361}'.is_space());
+    fn to_uppercase_expanded_special() {
+        with_env_locale("tr_TR.UTF-8", || {
+            assert_eq!('i'.to_uppercase_expanded(), "\u{0130}");
+            assert_eq!('\u{0131}'.to_uppercase_expanded(), "I");
+            assert_eq!('\u{00DF}'.to_uppercase_expanded(), "SS");
+        });
+        with_env_locale("de_DE.UTF-8", || {
+            assert_eq!('\u{00DF}'.to_uppercase_expanded(), "SS");
+        });
     }
 
     #[test]
-    fn is_blank() {
-        std::env::set_var("LC_ALL", "POSIX");
-        assert!(' '.is_blank());
-        assert!('\t'.is_blank());
-        assert!(!'\n'.is_blank());
-        assert!(!'\u{3000}'.is_blank());
-        std::env::set_var("LC_ALL", "en_US");
-        assert!('\u{3000}'.is_blank());
-        assert!(!'\u{2028}'.is_blank());
+    fn is_space_in() {
+        with_env_locale("en_US.UTF-8", || {
+            assert!(' '.is_space_in("POSIX"));
+            assert!(!'a'.is_space_in("POSIX"));
+            assert!(!'\u{2003}'.is_space_in("POSIX"));
+            assert!(!'\u{2003}'.is_space_in("C"));
+            assert!('\u{2003}'.is_space_in("en_US.UTF-8"));
+            assert!('\u{2003}'.is_space_in("en_US.UTF-8"));
+        });
+        with_env_locale("POSIX", || {
+            assert!('\u{2003}'.is_space_in("en_US.UTF-8"));
+            assert!(!'\u{2003}'.is_space());
+        });
+    }
+
+    #[test]
+    fn is_space_in_threads() {
+        let threads: Vec<_> = [("en_US.UTF-8", true), ("POSIX", false)]
+            .iter()
+            .map(|&(locale, expected)| {
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        assert_eq!('\u{2003}'.is_space_in(locale), expected, "{}", locale);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn is_space_in_missing() {
+        ' '.is_space_in("xx_XX");
+    }
+
+    #[test]
+    fn collate_cmp() {
+        use std::cmp::Ordering;
+
+        with_env_locale("POSIX", || {
+            assert_eq!('a'.collate_cmp(&'b'), Ordering::Less);
+            assert_eq!('b'.collate_cmp(&'a'), Ordering::Greater);
+            assert_eq!('a'.collate_cmp(&'a'), Ordering::Equal);
+            assert_eq!('B'.collate_cmp(&'a'), Ordering::Less);
+        });
+    }
+
+    #[test]
+    fn collate_cmp_i18n() {
+        use std::cmp::Ordering;
+
+        with_env_locale("POSIX", || {
+            assert_eq!('\u{00E4}'.collate_cmp(&'z'), Ordering::Greater);
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!('\u{00E4}'.collate_cmp(&'z'), Ordering::Less);
+            assert_eq!('a'.collate_cmp(&'\u{00E4}'), Ordering::Less);
+            assert_eq!('B'.collate_cmp(&'a'), Ordering::Greater);
+            let mut chars = vec!['z', '\u{00E4}', 'B', 'a'];
+            chars.sort_by(|a, b| a.collate_cmp(b));
+            assert_eq!(chars, ['a', '\u{00E4}', 'B', 'z']);
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn collate_cmp_special() {
+        use std::cmp::Ordering;
+
+        with_env_locale("de_DE.UTF-8", || {
+            assert_eq!('\u{00E4}'.collate_cmp(&'z'), Ordering::Less);
+        });
+        with_env_locale("sv_SE", || {
+            assert_eq!('\u{00E4}'.collate_cmp(&'z'), Ordering::Greater);
+        });
+    }
+
+    #[test]
+    fn to_casefold() {
+        with_env_locale("POSIX", || {
+            assert_eq!("Hello, WORLD".to_casefold(), "hello, world");
+            assert_eq!("".to_casefold(), "");
+            assert_eq!("Stra\u{00DF}e".to_casefold(), "stra\u{00DF}e");
+        });
+    }
+
+    #[test]
+    fn to_casefold_i18n() {
+        with_env_locale("POSIX", || {
+            assert_ne!("Stra\u{00DF}e".to_casefold(), "STRASSE".to_casefold());
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!("Stra\u{00DF}e".to_casefold(), "strasse");
+            assert_eq!("STRASSE".to_casefold(), "strasse");
+            assert_eq!("\u{017F}".to_casefold(), "s");
+            assert_eq!(
+                "\u{039F}\u{0394}\u{039F}\u{03A3}".to_casefold(),
+                "\u{03BF}\u{03B4}\u{03BF}\u{03C3}"
+            );
+            assert_eq!(
+                "\u{03BF}\u{03B4}\u{03BF}\u{03C2}".to_casefold(),
+                "\u{03BF}\u{03B4}\u{03BF}\u{03C3}"
+            );
+            assert_eq!("I".to_casefold(), "i");
+            assert_eq!("\u{0131}".to_casefold(), "i");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn to_casefold_special() {
+        with_env_locale("de_DE.UTF-8", || {
+            assert_eq!("Stra\u{00DF}e".to_casefold(), "STRASSE".to_casefold());
+        });
+        with_env_locale("tr_TR", || {
+            assert_eq!("I".to_casefold(), "\u{0131}");
+            assert_eq!("\u{0131}".to_casefold(), "\u{0131}");
+            assert_eq!("i".to_casefold(), "i");
+            assert_eq!("\u{0130}".to_casefold(), "i");
+            assert_ne!("I".to_casefold(), "i".to_casefold());
+        });
+    }
+
+    #[test]
+    fn digit_value() {
+        with_env_locale("POSIX", || {
+            for (i, c) in ('0'..='9').enumerate() {
+                assert_eq!(c.digit_value(), Some(i as u32));
+            }
+            assert_eq!('a'.digit_value(), None);
+            assert_eq!('A'.digit_value(), None);
+            assert_eq!(' '.digit_value(), None);
+            assert_eq!('\u{00B2}'.digit_value(), None);
+        });
+    }
+
+    #[test]
+    fn digit_value_i18n() {
+        for locale in ["POSIX", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                assert_eq!('7'.digit_value(), Some(7));
+                assert_eq!('\u{FF17}'.digit_value(), None);
+            });
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn digit_value_special() {
+        with_env_locale("ja_JP.UTF-8", || {
+            assert_eq!('7'.digit_value(), Some(7));
+            assert_eq!('\u{FF10}'.digit_value(), Some(0));
+            assert_eq!('\u{FF17}'.digit_value(), Some(7));
+            assert_eq!('\u{FF41}'.digit_value(), None);
+        });
+    }
+
+    fn individual_flags(c: char) -> CharFlags {
+        let mut flags = CharFlags::empty();
+        if CType::is_space(&c) {
+            flags |= CharFlags::SPACE;
+        }
+        if CType::is_blank(&c) {
+            flags |= CharFlags::BLANK;
+        }
+        if CType::is_alpha(&c) {
+            flags |= CharFlags::ALPHA;
+        }
+        if CType::is_digit(&c) {
+            flags |= CharFlags::DIGIT;
+        }
+        if CType::is_alnum(&c) {
+            flags |= CharFlags::ALNUM;
+        }
+        if CType::is_punct(&c) {
+            flags |= CharFlags::PUNCT;
+        }
+        if CType::is_cntrl(&c) {
+            flags |= CharFlags::CNTRL;
+        }
+        if CType::is_print(&c) {
+            flags |= CharFlags::PRINT;
+        }
+        if CType::is_graph(&c) {
+            flags |= CharFlags::GRAPH;
+        }
+        if CType::is_xdigit(&c) {
+            flags |= CharFlags::XDIGIT;
+        }
+        if CType::is_lower(&c) {
+            flags |= CharFlags::LOWER;
+        }
+        if CType::is_upper(&c) {
+            flags |= CharFlags::UPPER;
+        }
+        flags
+    }
+
+    #[test]
+    fn char_flags() {
+        let flags = CharFlags::ALPHA | CharFlags::LOWER;
+        assert!(flags.contains(CharFlags::ALPHA));
+        assert!(flags.contains(CharFlags::LOWER | CharFlags::ALPHA));
+        assert!(!flags.contains(CharFlags::UPPER));
+        assert_eq!(flags & CharFlags::LOWER, CharFlags::LOWER);
+        assert!((flags & CharFlags::DIGIT).is_empty());
+        assert_eq!(CharFlags::default(), CharFlags::empty());
+        assert_eq!(CharFlags::ALPHA.bits(), 1 << 2);
+    }
+
+    #[test]
+    fn classify_all() {
+        let s = "aZ9 \t\n\0!f\u{00E4}\u{00C4}\u{3000}\u{4E16}\u{FF10}\u{0085}\u{3002}";
+        for locale in ["POSIX", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                let flags = super::classify_all(s);
+                assert_eq!(flags.len(), s.chars().count());
+                for (c, flags) in s.chars().zip(flags) {
+                    assert_eq!(flags, individual_flags(c), "{:?} in {}", c, locale);
+                }
+            });
+        }
+        assert!(super::classify_all("").is_empty());
+    }
+
+    #[test]
+    fn classify_all_i18n() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::classify_all("\u{00E4}"), [CharFlags::empty()]);
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(
+                super::classify_all("\u{00E4}"),
+                [CharFlags::ALPHA
+                    | CharFlags::ALNUM
+                    | CharFlags::PRINT
+                    | CharFlags::GRAPH
+                    | CharFlags::LOWER]
+            );
+        });
+    }
+
+    #[test]
+    fn to_titlecase_str() {
+        with_env_locale("POSIX", || {
+            assert_eq!("hello world".to_titlecase(), "Hello world");
+            assert_eq!("HELLO WORLD".to_titlecase(), "Hello world");
+            assert_eq!("  \"hello\"".to_titlecase(), "  \"Hello\"");
+            assert_eq!("123abc".to_titlecase(), "123Abc");
+            assert_eq!("".to_titlecase(), "");
+            assert_eq!(" 1!".to_titlecase(), " 1!");
+            assert_eq!("\u{00E9}COLE".to_titlecase(), "\u{00E9}Cole");
+            assert_eq!("\u{00DF}".to_titlecase(), "\u{00DF}");
+            assert_eq!("\u{01C6}".to_titlecase(), "\u{01C6}");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!("hello world".to_titlecase(), "Hello world");
+            assert_eq!("\u{00E9}COLE".to_titlecase(), "\u{00C9}cole");
+            assert_eq!("istanbul".to_titlecase(), "Istanbul");
+            assert_eq!("\u{00DF}".to_titlecase(), "Ss");
+            assert_eq!("\u{00DF}A".to_titlecase(), "Ssa");
+            assert_eq!("\u{03BF}\u{03A3}".to_titlecase(), "\u{039F}\u{03C2}");
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!("\u{00DF}".to_titlecase(), "Ss");
+            assert_eq!("\u{01C6}".to_titlecase(), "\u{01C5}");
+            assert_eq!("\u{01C4}UNGLA".to_titlecase(), "\u{01C5}ungla");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn to_titlecase_str_special() {
+        with_env_locale("tr_TR", || {
+            assert_eq!("istanbul".to_titlecase(), "\u{0130}stanbul");
+            assert_eq!("ILIK".to_titlecase(), "Il\u{0131}k");
+        });
+    }
+
+    #[test]
+    fn to_titlecase() {
+        with_env_locale("POSIX", || {
+            assert_eq!('a'.to_titlecase(), 'A');
+            assert_eq!('A'.to_titlecase(), 'A');
+            assert_eq!('1'.to_titlecase(), '1');
+            assert_eq!(' '.to_titlecase(), ' ');
+            assert_eq!('\u{01C6}'.to_titlecase(), '\u{01C6}');
+        });
+    }
+
+    #[test]
+    fn to_titlecase_i18n() {
+        with_env_locale("en_US", || {
+            assert_eq!('a'.to_titlecase(), 'A');
+            assert_eq!('1'.to_titlecase(), '1');
+            for (c, title) in [
+                ('\u{01C4}', '\u{01C5}'),
+                ('\u{01C5}', '\u{01C5}'),
+                ('\u{01C6}', '\u{01C5}'),
+                ('\u{01C7}', '\u{01C8}'),
+                ('\u{01C9}', '\u{01C8}'),
+                ('\u{01CA}', '\u{01CB}'),
+                ('\u{01CC}', '\u{01CB}'),
+                ('\u{01F1}', '\u{01F2}'),
+                ('\u{01F3}', '\u{01F2}'),
+            ] {
+                assert_eq!(c.to_titlecase(), title, "{:?}", c);
+            }
+            assert_eq!(CType::to_uppercase(&'\u{01C6}'), '\u{01C4}');
+        });
+    }
+
+    #[test]
+    fn uppercase_chars() {
+        for locale in ["POSIX", "en_US"] {
+            with_env_locale(locale, || {
+                let s = "Hello, caf\u{00E9} \u{0131}\u{017F} 123";
+                assert_eq!(
+                    super::uppercase_chars(s).collect::<String>(),
+                    s.to_uppercase_str()
+                );
+            });
+        }
+        assert_eq!(super::uppercase_chars("").next(), None);
+        assert_eq!(
+            super::uppercase_chars("abc").rev().collect::<String>(),
+            "CBA"
+        );
+    }
+
+    #[test]
+    fn uppercase_chars_lazy() {
+        let mut chars = with_env_locale("POSIX", || {
+            let mut chars = super::uppercase_chars("\u{00E9}\u{00E9}\u{00E9}");
+            assert_eq!(chars.next(), Some('\u{00E9}'));
+            chars
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(chars.next(), Some('\u{00C9}'));
+            assert_eq!(chars.size_hint().0, 1);
+        });
+    }
+
+    #[test]
+    fn split_words_locale() {
+        with_env_locale("POSIX", || {
+            assert_eq!(
+                super::split_words_locale("  hello \t world\n").collect::<Vec<_>>(),
+                ["hello", "world"]
+            );
+            assert_eq!(super::split_words_locale("").next(), None);
+            assert_eq!(super::split_words_locale(" \t\n ").next(), None);
+            assert_eq!(
+                super::split_words_locale("one").collect::<Vec<_>>(),
+                ["one"]
+            );
+            let mut words = super::split_words_locale("a ");
+            assert_eq!(words.next(), Some("a"));
+            assert_eq!(words.next(), None);
+            assert_eq!(words.next(), None);
+        });
+    }
+
+    #[test]
+    fn split_words_locale_i18n() {
+        let s = "\u{3000}caf\u{00E9}\u{3000}au\u{2003}lait ";
+        with_env_locale("POSIX", || {
+            assert_eq!(
+                super::split_words_locale(s).collect::<Vec<_>>(),
+                ["\u{3000}caf\u{00E9}\u{3000}au\u{2003}lait"]
+            );
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(
+                super::split_words_locale(s).collect::<Vec<_>>(),
+                ["caf\u{00E9}", "au", "lait"]
+            );
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn split_words_locale_special() {
+        let s = "\u{12A0}\u{1361}\u{12A0}\u{1361}\u{1361}\u{12A0}";
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(super::split_words_locale(s).collect::<Vec<_>>(), [s]);
+        });
+        with_env_locale("am_ET", || {
+            assert_eq!(
+                super::split_words_locale(s).collect::<Vec<_>>(),
+                ["\u{12A0}", "\u{12A0}", "\u{12A0}"]
+            );
+        });
     }
 
     #[test]
     fn to_uppercase() {
         assert_eq!(CType::to_uppercase(&'a'), 'A');
         assert_eq!(CType::to_uppercase(&'1'), '1');
-        std::env::set_var("LC_ALL", "POSIX");
-        assert_eq!(CType::to_uppercase(&'\u{017F}'), '\u{017F}');
-        std::env::set_var("LC_ALL", "en_US");
-        assert_eq!(CType::to_uppercase(&'\u{017F}'), 'S');
+        with_env_locale("POSIX", || {
+            assert_eq!(CType::to_uppercase(&'\u{017F}'), '\u{017F}');
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(CType::to_uppercase(&'\u{017F}'), 'S');
+        });
+    }
+
+    #[test]
+    fn try_to_uppercase() {
+        with_env_locale("en_US", || {
+            assert_eq!('a'.try_to_uppercase(), Ok('A'));
+            assert_eq!('\u{017F}'.try_to_uppercase(), Ok('S'));
+        });
+        with_env_locale("xx_XX.UTF-8", || {
+            assert!(matches!(
+                'a'.try_to_uppercase(),
+                Err(LocaleError::LocaleNotFound { .. })
+            ));
+        });
+    }
+
+    #[test]
+    fn try_to_lowercase() {
+        with_env_locale("en_US", || {
+            assert_eq!('A'.try_to_lowercase(), Ok('a'));
+            assert_eq!('\u{0190}'.try_to_lowercase(), Ok('\u{025B}'));
+        });
+        with_env_locale("xx_XX.UTF-8", || {
+            assert!(matches!(
+                'A'.try_to_lowercase(),
+                Err(LocaleError::LocaleNotFound { .. })
+            ));
+        });
+    }
+
+    #[test]
+    fn try_case_conversion_non_ascii() {
+        // Conversions go through UTF-8 regardless of the codeset of the current locale,
+        // so characters outside the codeset are returned unmodified.
+        with_env_locale("C", || {
+            assert_eq!('\u{4E16}'.try_to_uppercase(), Ok('\u{4E16}'));
+            assert_eq!('\u{4E16}'.try_to_lowercase(), Ok('\u{4E16}'));
+            assert_eq!('\u{00C4}'.try_to_lowercase(), Ok('\u{00C4}'));
+        });
+        with_env_locale("en_US", || {
+            assert_eq!('\u{4E16}'.try_to_uppercase(), Ok('\u{4E16}'));
+        });
     }
 
     #[test]
     #[ignore]
     fn to_uppercase_special() {
-        std::env::set_var("LC_ALL", "en_US");
-        assert_eq!(CType::to_uppercase(&'i'), 'I');
-        std::env::set_var("LC_ALL", "tr_TR");
-        assert_eq!(CType::to_uppercase(&'i'), '\u{0130}');
+        with_env_locale("en_US", || {
+            assert_eq!(CType::to_uppercase(&'i'), 'I');
+        });
+        with_env_locale("tr_TR", || {
+            assert_eq!(CType::to_uppercase(&'i'), '\u{0130}');
+        });
     }
 
     #[test]
     fn to_lowercase() {
         assert_eq!(CType::to_lowercase(&'A'), 'a');
         assert_eq!(CType::to_lowercase(&'1'), '1');
-        std::env::set_var("LC_ALL", "POSIX");
-        assert_eq!(CType::to_lowercase(&'\u{0190}'), '\u{0190}');
-        std::env::set_var("LC_ALL", "en_US");
-        assert_eq!(CType::to_lowercase(&'\u{0190}'), '\u{025b}');
+        with_env_locale("POSIX", || {
+            assert_eq!(CType::to_lowercase(&'\u{0190}'), '\u{0190}');
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(CType::to_lowercase(&'\u{0190}'), '\u{025b}');
+        });
+    }
+
+    #[test]
+    fn to_lowercase_i18n() {
+        with_env_locale("POSIX", || {
+            assert_eq!(CType::to_lowercase(&'\u{0130}'), '\u{0130}');
+            assert_eq!(CType::to_lowercase(&'\u{00C9}'), '\u{00C9}');
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(CType::to_lowercase(&'\u{0130}'), 'i');
+            assert_eq!(CType::to_lowercase(&'\u{00C9}'), '\u{00E9}');
+            assert_eq!(CType::to_lowercase(&'\u{00E9}'), '\u{00E9}');
+        });
     }
 
     #[test]
     #[ignore]
     fn to_lowercase_special() {
-        std::env::set_var("LC_ALL", "en_US");
-        assert_eq!(CType::to_lowercase(&'I'), 'i');
-        std::env::set_var("LC_ALL", "tr_TR");
-        assert_eq!(CType::to_lowercase(&'I'), '\u{0131}');
+        with_env_locale("en_US", || {
+            assert_eq!(CType::to_lowercase(&'I'), 'i');
+        });
+        with_env_locale("tr_TR", || {
+            assert_eq!(CType::to_lowercase(&'I'), '\u{0131}');
+            assert_eq!(CType::to_lowercase(&'\u{0130}'), 'i');
+        });
+    }
+
+    #[test]
+    fn utf8towc() {
+        assert_eq!(super::utf8towc(b"a"), Ok(0x61));
+        assert_eq!(super::utf8towc(b"\0"), Ok(0));
+        assert_eq!(super::utf8towc("\u{3042}".as_bytes()), Ok(0x3042));
+        assert_eq!(super::utf8towc("\u{1F600}".as_bytes()), Ok(0x1F600));
+    }
+
+    #[test]
+    fn utf8towc_incomplete() {
+        assert_eq!(super::utf8towc(b""), Err(LocaleError::IncompleteSequence));
+        assert_eq!(
+            super::utf8towc(b"\xE3\x81"),
+            Err(LocaleError::IncompleteSequence)
+        );
+        assert_eq!(
+            super::utf8towc(b"\xF0\x9F\x98"),
+            Err(LocaleError::IncompleteSequence)
+        );
+    }
+
+    #[test]
+    fn utf8towc_invalid() {
+        assert_eq!(super::utf8towc(b"\xFF"), Err(LocaleError::InvalidByte));
+        assert_eq!(super::utf8towc(b"\x81"), Err(LocaleError::InvalidByte));
+        assert_eq!(super::utf8towc(b"\xE3\x41"), Err(LocaleError::InvalidByte));
+        assert_eq!(super::utf8towc(b"ab"), Err(LocaleError::InvalidByte));
+    }
+
+    fn predicates(c: char) -> [bool; 12] {
+        [
+            c.is_space(),
+            c.is_blank(),
+            c.is_alpha(),
+            CType::is_digit(&c),
+            c.is_alnum(),
+            c.is_punct(),
+            c.is_cntrl(),
+            c.is_print(),
+            c.is_graph(),
+            c.is_xdigit(),
+            c.is_lower(),
+            c.is_upper(),
+        ]
+    }
+
+    /// Enables the ASCII fast path until dropped, then restores the previous setting even on a panic.
+    ///
+    /// The setting is shared by all threads, so create it only in `with_env_locale`,
+    /// which keeps the other tests of this module from running meanwhile.
+    struct AsciiFastPath(bool);
+
+    impl AsciiFastPath {
+        fn enable() -> AsciiFastPath {
+            AsciiFastPath(ASCII_FAST_PATH.swap(true, Ordering::Relaxed))
+        }
+    }
+
+    impl Drop for AsciiFastPath {
+        fn drop(&mut self) {
+            super::set_ascii_fast_path(self.0);
+        }
+    }
+
+    #[test]
+    fn ascii_fast_path() {
+        for locale in ["POSIX", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                let expected: Vec<_> = (0..0x80u8).map(|b| predicates(b as char)).collect();
+                let actual: Vec<_> = {
+                    let _fast_path = AsciiFastPath::enable();
+                    (0..0x80u8).map(|b| predicates(b as char)).collect()
+                };
+                assert_eq!(actual, expected, "{}", locale);
+            });
+        }
+    }
+
+    #[test]
+    fn ascii_fast_path_multibyte() {
+        with_env_locale("en_US.UTF-8", || {
+            {
+                let _fast_path = AsciiFastPath::enable();
+                assert_eq!(predicates('\u{00E4}'), [false; 12]);
+            }
+            assert!('\u{00E4}'.is_alpha());
+            assert!('\u{00E4}'.is_lower());
+            assert!('\u{3000}'.is_space());
+            assert!(!'\u{00E4}'.is_upper());
+        });
+    }
+
+    #[test]
+    fn ascii_fast_path_panic() {
+        with_env_locale("en_US.UTF-8", || {
+            let result = std::panic::catch_unwind(|| {
+                let _fast_path = AsciiFastPath::enable();
+                panic!("test");
+            });
+            assert!(result.is_err());
+            assert!('\u{00E4}'.is_alpha());
+        });
     }
 }
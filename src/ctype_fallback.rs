@@ -0,0 +1,268 @@
+//! Character classification and case mapping by the `char` methods of Rust, used with the `no-native` feature.
+//!
+//! With the `no-native` feature, the C library is not built and [`CType`] is implemented without the current locale.
+//! The classes and the mappings are those of Unicode, so the results for ASCII characters are the same as those in the
+//! POSIX locale, but the results for other characters may differ from any locale:
+//!
+//! - [`CType::is_space`] is [`char::is_whitespace`], which is true for e.g. U+00A0 (No-Break Space).
+//! - [`CType::is_alpha`], [`CType::is_lower`] and [`CType::is_upper`] are [`char::is_alphabetic`],
+//!   [`char::is_lowercase`] and [`char::is_uppercase`].
+//! - [`CType::is_digit`] and [`CType::is_xdigit`] are true only for ASCII digits, as in glibc.
+//! - [`CType::is_punct`] is true for all graphic characters which are not alphanumeric, including symbols.
+//! - [`CType::to_uppercase`] and [`CType::to_lowercase`] are locale-independent, e.g. 'i' is always converted to 'I'.
+//!
+//! The functions depending on the locale data, such as collation, numeric and monetary formatting, are not available.
+
+use crate::LocaleError;
+
+pub trait CType {
+    /// Returns `true` if `self` is a whitespace character as [`char::is_whitespace`].
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert!(CType::is_space(&' '));
+    /// assert!(CType::is_space(&'\u{2003}'));
+    /// assert!(!CType::is_space(&'a'));
+    /// ```
+    fn is_space(&self) -> bool;
+
+    /// Fallible version of [`CType::is_space`]. This never fails.
+    fn try_is_space(&self) -> Result<bool, LocaleError>;
+
+    /// Returns `true` if `self` is a whitespace character other than line and page separators, e.g. space and tab.
+    fn is_blank(&self) -> bool;
+
+    /// Returns `true` if `self` is alphabetic as [`char::is_alphabetic`].
+    fn is_alpha(&self) -> bool;
+
+    /// Returns `true` if `self` is an ASCII decimal digit.
+    fn is_digit(&self) -> bool;
+
+    /// Returns the value of `self` as a decimal digit.
+    ///
+    /// Returns `None` if `self` is not a decimal digit.
+    fn digit_value(&self) -> Option<u32>;
+
+    /// Returns `true` if `self` is alphabetic or a decimal digit.
+    fn is_alnum(&self) -> bool;
+
+    /// Returns `true` if `self` is a graphic character which is not alphanumeric.
+    fn is_punct(&self) -> bool;
+
+    /// Returns `true` if `self` is a control character as [`char::is_control`].
+    fn is_cntrl(&self) -> bool;
+
+    /// Returns `true` if `self` is not a control character.
+    fn is_print(&self) -> bool;
+
+    /// Returns `true` if `self` is printable and not a whitespace character.
+    fn is_graph(&self) -> bool;
+
+    /// Returns `true` if `self` is an ASCII hexadecimal digit.
+    fn is_xdigit(&self) -> bool;
+
+    /// Returns `true` if `self` is lowercase as [`char::is_lowercase`].
+    fn is_lower(&self) -> bool;
+
+    /// Returns `true` if `self` is uppercase as [`char::is_uppercase`].
+    fn is_upper(&self) -> bool;
+
+    /// Converts `self` to uppercase by [`char::to_uppercase`].
+    ///
+    /// If the uppercase form consists of multiple characters, e.g. "SS" for 'ß', returns unmodified `self`.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::CType;
+    ///
+    /// assert_eq!(CType::to_uppercase(&'a'), 'A');
+    /// assert_eq!(CType::to_uppercase(&'\u{00DF}'), '\u{00DF}');
+    /// ```
+    fn to_uppercase(&self) -> Self;
+
+    /// Fallible version of [`CType::to_uppercase`]. This never fails.
+    fn try_to_uppercase(&self) -> Result<Self, LocaleError>
+    where
+        Self: Sized;
+
+    /// Converts `self` to uppercase by [`char::to_uppercase`], allowing it to expand to multiple characters.
+    fn to_uppercase_expanded(&self) -> String;
+
+    /// Converts `self` to lowercase by [`char::to_lowercase`].
+    ///
+    /// If the lowercase form consists of multiple characters, returns unmodified `self`.
+    fn to_lowercase(&self) -> Self;
+
+    /// Fallible version of [`CType::to_lowercase`]. This never fails.
+    fn try_to_lowercase(&self) -> Result<Self, LocaleError>
+    where
+        Self: Sized;
+
+    /// Converts `self` to titlecase. This is the same as [`CType::to_uppercase`].
+    fn to_titlecase(&self) -> Self;
+}
+
+impl CType for char {
+    fn is_space(&self) -> bool {
+        self.is_whitespace()
+    }
+
+    fn try_is_space(&self) -> Result<bool, LocaleError> {
+        Ok(CType::is_space(self))
+    }
+
+    fn is_blank(&self) -> bool {
+        self.is_whitespace()
+            && !matches!(
+                *self,
+                '\n' | '\x0b' | '\x0c' | '\r' | '\u{85}' | '\u{2028}' | '\u{2029}'
+            )
+    }
+
+    fn is_alpha(&self) -> bool {
+        self.is_alphabetic()
+    }
+
+    fn is_digit(&self) -> bool {
+        self.is_ascii_digit()
+    }
+
+    fn digit_value(&self) -> Option<u32> {
+        if self.is_ascii_digit() {
+            self.to_digit(10)
+        } else {
+            None
+        }
+    }
+
+    fn is_alnum(&self) -> bool {
+        CType::is_alpha(self) || CType::is_digit(self)
+    }
+
+    fn is_punct(&self) -> bool {
+        CType::is_graph(self) && !CType::is_alnum(self)
+    }
+
+    fn is_cntrl(&self) -> bool {
+        self.is_control()
+    }
+
+    fn is_print(&self) -> bool {
+        !self.is_control()
+    }
+
+    fn is_graph(&self) -> bool {
+        CType::is_print(self) && !self.is_whitespace()
+    }
+
+    fn is_xdigit(&self) -> bool {
+        self.is_ascii_hexdigit()
+    }
+
+    fn is_lower(&self) -> bool {
+        self.is_lowercase()
+    }
+
+    fn is_upper(&self) -> bool {
+        self.is_uppercase()
+    }
+
+    fn to_uppercase(&self) -> char {
+        single(char::to_uppercase(*self)).unwrap_or(*self)
+    }
+
+    fn try_to_uppercase(&self) -> Result<char, LocaleError> {
+        Ok(CType::to_uppercase(self))
+    }
+
+    fn to_uppercase_expanded(&self) -> String {
+        char::to_uppercase(*self).collect()
+    }
+
+    fn to_lowercase(&self) -> char {
+        single(char::to_lowercase(*self)).unwrap_or(*self)
+    }
+
+    fn try_to_lowercase(&self) -> Result<char, LocaleError> {
+        Ok(CType::to_lowercase(self))
+    }
+
+    fn to_titlecase(&self) -> char {
+        CType::to_uppercase(self)
+    }
+}
+
+/// Returns the only character of `chars`, or `None` if `chars` has more than one.
+fn single(mut chars: impl Iterator<Item = char>) -> Option<char> {
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Predicate = (
+        fn(&char) -> bool,
+        unsafe extern "C" fn(libc::c_int) -> libc::c_int,
+    );
+
+    #[test]
+    fn ascii() {
+        // The global locale of a Rust program is "C" unless `setlocale` is called.
+        let predicates: [Predicate; 12] = [
+            (CType::is_space, libc::isspace),
+            (CType::is_blank, libc::isblank),
+            (CType::is_alpha, libc::isalpha),
+            (CType::is_digit, libc::isdigit),
+            (CType::is_alnum, libc::isalnum),
+            (CType::is_punct, libc::ispunct),
+            (CType::is_cntrl, libc::iscntrl),
+            (CType::is_print, libc::isprint),
+            (CType::is_graph, libc::isgraph),
+            (CType::is_xdigit, libc::isxdigit),
+            (CType::is_lower, libc::islower),
+            (CType::is_upper, libc::isupper),
+        ];
+        for b in 0..0x80u8 {
+            let c = b as char;
+            for (predicate, expected) in predicates {
+                assert_eq!(predicate(&c), unsafe { expected(b.into()) } != 0, "{:?}", c);
+            }
+            assert_eq!(
+                CType::to_uppercase(&c),
+                unsafe { libc::toupper(b.into()) } as u8 as char
+            );
+            assert_eq!(
+                CType::to_lowercase(&c),
+                unsafe { libc::tolower(b.into()) } as u8 as char
+            );
+        }
+    }
+
+    #[test]
+    fn unicode() {
+        assert!(CType::is_space(&'\u{2003}'));
+        assert!(CType::is_blank(&'\u{2003}'));
+        assert!(!CType::is_blank(&'\u{2028}'));
+        assert!(CType::is_alpha(&'\u{00E4}'));
+        assert!(!CType::is_digit(&'\u{FF11}'));
+        assert_eq!(CType::digit_value(&'7'), Some(7));
+        assert_eq!(CType::digit_value(&'\u{FF17}'), None);
+        assert!(CType::is_punct(&'\u{00A7}'));
+        assert!(!CType::is_punct(&'\u{00E4}'));
+        assert_eq!(CType::to_uppercase(&'\u{00E4}'), '\u{00C4}');
+        assert_eq!(CType::to_uppercase(&'\u{00DF}'), '\u{00DF}');
+        assert_eq!(CType::to_lowercase(&'\u{0130}'), '\u{0130}');
+        assert_eq!(CType::to_titlecase(&'a'), 'A');
+        assert_eq!('\u{00DF}'.to_uppercase_expanded(), "SS");
+        assert_eq!(CType::try_to_uppercase(&'a'), Ok('A'));
+        assert_eq!(CType::try_is_space(&'a'), Ok(false));
+    }
+}
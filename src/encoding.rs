@@ -0,0 +1,821 @@
+//! Conversions between multibyte strings and wide strings dependent on `LC_CTYPE`.
+//!
+//! Multibyte strings are interpreted in the codeset of the current locale, e.g. ISO-8859-1 or EUC-JP,
+//! not necessarily UTF-8.
+//! Since the underlying C API uses NUL-terminated strings, conversions stop at the first NUL character.
+//!
+//! `wchar_t` is assumed to hold a Unicode scalar value, as on glibc.
+//! On targets where `wchar_t` is 16 bits, characters outside the BMP are rejected by [`char_to_wchar`]
+//! instead of being split into surrogates.
+
+use libc::{c_char, wchar_t};
+
+use errno::errno;
+
+use crate::ctype::{utf8_bytes, utf8towc};
+use crate::locale::{newlocale_error, Category, Locale};
+use crate::LocaleError;
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        pub fn mbstowcs_native(
+            dest: *mut libc::wchar_t,
+            n: libc::size_t,
+            src: *const libc::c_char,
+        ) -> libc::ssize_t;
+        pub fn wcstombs_native(
+            dest: *mut libc::c_char,
+            n: libc::size_t,
+            src: *const libc::wchar_t,
+        ) -> libc::ssize_t;
+        pub fn mbstate_new() -> *mut libc::c_void;
+        pub fn mbstate_free(state: *mut libc::c_void);
+        pub fn mbstate_reset(state: *mut libc::c_void);
+        pub fn mbsinit_native(state: *const libc::c_void) -> i8;
+        pub fn mbrtowc_native(
+            wc: *mut libc::wchar_t,
+            s: *const libc::c_char,
+            n: libc::size_t,
+            state: *mut libc::c_void,
+            locale: libc::locale_t,
+        ) -> libc::ssize_t;
+        pub fn wcrtomb_native(
+            dest: *mut libc::c_char,
+            wc: libc::wchar_t,
+            state: *mut libc::c_void,
+            locale: libc::locale_t,
+        ) -> libc::ssize_t;
+        pub fn wctoutf8(utf8_bytes: *mut libc::c_char, wc: libc::wchar_t) -> libc::ssize_t;
+    }
+}
+
+/// Converts the multibyte string `bytes` in the current locale to a wide string.
+///
+/// # errors
+///
+/// Returns [`LocaleError::InvalidMultibyte`] if `bytes` is not a valid multibyte string in the current locale,
+/// or [`LocaleError::LocaleNotFound`] if the locale specified by the environment is not installed.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::encoding::bytes_to_wide;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(bytes_to_wide(b"caf\xE9").unwrap().len(), 4);
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert!(bytes_to_wide(b"caf\xE9").is_err());
+/// ```
+pub fn bytes_to_wide(bytes: &[u8]) -> Result<Vec<wchar_t>, LocaleError> {
+    let mut src = bytes.to_vec();
+    src.push(0);
+    let src = src.as_ptr() as *const c_char;
+    let length = mbstowcs(std::ptr::null_mut(), 0, src)?;
+    let mut wide = vec![0; length + 1];
+    let length = mbstowcs(wide.as_mut_ptr(), wide.len(), src)?;
+    wide.truncate(length);
+    Ok(wide)
+}
+
+/// Converts the wide string `wide` to a multibyte string in the current locale.
+///
+/// # errors
+///
+/// Returns [`LocaleError::ConversionFailed`] if `wide` contains a character which cannot be represented
+/// in the codeset of the current locale,
+/// or [`LocaleError::LocaleNotFound`] if the locale specified by the environment is not installed.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::encoding::{bytes_to_wide, wide_to_bytes};
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let wide = bytes_to_wide(b"caf\xE9").unwrap();
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert_eq!(wide_to_bytes(&wide).unwrap(), "caf\u{00E9}".as_bytes());
+/// ```
+pub fn wide_to_bytes(wide: &[wchar_t]) -> Result<Vec<u8>, LocaleError> {
+    let mut src = wide.to_vec();
+    src.push(0);
+    let length = wcstombs(std::ptr::null_mut(), 0, src.as_ptr())?;
+    let mut bytes = vec![0; length + 1];
+    let length = wcstombs(bytes.as_mut_ptr() as *mut c_char, bytes.len(), src.as_ptr())?;
+    bytes.truncate(length);
+    Ok(bytes)
+}
+
+/// Checks if the codeset of the current locale is UTF-8, e.g. `en_US.UTF-8` but not `en_US`.
+///
+/// The codeset returned by `nl_langinfo(CODESET)` is compared with `"UTF-8"` ignoring case and hyphens,
+/// so `"utf8"` is also accepted.
+/// The multibyte strings of this module and the bytes of [`ByteCType`](crate::ctype::ByteCType) are UTF-8 only if this
+/// returns `true`, while `str` and `char` are converted to and from the codeset by this crate.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::encoding::is_utf8_locale;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert!(is_utf8_locale());
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert!(!is_utf8_locale());
+/// ```
+pub fn is_utf8_locale() -> bool {
+    is_utf8_codeset(&crate::langinfo::codeset())
+}
+
+/// Checks if `codeset` is a spelling of UTF-8.
+fn is_utf8_codeset(codeset: &str) -> bool {
+    codeset.replace('-', "").eq_ignore_ascii_case("utf8")
+}
+
+/// Whether `wchar_t` is 16 bits and holds a UTF-16 code unit, as on Windows.
+const WCHAR_IS_16_BIT: bool = std::mem::size_of::<wchar_t>() == 2;
+
+/// Converts `c` to the C wide character.
+///
+/// Unlike the other functions of this module, the conversion does not depend on the current locale.
+/// The result can be passed to the wide character functions of libc such as `iswalpha`.
+///
+/// # errors
+///
+/// Returns [`LocaleError::UnsupportedCodepoint`] if `c` is outside the BMP and `wchar_t` is 16 bits,
+/// or [`LocaleError::ConversionFailed`] if `c` cannot be represented as `wchar_t` otherwise.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::encoding::char_to_wchar;
+///
+/// assert_eq!(char_to_wchar('a'), Ok(0x61));
+/// assert_eq!(char_to_wchar('\u{1F600}'), Ok(0x1F600));
+/// ```
+pub fn char_to_wchar(c: char) -> Result<wchar_t, LocaleError> {
+    if WCHAR_IS_16_BIT && u32::from(c) > 0xFFFF {
+        return Err(LocaleError::UnsupportedCodepoint { c });
+    }
+    utf8towc(&utf8_bytes(&c))
+}
+
+/// Converts the C wide character `wc` to `char`.
+///
+/// This is the inverse of [`char_to_wchar`].
+///
+/// # errors
+///
+/// Returns [`LocaleError::ConversionFailed`] if `wc` does not correspond to a Unicode scalar value.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::encoding::wchar_to_char;
+///
+/// assert_eq!(wchar_to_char(0x61), Ok('a'));
+/// assert!(wchar_to_char(0xD800).is_err());
+/// ```
+pub fn wchar_to_char(wc: wchar_t) -> Result<char, LocaleError> {
+    // `MB_LEN_MAX` of glibc. `wcrtomb` encodes values beyond U+10FFFF in up to 6 bytes.
+    let mut buf = [0; 16];
+    match unsafe { c::wctoutf8(buf.as_mut_ptr(), wc) } {
+        length if length > 0 => {
+            let length = (length as usize).min(buf.len());
+            let bytes: Vec<u8> = buf[..length].iter().map(|c| *c as u8).collect();
+            utf8_to_char(&bytes)
+        }
+        _ => Err(LocaleError::ConversionFailed { errno: errno().0 }),
+    }
+}
+
+/// Decodes `bytes` written by `wctoutf8` as exactly one `char`.
+///
+/// A Unicode scalar value is at most 4 bytes in UTF-8, so anything longer means `wc` was not a scalar value.
+fn utf8_to_char(bytes: &[u8]) -> Result<char, LocaleError> {
+    const FAILED: LocaleError = LocaleError::ConversionFailed {
+        errno: libc::EILSEQ,
+    };
+    if bytes.is_empty() || bytes.len() > 4 {
+        return Err(FAILED);
+    }
+    let s = std::str::from_utf8(bytes).map_err(|_| FAILED)?;
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(FAILED),
+    }
+}
+
+/// Converts the C wide character `wc` to `char`, replacing an unconvertible value with U+FFFD.
+///
+/// This is the lossy version of [`wchar_to_char`], like [`String::from_utf8_lossy`].
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::encoding::wchar_to_char_lossy;
+///
+/// assert_eq!(wchar_to_char_lossy(0x61), 'a');
+/// assert_eq!(wchar_to_char_lossy(0xD800), '\u{FFFD}');
+/// ```
+pub fn wchar_to_char_lossy(wc: wchar_t) -> char {
+    wchar_to_char(wc).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// Conversion state of `mbrtowc` and `wcrtomb` allocated in C, since the size of `mbstate_t` is platform-dependent.
+#[derive(Debug)]
+struct MbState(*mut libc::c_void);
+
+impl MbState {
+    fn new() -> MbState {
+        let state = unsafe { c::mbstate_new() };
+        if state.is_null() {
+            panic!("mbstate_new failed. error={}", errno());
+        }
+        MbState(state)
+    }
+
+    fn reset(&mut self) {
+        unsafe { c::mbstate_reset(self.0) };
+    }
+
+    fn is_initial(&self) -> bool {
+        unsafe { c::mbsinit_native(self.0) != 0 }
+    }
+}
+
+impl Drop for MbState {
+    fn drop(&mut self) {
+        unsafe { c::mbstate_free(self.0) };
+    }
+}
+
+/// Decodes multibyte strings given in chunks in the codeset of a locale by `mbrtowc`.
+///
+/// The `LC_CTYPE` locale is captured when the `MbDecoder` is constructed.
+/// A multibyte character split across chunks is decoded when its last byte is pushed.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::encoding::MbDecoder;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// let mut decoder = MbDecoder::new();
+/// assert_eq!(decoder.push(b"caf\xC3"), ['c', 'a', 'f']);
+/// assert_eq!(decoder.push(b"\xA9!"), ['\u{00E9}', '!']);
+/// assert!(decoder.finish().is_ok());
+/// ```
+#[derive(Debug)]
+pub struct MbDecoder {
+    locale: Locale,
+    state: MbState,
+}
+
+impl MbDecoder {
+    /// Creates a `MbDecoder` for the `LC_CTYPE` locale specified by the environment.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale cannot be loaded. See [`MbDecoder::try_new`] for the fallible version.
+    pub fn new() -> MbDecoder {
+        MbDecoder::try_new().unwrap()
+    }
+
+    /// Fallible version of [`MbDecoder::new`].
+    ///
+    /// # errors
+    ///
+    /// Returns an error if the locale specified by the environment cannot be loaded. See [`Locale::new`].
+    pub fn try_new() -> Result<MbDecoder, LocaleError> {
+        let locale = Locale::new(&[Category::Ctype], "")?;
+        Ok(MbDecoder {
+            locale,
+            state: MbState::new(),
+        })
+    }
+
+    /// Decodes `bytes` following the bytes pushed before, and returns the characters completed by them.
+    ///
+    /// An invalid byte sequence is decoded as U+FFFD and the decoding restarts at the next byte,
+    /// or at the first byte of `bytes` if the sequence started in the previous bytes.
+    /// A NUL byte is decoded as `'\0'`.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<char> {
+        let mut chars = Vec::new();
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let mut wc = 0;
+            let continued = !self.state.is_initial();
+            let length = unsafe {
+                c::mbrtowc_native(
+                    &mut wc,
+                    rest.as_ptr() as *const c_char,
+                    rest.len(),
+                    self.state.0,
+                    self.locale.as_raw(),
+                )
+            };
+            match length {
+                -2 => break,
+                -1 => {
+                    chars.push(char::REPLACEMENT_CHARACTER);
+                    self.state.reset();
+                    // The byte breaking a sequence from the previous chunk may start a new character.
+                    if !continued {
+                        rest = &rest[1..];
+                    }
+                }
+                length => {
+                    chars.push(wchar_to_char_lossy(wc));
+                    rest = &rest[length as usize..];
+                }
+            }
+        }
+        chars
+    }
+
+    /// Finishes decoding.
+    ///
+    /// # errors
+    ///
+    /// Returns [`LocaleError::IncompleteSequence`] if the pushed bytes end in the middle of a multibyte character.
+    pub fn finish(self) -> Result<(), LocaleError> {
+        if self.state.is_initial() {
+            Ok(())
+        } else {
+            Err(LocaleError::IncompleteSequence)
+        }
+    }
+}
+
+impl Default for MbDecoder {
+    fn default() -> Self {
+        MbDecoder::new()
+    }
+}
+
+/// Encodes characters given in chunks to the codeset of a locale by `wcrtomb`.
+///
+/// The `LC_CTYPE` locale is captured when the `MbEncoder` is constructed.
+/// The shift state of a stateful encoding such as ISO-2022-JP is kept across chunks.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::encoding::MbEncoder;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let mut encoder = MbEncoder::new();
+/// assert_eq!(encoder.push(&['c', 'a', 'f', '\u{00E9}']).unwrap(), b"caf\xE9");
+/// let (bytes, _) = encoder.push(&['!', '\u{4E16}', '?']).unwrap_err();
+/// assert_eq!(bytes, b"!");
+/// assert_eq!(encoder.push(&['?']).unwrap(), b"?");
+/// assert_eq!(encoder.finish(), b"");
+/// ```
+#[derive(Debug)]
+pub struct MbEncoder {
+    locale: Locale,
+    state: MbState,
+}
+
+impl MbEncoder {
+    /// Creates a `MbEncoder` for the `LC_CTYPE` locale specified by the environment.
+    ///
+    /// # panics
+    ///
+    /// Panics if the locale cannot be loaded. See [`MbEncoder::try_new`] for the fallible version.
+    pub fn new() -> MbEncoder {
+        MbEncoder::try_new().unwrap()
+    }
+
+    /// Fallible version of [`MbEncoder::new`].
+    ///
+    /// # errors
+    ///
+    /// Returns an error if the locale specified by the environment cannot be loaded. See [`Locale::new`].
+    pub fn try_new() -> Result<MbEncoder, LocaleError> {
+        let locale = Locale::new(&[Category::Ctype], "")?;
+        Ok(MbEncoder {
+            locale,
+            state: MbState::new(),
+        })
+    }
+
+    /// Encodes `chars` following the characters pushed before.
+    ///
+    /// # errors
+    ///
+    /// Returns the bytes encoded from the characters before the first character of `chars`
+    /// which cannot be represented in the codeset, together with [`LocaleError::UnrepresentableChar`] with it.
+    /// The shift state is kept as after the last encoded character, so the caller can push the characters
+    /// following the unrepresentable one, or return to the initial shift state by [`MbEncoder::finish`].
+    pub fn push(&mut self, chars: &[char]) -> Result<Vec<u8>, (Vec<u8>, LocaleError)> {
+        let mut bytes = Vec::with_capacity(chars.len());
+        for &c in chars {
+            let wc = match char_to_wchar(c) {
+                Ok(wc) => wc,
+                Err(error) => return Err((bytes, error)),
+            };
+            if !self.encode(wc, &mut bytes) {
+                return Err((bytes, LocaleError::UnrepresentableChar { c }));
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Finishes encoding, and returns the bytes to return to the initial shift state.
+    ///
+    /// The bytes are empty unless the codeset is stateful.
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if !self.encode(0, &mut bytes) {
+            panic!("wcrtomb_native failed. error={}", errno());
+        }
+        // Drop NUL following the shift sequence.
+        bytes.pop();
+        bytes
+    }
+
+    /// Appends `wc` encoded to `bytes`, or returns `false` if it cannot be encoded.
+    fn encode(&mut self, wc: wchar_t, bytes: &mut Vec<u8>) -> bool {
+        // `MB_LEN_MAX` of glibc.
+        let mut buf = [0; 16];
+        let length =
+            unsafe { c::wcrtomb_native(buf.as_mut_ptr(), wc, self.state.0, self.locale.as_raw()) };
+        if length < 0 {
+            return false;
+        }
+        bytes.extend(buf[..length as usize].iter().map(|b| *b as u8));
+        true
+    }
+}
+
+impl Default for MbEncoder {
+    fn default() -> Self {
+        MbEncoder::new()
+    }
+}
+
+fn mbstowcs(dest: *mut wchar_t, n: usize, src: *const c_char) -> Result<usize, LocaleError> {
+    match unsafe { c::mbstowcs_native(dest, n, src) } {
+        -1 => Err(newlocale_error("")),
+        -2 => Err(LocaleError::InvalidMultibyte),
+        length => Ok(length as usize),
+    }
+}
+
+fn wcstombs(dest: *mut c_char, n: usize, src: *const wchar_t) -> Result<usize, LocaleError> {
+    match unsafe { c::wcstombs_native(dest, n, src) } {
+        -1 => Err(newlocale_error("")),
+        -2 => Err(LocaleError::ConversionFailed { errno: errno().0 }),
+        length => Ok(length as usize),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::with_env_locale;
+
+    fn wide(s: &str) -> Vec<wchar_t> {
+        s.chars().map(|c| c as wchar_t).collect()
+    }
+
+    #[test]
+    fn bytes_to_wide() {
+        with_env_locale("en_US.UTF-8", || {
+            let s = "caf\u{00E9} \u{4E16}";
+            assert_eq!(super::bytes_to_wide(s.as_bytes()), Ok(wide(s)));
+            assert_eq!(super::bytes_to_wide(b""), Ok(vec![]));
+            assert_eq!(super::bytes_to_wide(b"a\0b"), Ok(wide("a")));
+            assert_eq!(
+                super::bytes_to_wide(b"\xFF"),
+                Err(LocaleError::InvalidMultibyte)
+            );
+        });
+    }
+
+    #[test]
+    fn bytes_to_wide_last_error() {
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(
+                super::bytes_to_wide(b"caf\xE9"),
+                Err(LocaleError::InvalidMultibyte)
+            );
+            assert_eq!(crate::error::last_error(), LocaleError::InvalidMultibyte);
+        });
+    }
+
+    #[test]
+    fn wide_to_bytes() {
+        with_env_locale("en_US.UTF-8", || {
+            let s = "caf\u{00E9} \u{4E16}";
+            assert_eq!(super::wide_to_bytes(&wide(s)), Ok(s.as_bytes().to_vec()));
+            assert_eq!(super::wide_to_bytes(&[]), Ok(vec![]));
+            assert_eq!(super::wide_to_bytes(&wide("a\0b")), Ok(b"a".to_vec()));
+        });
+    }
+
+    #[test]
+    fn round_trip() {
+        with_env_locale("en_US.UTF-8", || {
+            let s = "\u{00C0} la carte, na\u{00EF}ve r\u{00E9}sum\u{00E9}";
+            let wide = super::bytes_to_wide(s.as_bytes()).unwrap();
+            assert_eq!(super::wide_to_bytes(&wide).unwrap(), s.as_bytes());
+        });
+    }
+
+    #[test]
+    fn encoding_i18n() {
+        with_env_locale("en_US", || {
+            assert_eq!(super::bytes_to_wide(b"caf\xE9"), Ok(wide("caf\u{00E9}")));
+            assert_eq!(
+                super::wide_to_bytes(&wide("caf\u{00E9}")),
+                Ok(b"caf\xE9".to_vec())
+            );
+            assert!(matches!(
+                super::wide_to_bytes(&wide("\u{4E16}")),
+                Err(LocaleError::ConversionFailed { .. })
+            ));
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(
+                super::bytes_to_wide(b"caf\xE9"),
+                Err(LocaleError::InvalidMultibyte)
+            );
+        });
+    }
+
+    #[test]
+    fn is_utf8_locale() {
+        for (locale, expected) in [
+            ("POSIX", false),
+            ("C", false),
+            ("en_US", false),
+            ("en_US.UTF-8", true),
+            ("en_US.utf8", true),
+        ] {
+            with_env_locale(locale, || {
+                assert_eq!(super::is_utf8_locale(), expected, "{}", locale);
+            });
+        }
+    }
+
+    #[test]
+    fn is_utf8_codeset() {
+        for codeset in ["UTF-8", "utf8", "utf-8", "UTF8"] {
+            assert!(super::is_utf8_codeset(codeset), "{}", codeset);
+        }
+        for codeset in ["", "ANSI_X3.4-1968", "ISO-8859-1", "UTF-16", "EUC-JP"] {
+            assert!(!super::is_utf8_codeset(codeset), "{}", codeset);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn is_utf8_locale_special() {
+        for (locale, expected) in [("ja_JP", false), ("ja_JP.UTF-8", true), ("de_DE", false)] {
+            with_env_locale(locale, || {
+                assert_eq!(super::is_utf8_locale(), expected, "{}", locale);
+            });
+        }
+    }
+
+    #[test]
+    fn char_to_wchar() {
+        with_env_locale("POSIX", || {
+            for c in [
+                '\0',
+                'a',
+                '~',
+                '\u{00E9}',
+                '\u{4E16}',
+                '\u{FFFD}',
+                '\u{1F600}',
+                '\u{10FFFF}',
+            ] {
+                let wc = super::char_to_wchar(c).unwrap();
+                assert_eq!(wc, c as wchar_t);
+                assert_eq!(super::wchar_to_char(wc), Ok(c));
+            }
+        });
+    }
+
+    #[test]
+    fn char_to_wchar_astral() {
+        with_env_locale("POSIX", || {
+            let result = super::char_to_wchar('\u{1F600}');
+            if WCHAR_IS_16_BIT {
+                assert_eq!(
+                    result,
+                    Err(LocaleError::UnsupportedCodepoint { c: '\u{1F600}' })
+                );
+                assert_eq!(super::char_to_wchar('\u{FFFD}'), Ok(0xFFFD));
+            } else {
+                assert_eq!(result, Ok(0x1F600));
+            }
+        });
+    }
+
+    #[test]
+    fn wchar_to_char() {
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(super::wchar_to_char(0x4E16), Ok('\u{4E16}'));
+            assert_eq!(super::wchar_to_char(0x1F600), Ok('\u{1F600}'));
+            assert!(matches!(
+                super::wchar_to_char(0xD800),
+                Err(LocaleError::ConversionFailed { .. })
+            ));
+            assert!(matches!(
+                super::wchar_to_char(0x110000),
+                Err(LocaleError::ConversionFailed { .. })
+            ));
+            assert!(matches!(
+                super::wchar_to_char(0x7FFFFFFF),
+                Err(LocaleError::ConversionFailed { .. })
+            ));
+            assert!(matches!(
+                super::wchar_to_char(-1),
+                Err(LocaleError::ConversionFailed { .. })
+            ));
+        });
+    }
+
+    #[test]
+    fn utf8_to_char() {
+        assert_eq!(super::utf8_to_char(b"a"), Ok('a'));
+        assert_eq!(super::utf8_to_char(b"\0"), Ok('\0'));
+        assert_eq!(super::utf8_to_char("\u{1F600}".as_bytes()), Ok('\u{1F600}'));
+        let failed = Err(LocaleError::ConversionFailed {
+            errno: libc::EILSEQ,
+        });
+        assert_eq!(super::utf8_to_char(b""), failed);
+        assert_eq!(super::utf8_to_char(b"ab"), failed);
+        assert_eq!(super::utf8_to_char(b"\xED\xA0\x80"), failed);
+        // 0x110000 and 0x7FFFFFFF as written by `wcrtomb`
+        assert_eq!(super::utf8_to_char(b"\xF4\x90\x80\x80"), failed);
+        assert_eq!(super::utf8_to_char(b"\xFD\xBF\xBF\xBF\xBF\xBF"), failed);
+    }
+
+    #[test]
+    fn wchar_to_char_lossy() {
+        with_env_locale("C", || {
+            assert_eq!(super::wchar_to_char_lossy(0x61), 'a');
+            assert_eq!(super::wchar_to_char_lossy(0x1F600), '\u{1F600}');
+            assert_eq!(super::wchar_to_char_lossy(0xDFFF), '\u{FFFD}');
+            assert_eq!(super::wchar_to_char_lossy(0x110000), '\u{FFFD}');
+            assert_eq!(super::wchar_to_char_lossy(-1), '\u{FFFD}');
+        });
+    }
+
+    #[test]
+    fn mb_decoder() {
+        with_env_locale("en_US.UTF-8", || {
+            let s = "a\u{00E9}\u{4E16}\u{1F600}z";
+            let bytes = s.as_bytes();
+            for i in 0..=bytes.len() {
+                let mut decoder = MbDecoder::new();
+                let mut chars = decoder.push(&bytes[..i]);
+                chars.extend(decoder.push(&bytes[i..]));
+                assert_eq!(chars, s.chars().collect::<Vec<_>>(), "{}", i);
+                assert_eq!(decoder.finish(), Ok(()));
+            }
+            let mut decoder = MbDecoder::new();
+            let chars: Vec<_> = bytes.iter().flat_map(|b| decoder.push(&[*b])).collect();
+            assert_eq!(chars, s.chars().collect::<Vec<_>>());
+            let mut decoder = MbDecoder::new();
+            assert_eq!(decoder.push(b"a\0b"), ['a', '\0', 'b']);
+        });
+    }
+
+    #[test]
+    fn mb_decoder_incomplete() {
+        with_env_locale("en_US.UTF-8", || {
+            let mut decoder = MbDecoder::new();
+            assert_eq!(decoder.push(b"a\xE4\xB8"), ['a']);
+            assert_eq!(decoder.finish(), Err(LocaleError::IncompleteSequence));
+            let mut decoder = MbDecoder::new();
+            assert_eq!(decoder.push(b"\xF0\x9F"), []);
+            assert_eq!(decoder.push(b"\x98"), []);
+            assert_eq!(decoder.finish(), Err(LocaleError::IncompleteSequence));
+        });
+    }
+
+    #[test]
+    fn mb_decoder_invalid() {
+        with_env_locale("en_US.UTF-8", || {
+            let mut decoder = MbDecoder::new();
+            assert_eq!(decoder.push(b"a\xFFb"), ['a', '\u{FFFD}', 'b']);
+            assert_eq!(decoder.push(b"\xE4\xB8"), []);
+            assert_eq!(decoder.push(b"c"), ['\u{FFFD}', 'c']);
+            assert_eq!(decoder.finish(), Ok(()));
+        });
+    }
+
+    #[test]
+    fn mb_decoder_i18n() {
+        with_env_locale("en_US", || {
+            let mut decoder = MbDecoder::new();
+            with_env_locale("en_US.UTF-8", || {
+                assert_eq!(decoder.push(b"caf\xE9"), ['c', 'a', 'f', '\u{00E9}']);
+                assert_eq!(decoder.finish(), Ok(()));
+                let mut decoder = MbDecoder::new();
+                assert_eq!(decoder.push(b"caf\xE9"), ['c', 'a', 'f']);
+                assert_eq!(decoder.finish(), Err(LocaleError::IncompleteSequence));
+            });
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn mb_decoder_special() {
+        with_env_locale("ja_JP", || {
+            let mut decoder = MbDecoder::new();
+            assert_eq!(decoder.push(b"\xC6\xFC\xCB"), ['\u{65E5}']);
+            assert_eq!(decoder.push(b"\xDC"), ['\u{672C}']);
+            assert_eq!(decoder.finish(), Ok(()));
+        });
+    }
+
+    #[test]
+    fn mb_encoder() {
+        with_env_locale("en_US.UTF-8", || {
+            let mut encoder = MbEncoder::new();
+            assert_eq!(encoder.push(&['a', 'b', 'c']), Ok(b"abc".to_vec()));
+            assert_eq!(encoder.push(&[]), Ok(vec![]));
+            let s = "\u{00E9}\u{4E16}\u{1F600}";
+            let chars: Vec<_> = s.chars().collect();
+            assert_eq!(encoder.push(&chars), Ok(s.as_bytes().to_vec()));
+            assert_eq!(encoder.push(&['\0']), Ok(vec![0]));
+            assert_eq!(encoder.finish(), b"");
+        });
+    }
+
+    #[test]
+    fn mb_encoder_unrepresentable() {
+        with_env_locale("C", || {
+            let mut encoder = MbEncoder::new();
+            assert_eq!(encoder.push(&['a', 'b']), Ok(b"ab".to_vec()));
+            assert_eq!(
+                encoder.push(&['x', '\u{00E9}', '\u{4E16}']),
+                Err((
+                    b"x".to_vec(),
+                    LocaleError::UnrepresentableChar { c: '\u{00E9}' }
+                ))
+            );
+            assert_eq!(
+                encoder.push(&['\u{4E16}']),
+                Err((vec![], LocaleError::UnrepresentableChar { c: '\u{4E16}' }))
+            );
+            assert_eq!(encoder.push(&['c']), Ok(b"c".to_vec()));
+        });
+    }
+
+    #[test]
+    fn mb_encoder_i18n() {
+        let chars = ['c', 'a', 'f', '\u{00E9}'];
+        with_env_locale("en_US", || {
+            assert_eq!(MbEncoder::new().push(&chars), Ok(b"caf\xE9".to_vec()));
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(
+                MbEncoder::new().push(&chars),
+                Ok("caf\u{00E9}".as_bytes().to_vec())
+            );
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn mb_encoder_special() {
+        with_env_locale("ja_JP", || {
+            let mut encoder = MbEncoder::new();
+            assert_eq!(
+                encoder.push(&['\u{65E5}', '\u{672C}']),
+                Ok(b"\xC6\xFC\xCB\xDC".to_vec())
+            );
+            assert_eq!(
+                encoder.push(&['\u{1F600}']),
+                Err((vec![], LocaleError::UnrepresentableChar { c: '\u{1F600}' }))
+            );
+            assert_eq!(encoder.push(&['\u{8A9E}']), Ok(b"\xB8\xEC".to_vec()));
+            assert_eq!(encoder.finish(), b"");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn encoding_special() {
+        let s = "\u{65E5}\u{672C}\u{8A9E}";
+        with_env_locale("ja_JP", || {
+            let euc_jp = b"\xC6\xFC\xCB\xDC\xB8\xEC";
+            assert_eq!(super::bytes_to_wide(euc_jp), Ok(wide(s)));
+            assert_eq!(super::wide_to_bytes(&wide(s)), Ok(euc_jp.to_vec()));
+        });
+    }
+}
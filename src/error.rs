@@ -0,0 +1,152 @@
+//! Errors reported by the fallible functions of this crate.
+
+use std::fmt;
+
+use errno::{errno, Errno};
+
+/// The error type for the locale-dependent operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocaleError {
+    /// A `wchar_t` could not be converted.
+    ///
+    /// Returned by [`wchar_to_char`](crate::encoding::wchar_to_char) if the value is not a Unicode scalar value,
+    /// and by [`wide_to_bytes`](crate::encoding::wide_to_bytes) if a value cannot be represented
+    /// in the codeset of the current locale.
+    /// `errno` is the value of `errno` when the failure occurred.
+    ConversionFailed { errno: i32 },
+    /// The character `c` cannot be represented in the codeset of the locale.
+    ///
+    /// Returned by [`MbEncoder::push`](crate::encoding::MbEncoder::push), which knows the failing `char`.
+    UnrepresentableChar { c: char },
+    /// The character `c` outside the BMP cannot be represented in a single `wchar_t` of 16 bits,
+    /// as on Windows.
+    UnsupportedCodepoint { c: char },
+    /// The bytes are not a valid multibyte string in the codeset of the locale (`EILSEQ`).
+    ///
+    /// Returned by [`bytes_to_wide`](crate::encoding::bytes_to_wide), and by [`last_error`] for `EILSEQ`.
+    InvalidMultibyte,
+    /// The bytes contain a byte which cannot appear at that position in UTF-8.
+    ///
+    /// Returned only when decoding bytes as UTF-8, independently of the codeset of the locale.
+    InvalidByte,
+    /// The bytes end in the middle of a UTF-8 sequence.
+    ///
+    /// More bytes may complete the sequence.
+    /// Also returned by [`MbDecoder::finish`](crate::encoding::MbDecoder::finish)
+    /// for a multibyte character in the codeset of the current locale.
+    IncompleteSequence,
+    /// The string is not a valid number in the current locale.
+    InvalidNumber,
+    /// The number is valid but does not fit in the type of the result.
+    NumberOutOfRange,
+    /// The locale `name` is not installed (`ENOENT` from `newlocale`).
+    ///
+    /// An empty `name` means the locale specified by the environment (`LC_ALL`, `LC_*`, `LANG`),
+    /// which every function depending on the current locale loads.
+    /// The installed locales are listed by `locale -a`.
+    LocaleNotFound { name: String },
+    /// The locale name `name` is invalid (`EINVAL` from `newlocale`), e.g. it contains a NUL character.
+    ///
+    /// Returned by [`Locale::new`](crate::locale::Locale::new) and [`Locale::with_categories`](crate::locale::Locale::with_categories).
+    InvalidLocaleName { name: String },
+    /// The string contains a NUL character, which cannot be passed to the C API, at the byte offset `position`.
+    EmbeddedNul { position: usize },
+    /// An argument was invalid (`EINVAL`).
+    ///
+    /// Returned only by [`last_error`], which does not know the argument.
+    /// The functions of this crate report an invalid locale name as [`LocaleError::InvalidLocaleName`].
+    InvalidArgument,
+    /// Any other failure reported by `errno`, e.g. `ENOMEM` when a locale cannot be loaded or duplicated.
+    Os { errno: i32 },
+}
+
+impl fmt::Display for LocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocaleError::ConversionFailed { errno } => {
+                write!(f, "conversion failed. error={}", Errno(*errno))
+            }
+            LocaleError::UnrepresentableChar { c } => {
+                write!(f, "{:?} cannot be represented in the codeset", c)
+            }
+            LocaleError::UnsupportedCodepoint { c } => {
+                write!(f, "{:?} cannot be represented in a 16-bit wchar_t", c)
+            }
+            LocaleError::InvalidMultibyte => write!(f, "invalid multibyte sequence"),
+            LocaleError::InvalidByte => write!(f, "invalid byte in UTF-8 sequence"),
+            LocaleError::IncompleteSequence => write!(f, "incomplete UTF-8 sequence"),
+            LocaleError::InvalidNumber => write!(f, "invalid number"),
+            LocaleError::NumberOutOfRange => write!(f, "number out of range"),
+            LocaleError::LocaleNotFound { name } if name.is_empty() => write!(
+                f,
+                "locale specified by the environment not found. Check that it is installed by `locale -a`"
+            ),
+            LocaleError::LocaleNotFound { name } => write!(
+                f,
+                "locale {:?} not found. Check that it is installed by `locale -a`",
+                name
+            ),
+            LocaleError::InvalidLocaleName { name } => write!(f, "invalid locale name {:?}", name),
+            LocaleError::EmbeddedNul { position } => {
+                write!(f, "NUL character at position {}", position)
+            }
+            LocaleError::InvalidArgument => write!(f, "invalid argument"),
+            LocaleError::Os { errno } => write!(f, "error={}", Errno(*errno)),
+        }
+    }
+}
+
+impl std::error::Error for LocaleError {}
+
+/// Returns the error corresponding to the current value of `errno`.
+///
+/// This should be called immediately after a fallible function of this crate failed,
+/// before anything else modifies `errno`.
+/// `EILSEQ` and `EINVAL` are mapped to [`LocaleError::InvalidMultibyte`] and [`LocaleError::InvalidArgument`],
+/// and the other values to [`LocaleError::Os`].
+///
+/// # examples
+///
+/// ```
+/// # #[cfg(not(feature = "no-native"))]
+/// # {
+/// use rust_locale::encoding::bytes_to_wide;
+/// use rust_locale::error::last_error;
+/// use rust_locale::LocaleError;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert!(bytes_to_wide(b"\xFF").is_err());
+/// assert_eq!(last_error(), LocaleError::InvalidMultibyte);
+/// # }
+/// ```
+pub fn last_error() -> LocaleError {
+    match errno().0 {
+        libc::EILSEQ => LocaleError::InvalidMultibyte,
+        libc::EINVAL => LocaleError::InvalidArgument,
+        errno => LocaleError::Os { errno },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_error() {
+        errno::set_errno(Errno(libc::EILSEQ));
+        assert_eq!(super::last_error(), LocaleError::InvalidMultibyte);
+        errno::set_errno(Errno(libc::EINVAL));
+        assert_eq!(super::last_error(), LocaleError::InvalidArgument);
+        errno::set_errno(Errno(libc::ENOMEM));
+        assert_eq!(
+            super::last_error(),
+            LocaleError::Os {
+                errno: libc::ENOMEM
+            }
+        );
+        assert_eq!(
+            LocaleError::InvalidMultibyte.to_string(),
+            "invalid multibyte sequence"
+        );
+    }
+}
@@ -0,0 +1,655 @@
+//! Information about the current locale provided by `nl_langinfo`.
+//!
+//! The locale is specified by the environment (`LC_ALL`, `LC_*`, `LANG`).
+//! All strings are converted to UTF-8 from the codeset of the locale.
+
+use crate::util::grow_string;
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        pub fn nl_langinfo_native(
+            dest: *mut libc::c_char,
+            n: libc::size_t,
+            item: libc::nl_item,
+        ) -> libc::ssize_t;
+        pub fn alt_digits_native(dest: *mut libc::c_char, n: libc::size_t) -> libc::ssize_t;
+        #[cfg(target_env = "gnu")]
+        pub fn week_day_native(workday: i8) -> i8;
+    }
+}
+
+/// Returns the name of the codeset of the current locale, e.g. `"UTF-8"` or `"ISO-8859-1"`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::codeset;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert_eq!(codeset(), "UTF-8");
+/// ```
+pub fn codeset() -> String {
+    langinfo(libc::CODESET)
+}
+
+/// Returns the radix character (decimal point) of the current locale.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::radixchar;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(radixchar(), ".");
+/// ```
+pub fn radixchar() -> String {
+    langinfo(libc::RADIXCHAR)
+}
+
+/// Returns the separator for thousands of the current locale.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::thousands_sep;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(thousands_sep(), ",");
+/// ```
+pub fn thousands_sep() -> String {
+    langinfo(libc::THOUSEP)
+}
+
+/// Returns the name of the `n`th day of the week in the current locale, where 1 is Sunday.
+///
+/// # panics
+///
+/// Panics if `n` is not in `1..=7`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::day_name;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(day_name(2), "Monday");
+/// ```
+pub fn day_name(n: u8) -> String {
+    const ITEMS: [libc::nl_item; 7] = [
+        libc::DAY_1,
+        libc::DAY_2,
+        libc::DAY_3,
+        libc::DAY_4,
+        libc::DAY_5,
+        libc::DAY_6,
+        libc::DAY_7,
+    ];
+    langinfo(ITEMS[day_index(n)])
+}
+
+/// Returns the abbreviated name of the `n`th day of the week in the current locale, where 1 is Sunday.
+///
+/// # panics
+///
+/// Panics if `n` is not in `1..=7`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::abday_name;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(abday_name(2), "Mon");
+/// ```
+pub fn abday_name(n: u8) -> String {
+    const ITEMS: [libc::nl_item; 7] = [
+        libc::ABDAY_1,
+        libc::ABDAY_2,
+        libc::ABDAY_3,
+        libc::ABDAY_4,
+        libc::ABDAY_5,
+        libc::ABDAY_6,
+        libc::ABDAY_7,
+    ];
+    langinfo(ITEMS[day_index(n)])
+}
+
+/// Returns the name of the `n`th month in the current locale, where 1 is January.
+///
+/// # panics
+///
+/// Panics if `n` is not in `1..=12`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::month_name;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(month_name(1), "January");
+/// ```
+pub fn month_name(n: u8) -> String {
+    const ITEMS: [libc::nl_item; 12] = [
+        libc::MON_1,
+        libc::MON_2,
+        libc::MON_3,
+        libc::MON_4,
+        libc::MON_5,
+        libc::MON_6,
+        libc::MON_7,
+        libc::MON_8,
+        libc::MON_9,
+        libc::MON_10,
+        libc::MON_11,
+        libc::MON_12,
+    ];
+    langinfo(ITEMS[month_index(n)])
+}
+
+/// Returns the abbreviated name of the `n`th month in the current locale, where 1 is January.
+///
+/// # panics
+///
+/// Panics if `n` is not in `1..=12`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::abmon_name;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(abmon_name(1), "Jan");
+/// ```
+pub fn abmon_name(n: u8) -> String {
+    const ITEMS: [libc::nl_item; 12] = [
+        libc::ABMON_1,
+        libc::ABMON_2,
+        libc::ABMON_3,
+        libc::ABMON_4,
+        libc::ABMON_5,
+        libc::ABMON_6,
+        libc::ABMON_7,
+        libc::ABMON_8,
+        libc::ABMON_9,
+        libc::ABMON_10,
+        libc::ABMON_11,
+        libc::ABMON_12,
+    ];
+    langinfo(ITEMS[month_index(n)])
+}
+
+/// Returns the regular expression matching affirmative responses in the current locale.
+///
+/// The expression is an extended regular expression for `regcomp`, e.g. `"^[+1yY]"`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::yesexpr;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(yesexpr(), "^[yY]");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(yesexpr(), "^[+1yY]");
+/// ```
+pub fn yesexpr() -> String {
+    langinfo(libc::YESEXPR)
+}
+
+/// Returns the regular expression matching negative responses in the current locale.
+///
+/// The expression is an extended regular expression for `regcomp`, e.g. `"^[-0nN]"`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::noexpr;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(noexpr(), "^[nN]");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(noexpr(), "^[-0nN]");
+/// ```
+pub fn noexpr() -> String {
+    langinfo(libc::NOEXPR)
+}
+
+/// Returns the string for ante meridiem (before noon) in the current locale.
+///
+/// The string is empty if the locale does not use the 12-hour clock.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::am_str;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(am_str(), "AM");
+/// ```
+pub fn am_str() -> String {
+    langinfo(libc::AM_STR)
+}
+
+/// Returns the string for post meridiem (after noon) in the current locale.
+///
+/// The string is empty if the locale does not use the 12-hour clock.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::pm_str;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(pm_str(), "PM");
+/// ```
+pub fn pm_str() -> String {
+    langinfo(libc::PM_STR)
+}
+
+/// Returns the `strftime` format of the 12-hour clock time in the current locale.
+///
+/// The format is empty if the locale does not use the 12-hour clock.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::t_fmt_ampm;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(t_fmt_ampm(), "%I:%M:%S %p");
+/// ```
+pub fn t_fmt_ampm() -> String {
+    langinfo(libc::T_FMT_AMPM)
+}
+
+/// Returns the preferred `strftime` format of the date in the current locale, used by `%x`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::date_format;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(date_format(), "%m/%d/%y");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(date_format(), "%m/%d/%Y");
+/// ```
+pub fn date_format() -> String {
+    langinfo(libc::D_FMT)
+}
+
+/// Returns the preferred `strftime` format of the time in the current locale, used by `%X`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::time_format;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(time_format(), "%H:%M:%S");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(time_format(), "%r");
+/// ```
+pub fn time_format() -> String {
+    langinfo(libc::T_FMT)
+}
+
+/// Returns the preferred `strftime` format of the date and time in the current locale, used by `%c`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::datetime_format;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(datetime_format(), "%a %b %e %H:%M:%S %Y");
+/// ```
+pub fn datetime_format() -> String {
+    langinfo(libc::D_T_FMT)
+}
+
+/// Returns the first day of the week in the current locale, where 1 is Sunday as [`day_name`].
+///
+/// This is available only with glibc (`_NL_TIME_FIRST_WEEKDAY`). Otherwise 1 (Sunday) is returned.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::{day_name, first_weekday};
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(first_weekday(), 1);
+/// assert_eq!(day_name(first_weekday()), "Sunday");
+/// ```
+pub fn first_weekday() -> u8 {
+    #[cfg(target_env = "gnu")]
+    {
+        week_day(false)
+    }
+    #[cfg(not(target_env = "gnu"))]
+    {
+        1
+    }
+}
+
+/// Returns the first working day of the week in the current locale, where 1 is Sunday as [`day_name`].
+///
+/// This is available only with glibc (`_NL_TIME_FIRST_WORKDAY`). Otherwise 2 (Monday) is returned.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::first_workday;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(first_workday(), 2);
+/// ```
+pub fn first_workday() -> u8 {
+    #[cfg(target_env = "gnu")]
+    {
+        week_day(true)
+    }
+    #[cfg(not(target_env = "gnu"))]
+    {
+        2
+    }
+}
+
+/// Returns the alternative digits of the current locale, used by the `O` modifier of `strftime` such as `%Od`.
+///
+/// The `n`th element is the representation of the number `n`, e.g. `"\u{4E09}"` for 3 in `ja_JP.UTF-8`.
+/// A locale defines up to 100 alternative digits, and the result is empty if it defines none, as in `en_US`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::langinfo::alt_digits;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert!(alt_digits().is_empty());
+/// ```
+pub fn alt_digits() -> Vec<String> {
+    let digits = grow_string("alt_digits_native", |dest, n| unsafe {
+        c::alt_digits_native(dest, n)
+    });
+    if digits.is_empty() {
+        Vec::new()
+    } else {
+        digits.split('\0').map(str::to_string).collect()
+    }
+}
+
+#[cfg(target_env = "gnu")]
+fn week_day(workday: bool) -> u8 {
+    match unsafe { c::week_day_native(workday.into()) } {
+        day @ 1..=7 => day as u8,
+        _ => panic!("week_day_native failed. error={}", errno::errno()),
+    }
+}
+
+fn day_index(n: u8) -> usize {
+    match n {
+        1..=7 => (n - 1).into(),
+        _ => panic!("day of the week must be in 1..=7. n={}", n),
+    }
+}
+
+fn month_index(n: u8) -> usize {
+    match n {
+        1..=12 => (n - 1).into(),
+        _ => panic!("month must be in 1..=12. n={}", n),
+    }
+}
+
+pub(crate) fn langinfo(item: libc::nl_item) -> String {
+    grow_string("nl_langinfo_native", |dest, n| unsafe {
+        c::nl_langinfo_native(dest, n, item)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::with_env_locale;
+
+    #[test]
+    fn codeset() {
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(super::codeset(), "UTF-8");
+        });
+        with_env_locale("POSIX", || {
+            assert_ne!(super::codeset(), "UTF-8");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn codeset_special() {
+        with_env_locale("en_US", || {
+            assert_eq!(super::codeset(), "ISO-8859-1");
+        });
+        with_env_locale("ja_JP", || {
+            assert_eq!(super::codeset(), "EUC-JP");
+        });
+    }
+
+    #[test]
+    fn separators() {
+        with_env_locale("POSIX", || {
+            assert_eq!(radixchar(), ".");
+            assert_eq!(thousands_sep(), "");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(radixchar(), ".");
+            assert_eq!(thousands_sep(), ",");
+        });
+    }
+
+    #[test]
+    fn names() {
+        with_env_locale("en_US", || {
+            assert_eq!(day_name(1), "Sunday");
+            assert_eq!(day_name(2), "Monday");
+            assert_eq!(day_name(7), "Saturday");
+            assert_eq!(abday_name(1), "Sun");
+            assert_eq!(month_name(1), "January");
+            assert_eq!(month_name(12), "December");
+            assert_eq!(abmon_name(1), "Jan");
+            assert_eq!(abmon_name(12), "Dec");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn names_special() {
+        with_env_locale("de_DE", || {
+            assert_eq!(day_name(2), "Montag");
+        });
+        with_env_locale("fr_FR", || {
+            assert_eq!(day_name(2), "lundi");
+            assert_eq!(month_name(1), "janvier");
+            assert_eq!(month_name(2), "f\u{00E9}vrier");
+        });
+        with_env_locale("fr_FR.UTF-8", || {
+            assert_eq!(month_name(2), "f\u{00E9}vrier");
+        });
+    }
+
+    #[test]
+    fn week() {
+        for locale in ["POSIX", "en_US"] {
+            with_env_locale(locale, || {
+                assert_eq!(first_weekday(), 1, "{}", locale);
+                assert_eq!(first_workday(), 2, "{}", locale);
+            });
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn week_special() {
+        with_env_locale("fr_FR", || {
+            assert_eq!(first_weekday(), 2);
+            assert_eq!(day_name(first_weekday()), "lundi");
+        });
+        with_env_locale("de_DE", || {
+            assert_eq!(first_weekday(), 2);
+        });
+        with_env_locale("fa_IR", || {
+            assert_eq!(first_weekday(), 7);
+            assert_eq!(first_workday(), 7);
+        });
+        with_env_locale("ar_SA.UTF-8", || {
+            assert_eq!(first_weekday(), 1);
+            assert_eq!(first_workday(), 1);
+        });
+    }
+
+    #[test]
+    fn responses() {
+        with_env_locale("POSIX", || {
+            assert_eq!(yesexpr(), "^[yY]");
+            assert_eq!(noexpr(), "^[nN]");
+        });
+        with_env_locale("en_US", || {
+            assert!(!yesexpr().is_empty());
+            assert!(!noexpr().is_empty());
+            assert_ne!(yesexpr(), noexpr());
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn responses_special() {
+        with_env_locale("en_US", || {
+            let english = yesexpr();
+            with_env_locale("fr_FR", || {
+                assert_ne!(yesexpr(), english);
+                assert!(yesexpr().contains('o'));
+            });
+            with_env_locale("de_DE", || {
+                assert!(yesexpr().contains('j'));
+            });
+            with_env_locale("ru_RU.UTF-8", || {
+                assert!(yesexpr().contains('\u{0434}'));
+                assert!(noexpr().contains('\u{043D}'));
+            });
+        });
+    }
+
+    #[test]
+    fn ampm() {
+        for locale in ["POSIX", "en_US"] {
+            with_env_locale(locale, || {
+                assert_eq!(am_str(), "AM");
+                assert_eq!(pm_str(), "PM");
+                assert_eq!(t_fmt_ampm(), "%I:%M:%S %p");
+            });
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn ampm_special() {
+        with_env_locale("de_DE", || {
+            assert_eq!(am_str(), "");
+            assert_eq!(pm_str(), "");
+            assert_eq!(t_fmt_ampm(), "");
+        });
+        with_env_locale("ja_JP.UTF-8", || {
+            assert_eq!(am_str(), "\u{5348}\u{524D}");
+            assert_eq!(pm_str(), "\u{5348}\u{5F8C}");
+            assert_eq!(t_fmt_ampm(), "%p%I\u{6642}%M\u{5206}%S\u{79D2}");
+        });
+    }
+
+    #[test]
+    fn formats() {
+        with_env_locale("POSIX", || {
+            assert_eq!(date_format(), "%m/%d/%y");
+            assert_eq!(time_format(), "%H:%M:%S");
+            assert_eq!(datetime_format(), "%a %b %e %H:%M:%S %Y");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(date_format(), "%m/%d/%Y");
+            assert_eq!(time_format(), "%r");
+            assert!(!datetime_format().is_empty());
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn formats_special() {
+        with_env_locale("en_US", || {
+            let english = date_format();
+            with_env_locale("de_DE", || {
+                assert_eq!(date_format(), "%d.%m.%Y");
+                assert_ne!(date_format(), english);
+                assert_eq!(time_format(), "%T");
+            });
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "day of the week must be in 1..=7")]
+    fn day_name_out_of_range() {
+        day_name(8);
+    }
+
+    #[test]
+    #[should_panic(expected = "month must be in 1..=12")]
+    fn month_name_out_of_range() {
+        month_name(0);
+    }
+
+    #[test]
+    #[ignore]
+    fn separators_special() {
+        with_env_locale("de_DE", || {
+            assert_eq!(radixchar(), ",");
+            assert_eq!(thousands_sep(), ".");
+        });
+    }
+
+    #[test]
+    fn alt_digits() {
+        for locale in ["POSIX", "en_US", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                assert_eq!(super::alt_digits(), Vec::<String>::new(), "{}", locale);
+            });
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn alt_digits_special() {
+        for locale in ["ja_JP.UTF-8", "ja_JP"] {
+            with_env_locale(locale, || {
+                let digits = super::alt_digits();
+                assert_eq!(digits.len(), 100, "{}", locale);
+                assert_eq!(digits[0], "\u{3007}");
+                assert_eq!(digits[3], "\u{4E09}");
+                assert_eq!(digits[10], "\u{5341}");
+                assert_eq!(digits[99], "\u{4E5D}\u{5341}\u{4E5D}");
+            });
+        }
+        with_env_locale("fa_IR", || {
+            let digits = super::alt_digits();
+            assert_eq!(digits.len(), 100);
+            assert_eq!(digits[5], "\u{06F0}\u{06F5}");
+            assert_eq!(
+                crate::time::format_time("%Od", &crate::time::Tm::builder().day(5).build()),
+                digits[5]
+            );
+        });
+    }
+}
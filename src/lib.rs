@@ -1,7 +1,41 @@
 //! # Rust Locale
 //! 
 //! `rust_locale` provides various functions dependent on locale specified in POSIX.1.
+//!
+//! With the `no-native` feature, the C library is not built and only [`CType`] and [`LocaleError`] are provided.
+//! `CType` is then implemented by the `char` methods of Rust without the current locale,
+//! so the results for non-ASCII characters may differ from any locale.
 
+#[cfg(not(feature = "no-native"))]
+pub use collate::Collator;
+#[cfg(feature = "no-native")]
 pub use ctype::CType;
+#[cfg(not(feature = "no-native"))]
+pub use ctype::{CType, CTypeStr, CTypeString};
+pub use error::LocaleError;
 
+#[cfg(not(feature = "no-native"))]
+pub mod collate;
+#[cfg(not(feature = "no-native"))]
 pub mod ctype;
+#[cfg(feature = "no-native")]
+#[path = "ctype_fallback.rs"]
+pub mod ctype;
+#[cfg(not(feature = "no-native"))]
+pub mod encoding;
+pub mod error;
+#[cfg(not(feature = "no-native"))]
+pub mod langinfo;
+#[cfg(not(feature = "no-native"))]
+pub mod locale;
+#[cfg(not(feature = "no-native"))]
+pub mod monetary;
+#[cfg(not(feature = "no-native"))]
+pub mod numeric;
+#[cfg(not(feature = "no-native"))]
+pub mod time;
+#[cfg(not(feature = "no-native"))]
+pub mod width;
+
+#[cfg(not(feature = "no-native"))]
+mod util;
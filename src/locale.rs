@@ -0,0 +1,1003 @@
+//! Locale objects independent of the global locale.
+
+use std::ffi::{CStr, CString};
+use std::fmt;
+#[cfg(target_env = "gnu")]
+use std::fs::File;
+#[cfg(target_env = "gnu")]
+use std::io::{self, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+#[cfg(target_env = "gnu")]
+use std::path::Path;
+
+use errno::errno;
+
+use crate::LocaleError;
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        #[cfg(target_env = "gnu")]
+        pub fn locale_name_native(
+            category: libc::c_int,
+            locale: libc::locale_t,
+        ) -> *const libc::c_char;
+        #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+        pub fn locale_name_native(mask: libc::c_int, locale: libc::locale_t)
+            -> *const libc::c_char;
+    }
+}
+
+/// Locale categories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// All categories (`LC_ALL`).
+    All,
+    /// String collation (`LC_COLLATE`).
+    Collate,
+    /// Character classification and case conversion (`LC_CTYPE`).
+    Ctype,
+    /// Monetary formatting (`LC_MONETARY`).
+    Monetary,
+    /// Numeric formatting (`LC_NUMERIC`).
+    Numeric,
+    /// Date and time formatting (`LC_TIME`).
+    Time,
+    /// Messages and affirmative/negative responses (`LC_MESSAGES`).
+    Messages,
+}
+
+impl Category {
+    /// Returns the corresponding `LC_*` constant.
+    pub fn as_raw(self) -> libc::c_int {
+        match self {
+            Category::All => libc::LC_ALL,
+            Category::Collate => libc::LC_COLLATE,
+            Category::Ctype => libc::LC_CTYPE,
+            Category::Monetary => libc::LC_MONETARY,
+            Category::Numeric => libc::LC_NUMERIC,
+            Category::Time => libc::LC_TIME,
+            Category::Messages => libc::LC_MESSAGES,
+        }
+    }
+
+    /// Returns the name of the corresponding `LC_*` constant, e.g. `"LC_CTYPE"`.
+    fn variable(self) -> &'static str {
+        match self {
+            Category::All => "LC_ALL",
+            Category::Collate => "LC_COLLATE",
+            Category::Ctype => "LC_CTYPE",
+            Category::Monetary => "LC_MONETARY",
+            Category::Numeric => "LC_NUMERIC",
+            Category::Time => "LC_TIME",
+            Category::Messages => "LC_MESSAGES",
+        }
+    }
+
+    /// Returns the corresponding `LC_*_MASK` constant used by `newlocale`.
+    pub fn mask(self) -> libc::c_int {
+        match self {
+            Category::All => libc::LC_ALL_MASK,
+            Category::Collate => libc::LC_COLLATE_MASK,
+            Category::Ctype => libc::LC_CTYPE_MASK,
+            Category::Monetary => libc::LC_MONETARY_MASK,
+            Category::Numeric => libc::LC_NUMERIC_MASK,
+            Category::Time => libc::LC_TIME_MASK,
+            Category::Messages => libc::LC_MESSAGES_MASK,
+        }
+    }
+}
+
+/// Returns the name of the global locale of `category`.
+///
+/// The name is queried by `setlocale` without changing the locale.
+/// Note that the global locale is the "C" locale until it is set by `setlocale`,
+/// even if the environment specifies another locale.
+///
+/// # panics
+///
+/// Panics if `setlocale` fails.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::locale::{current_locale, with_locale, Category};
+///
+/// assert_eq!(current_locale(Category::Ctype), "C");
+/// std::env::set_var("LC_ALL", "en_US");
+/// let name = with_locale(Category::All, "", || current_locale(Category::Ctype));
+/// assert_eq!(name, "en_US");
+/// ```
+pub fn current_locale(category: Category) -> String {
+    let name = unsafe { libc::setlocale(category.as_raw(), std::ptr::null()) };
+    if name.is_null() {
+        panic!("setlocale failed. category={:?}", category);
+    }
+    unsafe { CStr::from_ptr(name) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Sets the global locale of all categories to the locale specified by the environment
+/// (`LC_ALL`, `LC_*`, `LANG`) by `setlocale(LC_ALL, "")`, and returns its name.
+///
+/// Call this at startup so that the functions of libc which use the global locale follow the environment.
+/// The functions of this crate loading the locale from the environment by themselves are not affected.
+/// Note that the global locale is shared by all threads.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be set.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::locale::init_from_env;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert_eq!(init_from_env(), "en_US.UTF-8");
+/// ```
+pub fn init_from_env() -> String {
+    let empty = CString::new("").unwrap();
+    let name = unsafe { libc::setlocale(libc::LC_ALL, empty.as_ptr()) };
+    if name.is_null() {
+        panic!("setlocale failed. category={:?}, name=", Category::All);
+    }
+    unsafe { CStr::from_ptr(name) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Runs `f` with the global locale of `category` switched to `name` by `setlocale`.
+///
+/// The previous global locale is restored when `f` returns or panics.
+/// Note that the global locale is shared by all threads.
+///
+/// # panics
+///
+/// Panics if the locale `name` cannot be set.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::locale::{with_locale, Category};
+///
+/// let upper = with_locale(Category::Ctype, "en_US", || unsafe { libc::toupper(0xE9) });
+/// assert_eq!(upper, 0xC9);
+/// assert_eq!(unsafe { libc::toupper(0xE9) }, 0xE9);
+/// ```
+pub fn with_locale<T>(category: Category, name: &str, f: impl FnOnce() -> T) -> T {
+    let _guard = SetlocaleGuard::new(category, name);
+    f()
+}
+
+/// Restores the global locale of `category` to `previous` on drop.
+struct SetlocaleGuard {
+    category: Category,
+    previous: CString,
+}
+
+impl SetlocaleGuard {
+    fn new(category: Category, name: &str) -> SetlocaleGuard {
+        let previous = unsafe { libc::setlocale(category.as_raw(), std::ptr::null()) };
+        if previous.is_null() {
+            panic!("setlocale failed. category={:?}", category);
+        }
+        let previous = unsafe { CStr::from_ptr(previous) }.to_owned();
+        let c_name = CString::new(name).unwrap();
+        if unsafe { libc::setlocale(category.as_raw(), c_name.as_ptr()) }.is_null() {
+            panic!("setlocale failed. category={:?}, name={}", category, name);
+        }
+        SetlocaleGuard { category, previous }
+    }
+}
+
+impl Drop for SetlocaleGuard {
+    fn drop(&mut self) {
+        unsafe { libc::setlocale(self.category.as_raw(), self.previous.as_ptr()) };
+    }
+}
+
+/// A locale object created by `newlocale`.
+///
+/// Unlike the global locale set by `setlocale` or the environment, a `Locale` is not affected by other threads.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::locale::{Category, Locale};
+///
+/// let locale = Locale::new(&[Category::Collate], "en_US").unwrap();
+/// assert!(Locale::new(&[Category::All], "xx_XX").is_err());
+/// ```
+#[derive(Debug)]
+pub struct Locale {
+    locale: libc::locale_t,
+}
+
+impl Locale {
+    /// Creates a `Locale` whose `categories` are loaded from the locale `name`.
+    ///
+    /// The categories not in `categories` are from the POSIX locale.
+    /// If `name` is empty, the locale is specified by the environment (`LC_ALL`, `LC_*`, `LANG`).
+    ///
+    /// # errors
+    ///
+    /// Returns [`LocaleError::LocaleNotFound`] if the locale is not installed,
+    /// [`LocaleError::InvalidLocaleName`] if `name` is invalid,
+    /// or [`LocaleError::Os`] if the locale cannot be loaded for another reason.
+    pub fn new(categories: &[Category], name: &str) -> Result<Locale, LocaleError> {
+        let mask = categories.iter().fold(0, |mask, c| mask | c.mask());
+        let c_name = CString::new(name).map_err(|_| LocaleError::InvalidLocaleName {
+            name: name.to_string(),
+        })?;
+        let locale = unsafe { libc::newlocale(mask, c_name.as_ptr(), std::ptr::null_mut()) };
+        if locale.is_null() {
+            Err(newlocale_error(name))
+        } else {
+            Ok(Locale { locale })
+        }
+    }
+
+    /// Replaces `categories` of `self` with those loaded from the locale `name` by `newlocale`.
+    ///
+    /// The other categories are kept, so a locale mixing several locales can be built.
+    /// If `name` is empty, the locale is specified by the environment (`LC_ALL`, `LC_*`, `LANG`).
+    ///
+    /// # errors
+    ///
+    /// Same as [`Locale::new`].
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::locale::{Category, Locale};
+    ///
+    /// let locale = Locale::new(&[Category::Ctype], "en_US")
+    ///     .and_then(|l| l.with_categories(&[Category::Collate], "en_US.UTF-8"))
+    ///     .unwrap();
+    /// assert_eq!(locale.name(Category::Ctype), "en_US");
+    /// assert_eq!(locale.name(Category::Collate), "en_US.UTF-8");
+    /// ```
+    pub fn with_categories(
+        self,
+        categories: &[Category],
+        name: &str,
+    ) -> Result<Locale, LocaleError> {
+        let mask = categories.iter().fold(0, |mask, c| mask | c.mask());
+        let c_name = CString::new(name).map_err(|_| LocaleError::InvalidLocaleName {
+            name: name.to_string(),
+        })?;
+        let locale = unsafe { libc::newlocale(mask, c_name.as_ptr(), self.locale) };
+        if locale.is_null() {
+            // `self.locale` is still valid and freed by `drop`.
+            Err(newlocale_error(name))
+        } else {
+            // `self.locale` has been reused or freed by `newlocale`.
+            std::mem::forget(self);
+            Ok(Locale { locale })
+        }
+    }
+
+    /// Returns the name of the locale from which `category` of `self` is loaded.
+    ///
+    /// The name is the one given to `newlocale`, except that the locale specified by the environment
+    /// is resolved to its name, e.g. `"en_US.UTF-8"`, and the POSIX locale is called `"C"`.
+    /// For [`Category::All`], the name is returned if all the categories are loaded from the same locale,
+    /// otherwise the names of the categories are joined as `"LC_CTYPE=en_US;LC_COLLATE=de_DE;..."`.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::locale::{Category, Locale};
+    ///
+    /// std::env::set_var("LC_ALL", "en_US.UTF-8");
+    /// let locale = Locale::new(&[Category::All], "").unwrap();
+    /// assert_eq!(locale.name(Category::Time), "en_US.UTF-8");
+    /// assert_eq!(locale.name(Category::All), "en_US.UTF-8");
+    /// ```
+    pub fn name(&self, category: Category) -> String {
+        if category != Category::All {
+            // glibc looks up the name by the category, and `querylocale` of Apple and BSD by the mask.
+            #[cfg(target_env = "gnu")]
+            let name = unsafe { c::locale_name_native(category.as_raw(), self.locale) };
+            #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+            let name = unsafe { c::locale_name_native(category.mask(), self.locale) };
+            return unsafe { CStr::from_ptr(name) }
+                .to_string_lossy()
+                .into_owned();
+        }
+        let categories = [
+            Category::Ctype,
+            Category::Numeric,
+            Category::Time,
+            Category::Collate,
+            Category::Monetary,
+            Category::Messages,
+        ];
+        let names: Vec<_> = categories.iter().map(|&c| self.name(c)).collect();
+        if names.iter().all(|name| *name == names[0]) {
+            return names[0].clone();
+        }
+        categories
+            .iter()
+            .zip(&names)
+            .map(|(c, name)| format!("{}={}", c.variable(), name))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Creates a copy of `self` by `duplocale`.
+    ///
+    /// The copy is independent of `self` and can outlive it.
+    ///
+    /// # errors
+    ///
+    /// Returns [`LocaleError::Os`] if the locale cannot be duplicated.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::locale::{Category, Locale};
+    ///
+    /// let locale = Locale::new(&[Category::Ctype], "en_US").unwrap();
+    /// let copy = locale.try_clone().unwrap();
+    /// drop(locale);
+    /// assert!(copy.is_alpha('\u{00E9}'));
+    /// ```
+    pub fn try_clone(&self) -> Result<Locale, LocaleError> {
+        let locale = unsafe { libc::duplocale(self.locale) };
+        if locale.is_null() {
+            Err(LocaleError::Os { errno: errno().0 })
+        } else {
+            Ok(Locale { locale })
+        }
+    }
+
+    /// Returns the raw `locale_t`, which is valid as long as `self` is alive.
+    pub(crate) fn as_raw(&self) -> libc::locale_t {
+        self.locale
+    }
+}
+
+/// Converts `errno` set by `newlocale` for `name` to `LocaleError`.
+///
+/// An empty `name` is the locale specified by the environment.
+pub(crate) fn newlocale_error(name: &str) -> LocaleError {
+    let errno = errno().0;
+    let name = name.to_string();
+    match errno {
+        // glibc does not set `errno` when it finds a locale missing again.
+        libc::ENOENT | 0 => LocaleError::LocaleNotFound { name },
+        libc::EINVAL => LocaleError::InvalidLocaleName { name },
+        errno => LocaleError::Os { errno },
+    }
+}
+
+// A locale object is not modified after `newlocale`,
+// so it can be used on a thread other than the one that created it.
+unsafe impl Send for Locale {}
+
+impl Drop for Locale {
+    fn drop(&mut self) {
+        unsafe { libc::freelocale(self.locale) };
+    }
+}
+
+/// Makes `locale` the locale of the current thread by `uselocale` until the returned guard is dropped.
+///
+/// Unlike [`with_locale`], other threads are not affected.
+/// The locale of the current thread is used by the C library functions such as `toupper`,
+/// while the functions of this crate which load the locale specified by the environment are not affected.
+/// The previous locale of the current thread is restored when the guard is dropped.
+///
+/// # panics
+///
+/// Panics if `uselocale` fails.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::locale::{use_locale, Category, Locale};
+///
+/// let locale = Locale::new(&[Category::Ctype], "en_US").unwrap();
+/// {
+///     let _guard = use_locale(&locale);
+///     assert_eq!(unsafe { libc::toupper(0xE9) }, 0xC9);
+/// }
+/// assert_eq!(unsafe { libc::toupper(0xE9) }, 0xE9);
+/// ```
+pub fn use_locale(locale: &Locale) -> LocaleGuard<'_> {
+    let previous = unsafe { libc::uselocale(locale.as_raw()) };
+    if previous.is_null() {
+        panic!("uselocale failed. error={}", errno());
+    }
+    LocaleGuard {
+        previous,
+        _locale: PhantomData,
+    }
+}
+
+/// Restores the previous locale of the current thread on drop. Returned by [`use_locale`].
+#[derive(Debug)]
+#[must_use = "the locale is restored as soon as the guard is dropped"]
+pub struct LocaleGuard<'a> {
+    previous: libc::locale_t,
+    _locale: PhantomData<&'a Locale>,
+}
+
+impl Drop for LocaleGuard<'_> {
+    fn drop(&mut self) {
+        unsafe { libc::uselocale(self.previous) };
+    }
+}
+
+/// A locale name of the form `language[_territory][.codeset][@modifier]`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::locale::LocaleName;
+///
+/// let name = LocaleName::parse("de_DE.UTF-8@euro").unwrap();
+/// assert_eq!(name.language, "de");
+/// assert_eq!(name.territory.as_deref(), Some("DE"));
+/// assert_eq!(name.codeset.as_deref(), Some("UTF-8"));
+/// assert_eq!(name.modifier.as_deref(), Some("euro"));
+/// assert_eq!(name.to_string(), "de_DE.UTF-8@euro");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocaleName {
+    /// The language, e.g. `"de"`. `"C"` and `"POSIX"` are also parsed as languages.
+    pub language: String,
+    /// The territory, e.g. `"DE"`.
+    pub territory: Option<String>,
+    /// The codeset, e.g. `"UTF-8"`.
+    pub codeset: Option<String>,
+    /// The modifier, e.g. `"euro"`.
+    pub modifier: Option<String>,
+}
+
+impl LocaleName {
+    /// Parses `name` into its components.
+    ///
+    /// Returns `None` if `name` or any of its components is empty, or `name` contains `'/'`
+    /// (a locale specified by a path).
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::locale::LocaleName;
+    ///
+    /// let name = LocaleName::parse("en_US").unwrap();
+    /// assert_eq!(name.language, "en");
+    /// assert_eq!(name.territory.as_deref(), Some("US"));
+    /// assert_eq!(name.codeset, None);
+    /// assert_eq!(LocaleName::parse("en_"), None);
+    /// ```
+    pub fn parse(name: &str) -> Option<LocaleName> {
+        if name.contains('/') {
+            return None;
+        }
+        let (name, modifier) = split_component(name, '@')?;
+        let (name, codeset) = split_component(name, '.')?;
+        let (language, territory) = split_component(name, '_')?;
+        if language.is_empty() {
+            return None;
+        }
+        Some(LocaleName {
+            language: language.to_string(),
+            territory,
+            codeset,
+            modifier,
+        })
+    }
+}
+
+/// Splits `s` at the first `separator`, failing if the part after it is empty.
+fn split_component(s: &str, separator: char) -> Option<(&str, Option<String>)> {
+    match s.split_once(separator) {
+        Some((_, "")) => None,
+        Some((head, tail)) => Some((head, Some(tail.to_string()))),
+        None => Some((s, None)),
+    }
+}
+
+impl fmt::Display for LocaleName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.language)?;
+        if let Some(territory) = &self.territory {
+            write!(f, "_{}", territory)?;
+        }
+        if let Some(codeset) = &self.codeset {
+            write!(f, ".{}", codeset)?;
+        }
+        if let Some(modifier) = &self.modifier {
+            write!(f, "@{}", modifier)?;
+        }
+        Ok(())
+    }
+}
+
+/// The directory where glibc looks up the locales compiled by `localedef`.
+#[cfg(target_env = "gnu")]
+const LOCALE_DIR: &str = "/usr/lib/locale";
+
+/// Returns the names of the locales installed on the system, sorted and without duplicates.
+///
+/// On glibc, the names are read from the locale archive `/usr/lib/locale/locale-archive`
+/// and the locale directories in `/usr/lib/locale`, which gives the same list as `locale -a`.
+/// On the other systems, only `"C"` and `"POSIX"` are returned. They are always included.
+/// A source which cannot be read or parsed is skipped, so the result may lack some locales but this never fails.
+/// The names are as they are installed, e.g. `"en_US.utf8"` rather than `"en_US.UTF-8"`,
+/// but [`Locale::new`] accepts either.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::locale::{installed_locales, Category, Locale};
+///
+/// let locales = installed_locales();
+/// assert!(locales.iter().any(|name| name == "POSIX"));
+/// for name in &locales {
+///     assert!(Locale::new(&[Category::Ctype], name).is_ok(), "{}", name);
+/// }
+/// ```
+pub fn installed_locales() -> Vec<String> {
+    let mut names = vec!["C".to_string(), "POSIX".to_string()];
+    #[cfg(target_env = "gnu")]
+    {
+        let dir = Path::new(LOCALE_DIR);
+        names.extend(archive_locales(&dir.join("locale-archive")).unwrap_or_default());
+        names.extend(directory_locales(dir).unwrap_or_default());
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Reads the names of the locales in the glibc locale archive at `path`.
+///
+/// The archive starts with `struct locarhead` of `locarchive.h` in the native byte order,
+/// whose name hash table refers to the names in the string table by their offsets in the file.
+#[cfg(target_env = "gnu")]
+fn archive_locales(path: &Path) -> io::Result<Vec<String>> {
+    const MAGIC: u32 = 0xDE02_0109;
+    const ENTRY_SIZE: usize = 12;
+    let invalid = || io::Error::from(io::ErrorKind::InvalidData);
+    let field = |bytes: &[u8], index: usize| {
+        let mut word = [0; 4];
+        word.copy_from_slice(&bytes[index * 4..index * 4 + 4]);
+        u32::from_ne_bytes(word)
+    };
+
+    let mut file = File::open(path)?;
+    let length = file.metadata()?.len();
+    let mut header = [0; 32];
+    file.read_exact(&mut header)?;
+    if field(&header, 0) != MAGIC {
+        return Err(invalid());
+    }
+    let (hash_offset, hash_size) = (field(&header, 2), field(&header, 4));
+    let (string_offset, string_used) = (field(&header, 5), field(&header, 6));
+    if u64::from(hash_offset) + u64::from(hash_size) * ENTRY_SIZE as u64 > length
+        || u64::from(string_offset) + u64::from(string_used) > length
+    {
+        return Err(invalid());
+    }
+    let mut table = vec![0; hash_size as usize * ENTRY_SIZE];
+    file.seek(SeekFrom::Start(hash_offset.into()))?;
+    file.read_exact(&mut table)?;
+    let mut strings = vec![0; string_used as usize];
+    file.seek(SeekFrom::Start(string_offset.into()))?;
+    file.read_exact(&mut strings)?;
+
+    let mut names = Vec::new();
+    for entry in table.chunks_exact(ENTRY_SIZE) {
+        let (name_offset, record_offset) = (field(entry, 1), field(entry, 2));
+        // Unused and deleted entries have no name or no locale record.
+        if name_offset == 0 || record_offset == 0 {
+            continue;
+        }
+        let start = name_offset
+            .checked_sub(string_offset)
+            .and_then(|start| strings.get(start as usize..))
+            .ok_or_else(invalid)?;
+        let end = start.iter().position(|&b| b == 0).ok_or_else(invalid)?;
+        if let Ok(name) = std::str::from_utf8(&start[..end]) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Reads the names of the locale directories, which contain `LC_CTYPE`, in `dir`.
+#[cfg(target_env = "gnu")]
+fn directory_locales(dir: &Path) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        if !entry.path().join("LC_CTYPE").is_file() {
+            continue;
+        }
+        if let Ok(name) = entry.file_name().into_string() {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{with_env_locale, with_env_vars};
+
+    #[test]
+    fn installed_locales() {
+        let locales = super::installed_locales();
+        assert!(locales.iter().any(|name| name == "C"));
+        assert!(locales.iter().any(|name| name == "POSIX"));
+        assert!(locales.windows(2).all(|pair| pair[0] < pair[1]));
+        // `locale -a` lists the same sources only on glibc, and is not available on some systems.
+        #[cfg(target_env = "gnu")]
+        if let Ok(output) = std::process::Command::new("locale").arg("-a").output() {
+            if output.status.success() {
+                let mut expected: Vec<String> = String::from_utf8(output.stdout)
+                    .unwrap()
+                    .lines()
+                    .map(str::to_string)
+                    .collect();
+                expected.sort();
+                assert_eq!(locales, expected);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(target_env = "gnu")]
+    fn installed_locales_unavailable() {
+        let missing = Path::new("/nonexistent/locale-archive");
+        assert!(archive_locales(missing).is_err());
+        assert!(directory_locales(Path::new("/nonexistent")).is_err());
+        let not_archive = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        assert_eq!(
+            archive_locales(&not_archive).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            directory_locales(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn category() {
+        assert_eq!(Category::All.as_raw(), libc::LC_ALL);
+        assert_eq!(Category::Collate.as_raw(), libc::LC_COLLATE);
+        assert_eq!(Category::Ctype.as_raw(), libc::LC_CTYPE);
+        assert_eq!(Category::Monetary.as_raw(), libc::LC_MONETARY);
+        assert_eq!(Category::Numeric.as_raw(), libc::LC_NUMERIC);
+        assert_eq!(Category::Time.as_raw(), libc::LC_TIME);
+        assert_eq!(Category::Messages.as_raw(), libc::LC_MESSAGES);
+    }
+
+    #[test]
+    fn category_mask() {
+        assert_eq!(Category::All.mask(), libc::LC_ALL_MASK);
+        assert_eq!(Category::Collate.mask(), libc::LC_COLLATE_MASK);
+        assert_eq!(Category::Ctype.mask(), libc::LC_CTYPE_MASK);
+        assert_eq!(Category::Monetary.mask(), libc::LC_MONETARY_MASK);
+        assert_eq!(Category::Numeric.mask(), libc::LC_NUMERIC_MASK);
+        assert_eq!(Category::Time.mask(), libc::LC_TIME_MASK);
+        assert_eq!(Category::Messages.mask(), libc::LC_MESSAGES_MASK);
+    }
+
+    #[test]
+    fn current_locale() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::current_locale(Category::All), "C");
+            std::env::set_var("LC_ALL", "en_US.UTF-8");
+            super::with_locale(Category::All, "", || {
+                assert!(super::current_locale(Category::Ctype).contains("en_US"));
+                assert!(super::current_locale(Category::Collate).contains("en_US"));
+            });
+            assert_eq!(super::current_locale(Category::Ctype), "C");
+        });
+    }
+
+    #[test]
+    fn init_from_env() {
+        with_env_vars(&[("LC_ALL", None), ("LANG", Some("en_US.UTF-8"))], || {
+            let c_locale = CString::new("C").unwrap();
+            unsafe { libc::setlocale(libc::LC_ALL, c_locale.as_ptr()) };
+            assert_eq!(super::init_from_env(), "en_US.UTF-8");
+            assert_eq!(super::current_locale(Category::Ctype), "en_US.UTF-8");
+        });
+    }
+
+    #[test]
+    fn new_error_message() {
+        let error = Locale::new(&[Category::All], "xx_XX").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "locale \"xx_XX\" not found. Check that it is installed by `locale -a`"
+        );
+    }
+
+    #[test]
+    fn with_categories() {
+        let locale = Locale::new(&[Category::Ctype], "en_US").unwrap();
+        let locale = locale
+            .with_categories(&[Category::Collate, Category::Time], "en_US.UTF-8")
+            .unwrap();
+        assert_eq!(locale.name(Category::Ctype), "en_US");
+        assert_eq!(locale.name(Category::Collate), "en_US.UTF-8");
+        assert_eq!(locale.name(Category::Time), "en_US.UTF-8");
+        assert_eq!(locale.name(Category::Numeric), "C");
+        assert!(locale.is_alpha('\u{00E9}'));
+        let locale = Locale::new(&[Category::Ctype], "en_US").unwrap();
+        assert!(locale
+            .with_categories(&[Category::Collate], "xx_XX")
+            .is_err());
+    }
+
+    #[test]
+    fn name() {
+        let locale = Locale::new(&[Category::Ctype], "en_US")
+            .and_then(|l| l.with_categories(&[Category::Collate], "POSIX"))
+            .unwrap();
+        assert_eq!(locale.name(Category::Ctype), "en_US");
+        // glibc calls the POSIX locale "C".
+        assert_eq!(locale.name(Category::Collate), "C");
+        assert_eq!(locale.name(Category::Messages), "C");
+        assert_eq!(
+            locale.name(Category::All),
+            "LC_CTYPE=en_US;LC_NUMERIC=C;LC_TIME=C;LC_COLLATE=C;LC_MONETARY=C;LC_MESSAGES=C"
+        );
+        let locale = Locale::new(&[Category::All], "en_US").unwrap();
+        assert_eq!(locale.name(Category::All), "en_US");
+        with_env_locale("en_US.UTF-8", || {
+            let locale = Locale::new(&[Category::All], "").unwrap();
+            assert_eq!(locale.name(Category::Ctype), "en_US.UTF-8");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn name_special() {
+        let locale = Locale::new(&[Category::Ctype], "en_US")
+            .and_then(|l| l.with_categories(&[Category::Collate], "de_DE"))
+            .unwrap();
+        assert_eq!(locale.name(Category::Ctype), "en_US");
+        assert_eq!(locale.name(Category::Collate), "de_DE");
+    }
+
+    #[test]
+    fn with_locale() {
+        let previous = super::current_locale(Category::Ctype);
+        let inside = super::with_locale(Category::Ctype, "en_US", || {
+            assert!(unsafe { libc::isalpha(0xE9) } != 0);
+            super::current_locale(Category::Ctype)
+        });
+        assert_eq!(inside, "en_US");
+        assert_eq!(super::current_locale(Category::Ctype), previous);
+        assert!(unsafe { libc::isalpha(0xE9) } == 0);
+    }
+
+    #[test]
+    fn with_locale_panic() {
+        let previous = super::current_locale(Category::All);
+        let result = std::panic::catch_unwind(|| {
+            super::with_locale(Category::All, "en_US", || panic!("in closure"))
+        });
+        assert!(result.is_err());
+        assert_eq!(super::current_locale(Category::All), previous);
+    }
+
+    #[test]
+    #[should_panic(expected = "setlocale failed")]
+    fn with_locale_invalid() {
+        super::with_locale(Category::Ctype, "xx_XX", || ());
+    }
+
+    #[test]
+    #[ignore]
+    fn with_locale_special() {
+        let previous = super::current_locale(Category::Ctype);
+        super::with_locale(Category::Ctype, "tr_TR", || {
+            assert_eq!(super::current_locale(Category::Ctype), "tr_TR");
+            assert_eq!(unsafe { libc::toupper(b'i'.into()) }, 0xDD);
+        });
+        assert_eq!(super::current_locale(Category::Ctype), previous);
+        assert_eq!(unsafe { libc::toupper(b'i'.into()) }, b'I'.into());
+    }
+
+    #[test]
+    fn new() {
+        let locale = Locale::new(&[Category::Ctype], "en_US").unwrap();
+        assert!(!locale.as_raw().is_null());
+        drop(locale);
+        assert!(Locale::new(&[Category::All], "POSIX").is_ok());
+        assert!(Locale::new(&[Category::Ctype, Category::Collate], "en_US").is_ok());
+    }
+
+    #[test]
+    fn new_env() {
+        with_env_locale("en_US", || {
+            let locale = Locale::new(&[Category::All], "").unwrap();
+            assert_eq!(locale.name(Category::Ctype), "en_US");
+        });
+        with_env_locale("xx_XX", || {
+            let error = Locale::new(&[Category::Ctype], "").unwrap_err();
+            assert_eq!(
+                error,
+                LocaleError::LocaleNotFound {
+                    name: String::new()
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn new_invalid() {
+        assert_eq!(
+            Locale::new(&[Category::All], "xx_XX").unwrap_err(),
+            LocaleError::LocaleNotFound {
+                name: "xx_XX".to_string()
+            }
+        );
+        assert_eq!(
+            Locale::new(&[Category::All], "en_US\0").unwrap_err(),
+            LocaleError::InvalidLocaleName {
+                name: "en_US\0".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn try_clone() {
+        let locale = Locale::new(&[Category::Ctype], "en_US").unwrap();
+        let copy = locale.try_clone().unwrap();
+        assert_ne!(copy.as_raw(), locale.as_raw());
+        drop(locale);
+        let thread = std::thread::spawn(move || {
+            assert!(copy.is_alpha('\u{00E9}'));
+            assert_eq!(copy.to_uppercase('\u{00E9}'), '\u{00C9}');
+            copy
+        });
+        let copy = thread.join().unwrap();
+        assert!(copy.is_alpha('\u{00E9}'));
+    }
+
+    #[test]
+    fn use_locale() {
+        let locale = Locale::new(&[Category::Ctype], "en_US").unwrap();
+        {
+            let _guard = super::use_locale(&locale);
+            assert!(unsafe { libc::isalpha(0xE9) } != 0);
+            assert_eq!(unsafe { libc::toupper(0xE9) }, 0xC9);
+            assert_eq!(super::current_locale(Category::Ctype), "C");
+        }
+        assert!(unsafe { libc::isalpha(0xE9) } == 0);
+    }
+
+    #[test]
+    fn use_locale_nested() {
+        let english = Locale::new(&[Category::Ctype], "en_US").unwrap();
+        let posix = Locale::new(&[Category::Ctype], "POSIX").unwrap();
+        let _outer = super::use_locale(&english);
+        {
+            let _inner = super::use_locale(&posix);
+            assert!(unsafe { libc::isalpha(0xE9) } == 0);
+        }
+        assert!(unsafe { libc::isalpha(0xE9) } != 0);
+        // The C functions of this crate restore the locale of the current thread.
+        with_env_locale("POSIX", || {
+            assert!(crate::CType::is_alpha(&'a'));
+            assert!(!crate::CType::is_alpha(&'\u{00E9}'));
+            assert!(unsafe { libc::isalpha(0xE9) } != 0);
+        });
+    }
+
+    #[test]
+    fn use_locale_threads() {
+        let threads: Vec<_> = [("POSIX", 0xE9), ("en_US", 0xC9)]
+            .iter()
+            .map(|&(name, expected)| {
+                std::thread::spawn(move || {
+                    let locale = Locale::new(&[Category::Ctype], name).unwrap();
+                    let _guard = super::use_locale(&locale);
+                    for _ in 0..1000 {
+                        assert_eq!(unsafe { libc::toupper(0xE9) }, expected);
+                        assert_eq!(unsafe { libc::isalpha(0xE9) } != 0, expected == 0xC9);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(unsafe { libc::toupper(0xE9) }, 0xE9);
+    }
+
+    #[test]
+    #[ignore]
+    fn use_locale_threads_special() {
+        let threads: Vec<_> = [("tr_TR", 0xDD), ("en_US", 0x49)]
+            .iter()
+            .map(|&(name, expected)| {
+                std::thread::spawn(move || {
+                    let locale = Locale::new(&[Category::Ctype], name).unwrap();
+                    let _guard = super::use_locale(&locale);
+                    for _ in 0..1000 {
+                        assert_eq!(unsafe { libc::toupper(0x69) }, expected);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    fn locale_name(
+        language: &str,
+        territory: Option<&str>,
+        codeset: Option<&str>,
+        modifier: Option<&str>,
+    ) -> LocaleName {
+        LocaleName {
+            language: language.to_string(),
+            territory: territory.map(str::to_string),
+            codeset: codeset.map(str::to_string),
+            modifier: modifier.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn locale_name_parse() {
+        for (name, expected) in [
+            ("C", locale_name("C", None, None, None)),
+            ("POSIX", locale_name("POSIX", None, None, None)),
+            ("en", locale_name("en", None, None, None)),
+            ("en_US", locale_name("en", Some("US"), None, None)),
+            ("C.UTF-8", locale_name("C", None, Some("UTF-8"), None)),
+            (
+                "de_DE.UTF-8",
+                locale_name("de", Some("DE"), Some("UTF-8"), None),
+            ),
+            (
+                "de_DE@euro",
+                locale_name("de", Some("DE"), None, Some("euro")),
+            ),
+            (
+                "de_DE.UTF-8@euro",
+                locale_name("de", Some("DE"), Some("UTF-8"), Some("euro")),
+            ),
+            (
+                "sr_RS@latin",
+                locale_name("sr", Some("RS"), None, Some("latin")),
+            ),
+        ] {
+            let parsed = LocaleName::parse(name);
+            assert_eq!(parsed.as_ref(), Some(&expected), "{}", name);
+            assert_eq!(parsed.unwrap().to_string(), name);
+        }
+    }
+
+    #[test]
+    fn locale_name_parse_invalid() {
+        for name in [
+            "",
+            "_US",
+            "en_",
+            "en.",
+            "en@",
+            ".UTF-8",
+            "@euro",
+            "/usr/lib/locale/en_US",
+        ] {
+            assert_eq!(LocaleName::parse(name), None, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,580 @@
+//! Monetary formatting dependent on `LC_MONETARY`.
+
+use std::ffi::CString;
+
+use crate::langinfo::langinfo;
+use crate::numeric::{group_digits, localeconv};
+use crate::util::grow_string;
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        pub fn strfmon_native(
+            dest: *mut libc::c_char,
+            n: libc::size_t,
+            format: *const libc::c_char,
+            amount: libc::c_double,
+        ) -> libc::ssize_t;
+    }
+}
+
+/// How negative amounts are indicated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeStyle {
+    /// Uses the negative sign of the current locale, e.g. `-$1,234.50`.
+    Sign,
+    /// Encloses negative amounts in parentheses, e.g. `($1,234.50)`.
+    Parentheses,
+}
+
+// `#[default]` on enum variants requires Rust 1.62.
+#[allow(clippy::derivable_impls)]
+impl Default for NegativeStyle {
+    fn default() -> Self {
+        NegativeStyle::Sign
+    }
+}
+
+/// Options for [`format_monetary_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MonetaryFlags {
+    /// Minimum field width. The result is padded with spaces on the left.
+    pub width: Option<usize>,
+    /// How negative amounts are indicated.
+    pub negative: NegativeStyle,
+}
+
+/// Formats `amount` as a monetary quantity of the current locale.
+///
+/// The locale is specified by the environment (`LC_ALL`, `LC_MONETARY`, `LANG`).
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::monetary::format_monetary;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(format_monetary(1234.5), "1234.50");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(format_monetary(1234.5), "$1,234.50");
+/// ```
+pub fn format_monetary(amount: f64) -> String {
+    format_monetary_with(amount, MonetaryFlags::default())
+}
+
+/// Formats `amount` as a monetary quantity of the current locale with `flags`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::monetary::{format_monetary_with, MonetaryFlags, NegativeStyle};
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// let flags = MonetaryFlags {
+///     width: Some(12),
+///     negative: NegativeStyle::Parentheses,
+/// };
+/// assert_eq!(format_monetary_with(-1234.5, flags), " ($1,234.50)");
+/// ```
+pub fn format_monetary_with(amount: f64, flags: MonetaryFlags) -> String {
+    let mut format = String::from("%");
+    if flags.negative == NegativeStyle::Parentheses {
+        format.push('(');
+    }
+    if let Some(width) = flags.width {
+        format.push_str(&width.to_string());
+    }
+    format.push('n');
+    let format = CString::new(format).unwrap();
+    grow_string("strfmon_native", |dest, n| unsafe {
+        c::strfmon_native(dest, n, format.as_ptr(), amount)
+    })
+}
+
+/// Formats `amount` as a monetary quantity of the current locale without the currency symbol and `strfmon`.
+///
+/// The amount is rounded to `frac_digits` digits after `mon_decimal_point`,
+/// and the digits before it are separated by `mon_thousands_sep` according to `mon_grouping` of
+/// [`crate::numeric::Lconv`], e.g. `12,34,567.89` in `en_IN`.
+/// A negative amount is preceded by `negative_sign` unless it is rounded to zero.
+/// As `strfmon`, the decimal point is `.`, the number of fractional digits is 2 and the negative sign is `-`
+/// if they are not available in the current locale, as in the POSIX locale.
+/// Infinity and NaN are formatted as `inf`, `-inf` and `NaN`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::monetary::format_monetary_manual;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(format_monetary_manual(1234567.891), "1234567.89");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(format_monetary_manual(1234567.891), "1,234,567.89");
+/// assert_eq!(format_monetary_manual(-1234.5), "-1,234.50");
+/// ```
+pub fn format_monetary_manual(amount: f64) -> String {
+    if !amount.is_finite() {
+        return amount.to_string();
+    }
+    let lconv = localeconv();
+    let frac_digits = count(lconv.frac_digits).unwrap_or(2);
+    let formatted = format!("{:.*}", usize::from(frac_digits), amount.abs());
+    let (integer, fraction) = match formatted.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (formatted.as_str(), None),
+    };
+    let mut result = String::new();
+    if amount < 0.0 && formatted.bytes().any(|b| b != b'0' && b != b'.') {
+        result.push_str(match lconv.negative_sign.as_str() {
+            "" => "-",
+            sign => sign,
+        });
+    }
+    result.push_str(&group_digits(
+        integer,
+        &lconv.mon_thousands_sep,
+        &lconv.mon_grouping,
+    ));
+    if let Some(fraction) = fraction {
+        result.push_str(match lconv.mon_decimal_point.as_str() {
+            "" => ".",
+            point => point,
+        });
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// Returns the local currency symbol of the current locale, e.g. `"$"`.
+///
+/// The locale is specified by the environment (`LC_ALL`, `LC_MONETARY`, `LANG`).
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::monetary::currency_symbol;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(currency_symbol(), "");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(currency_symbol(), "$");
+/// ```
+pub fn currency_symbol() -> String {
+    localeconv().currency_symbol
+}
+
+/// Returns the international currency symbol of the current locale, e.g. `"USD "`.
+///
+/// As specified by POSIX, the symbol is the ISO 4217 currency code followed by the character
+/// that separates it from the monetary quantity, which is preserved.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::monetary::int_curr_symbol;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(int_curr_symbol(), "");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(int_curr_symbol(), "USD ");
+/// ```
+pub fn int_curr_symbol() -> String {
+    localeconv().int_curr_symbol
+}
+
+/// Where the currency symbol is placed, returned by [`crncystr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CurrencyPosition {
+    /// The symbol precedes the monetary quantity, e.g. `$1.50`.
+    Before,
+    /// The symbol follows the monetary quantity, e.g. `1,50 €`.
+    After,
+    /// The symbol replaces the radix character, e.g. `1$50`.
+    Radix,
+}
+
+/// Returns the currency symbol of the current locale and where it is placed, by `nl_langinfo(CRNCYSTR)`.
+///
+/// `CRNCYSTR` is the currency symbol preceded by `-` if it is placed before the quantity,
+/// `+` if after, or `.` if it replaces the radix character. The symbol is returned without the indicator.
+/// If the indicator is missing, the whole string is returned as placed before.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::monetary::{crncystr, CurrencyPosition};
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(crncystr(), (CurrencyPosition::Before, "".to_string()));
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(crncystr(), (CurrencyPosition::Before, "$".to_string()));
+/// ```
+pub fn crncystr() -> (CurrencyPosition, String) {
+    let s = langinfo(libc::CRNCYSTR);
+    let position = match s.chars().next() {
+        Some('-') => CurrencyPosition::Before,
+        Some('+') => CurrencyPosition::After,
+        Some('.') => CurrencyPosition::Radix,
+        _ => return (CurrencyPosition::Before, s),
+    };
+    (position, s[1..].to_string())
+}
+
+/// Positioning of the sign string relative to a monetary quantity and the currency symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignPosition {
+    /// Parentheses enclose the quantity and the currency symbol.
+    Parentheses,
+    /// The sign string precedes the quantity and the currency symbol.
+    BeforeAll,
+    /// The sign string succeeds the quantity and the currency symbol.
+    AfterAll,
+    /// The sign string immediately precedes the currency symbol.
+    BeforeCurrency,
+    /// The sign string immediately succeeds the currency symbol.
+    AfterCurrency,
+}
+
+impl SignPosition {
+    fn from_raw(value: i8) -> Option<SignPosition> {
+        match value {
+            0 => Some(SignPosition::Parentheses),
+            1 => Some(SignPosition::BeforeAll),
+            2 => Some(SignPosition::AfterAll),
+            3 => Some(SignPosition::BeforeCurrency),
+            4 => Some(SignPosition::AfterCurrency),
+            _ => None,
+        }
+    }
+}
+
+/// Conventions to format monetary quantities in the current locale, decoded from the C `struct lconv`.
+///
+/// Each field is `None` if the value is not available in the current locale (`CHAR_MAX` in C),
+/// as in the POSIX locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonetaryConventions {
+    /// Whether the currency symbol precedes a non-negative monetary quantity.
+    pub p_cs_precedes: Option<bool>,
+    /// Separation of the currency symbol, the sign string and a non-negative monetary quantity.
+    ///
+    /// 0 means no space, 1 means a space between the currency symbol and the quantity if they are adjacent,
+    /// and 2 means a space between the sign string and the currency symbol if they are adjacent.
+    pub p_sep_by_space: Option<u8>,
+    /// Whether the currency symbol precedes a negative monetary quantity.
+    pub n_cs_precedes: Option<bool>,
+    /// Same as `p_sep_by_space` for a negative monetary quantity.
+    pub n_sep_by_space: Option<u8>,
+    /// Positioning of `positive_sign` for a non-negative monetary quantity.
+    pub p_sign_posn: Option<SignPosition>,
+    /// Positioning of `negative_sign` for a negative monetary quantity.
+    pub n_sign_posn: Option<SignPosition>,
+    /// String used to indicate a non-negative monetary quantity.
+    pub positive_sign: String,
+    /// String used to indicate a negative monetary quantity.
+    pub negative_sign: String,
+    /// Number of fractional digits in locally formatted monetary quantities.
+    pub frac_digits: Option<u8>,
+    /// Number of fractional digits in internationally formatted monetary quantities.
+    pub int_frac_digits: Option<u8>,
+}
+
+/// Returns the conventions to format monetary quantities in the current locale.
+///
+/// The locale is specified by the environment (`LC_ALL`, `LC_MONETARY`, `LANG`).
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::monetary::{monetary_conventions, SignPosition};
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(monetary_conventions().frac_digits, None);
+/// std::env::set_var("LC_ALL", "en_US");
+/// let conventions = monetary_conventions();
+/// assert_eq!(conventions.p_cs_precedes, Some(true));
+/// assert_eq!(conventions.n_sign_posn, Some(SignPosition::BeforeAll));
+/// assert_eq!(conventions.negative_sign, "-");
+/// assert_eq!(conventions.frac_digits, Some(2));
+/// ```
+pub fn monetary_conventions() -> MonetaryConventions {
+    let lconv = localeconv();
+    MonetaryConventions {
+        p_cs_precedes: flag(lconv.p_cs_precedes),
+        p_sep_by_space: count(lconv.p_sep_by_space),
+        n_cs_precedes: flag(lconv.n_cs_precedes),
+        n_sep_by_space: count(lconv.n_sep_by_space),
+        p_sign_posn: SignPosition::from_raw(lconv.p_sign_posn),
+        n_sign_posn: SignPosition::from_raw(lconv.n_sign_posn),
+        positive_sign: lconv.positive_sign,
+        negative_sign: lconv.negative_sign,
+        frac_digits: count(lconv.frac_digits),
+        int_frac_digits: count(lconv.int_frac_digits),
+    }
+}
+
+fn flag(value: i8) -> Option<bool> {
+    match value {
+        i8::MAX => None,
+        value => Some(value != 0),
+    }
+}
+
+fn count(value: i8) -> Option<u8> {
+    match value {
+        i8::MAX => None,
+        value => u8::try_from(value).ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::with_env_locale;
+
+    #[test]
+    fn format_monetary() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::format_monetary(1234.5), "1234.50");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_monetary(1234.5), "$1,234.50");
+            assert_eq!(super::format_monetary(-1234.5), "-$1,234.50");
+            assert_eq!(super::format_monetary(0.0), "$0.00");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn format_monetary_special() {
+        with_env_locale("de_DE.UTF-8", || {
+            assert_eq!(super::format_monetary(1234.5), "1.234,50 \u{20AC}");
+            assert_eq!(super::format_monetary(-1234.5), "-1.234,50 \u{20AC}");
+        });
+    }
+
+    #[test]
+    fn format_monetary_with() {
+        with_env_locale("en_US", || {
+            let parentheses = MonetaryFlags {
+                negative: NegativeStyle::Parentheses,
+                ..MonetaryFlags::default()
+            };
+            assert_eq!(
+                super::format_monetary_with(-1234.5, parentheses),
+                "($1,234.50)"
+            );
+            assert_eq!(
+                super::format_monetary_with(1234.5, parentheses),
+                "$1,234.50"
+            );
+            let width = MonetaryFlags {
+                width: Some(12),
+                ..MonetaryFlags::default()
+            };
+            assert_eq!(super::format_monetary_with(1234.5, width), "   $1,234.50");
+        });
+    }
+
+    #[test]
+    fn format_monetary_long() {
+        with_env_locale("en_US", || {
+            let flags = MonetaryFlags {
+                width: Some(200),
+                ..MonetaryFlags::default()
+            };
+            let s = super::format_monetary_with(1234.5, flags);
+            assert_eq!(s.len(), 200);
+            assert!(s.ends_with(" $1,234.50"));
+        });
+    }
+
+    #[test]
+    fn format_monetary_manual() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::format_monetary_manual(1234567.891), "1234567.89");
+            assert_eq!(super::format_monetary_manual(-1234.5), "-1234.50");
+            assert_eq!(super::format_monetary_manual(f64::NAN), "NaN");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_monetary_manual(1234567.891), "1,234,567.89");
+            assert_eq!(super::format_monetary_manual(-1234.5), "-1,234.50");
+            assert_eq!(super::format_monetary_manual(999.999), "1,000.00");
+            assert_eq!(super::format_monetary_manual(0.0), "0.00");
+            assert_eq!(super::format_monetary_manual(-0.001), "0.00");
+            assert_eq!(super::format_monetary_manual(f64::NEG_INFINITY), "-inf");
+        });
+    }
+
+    #[test]
+    fn format_monetary_manual_i18n() {
+        with_env_locale("en_US", || {
+            let grouped = super::format_monetary_manual(1234.5);
+            assert_eq!(
+                super::format_monetary(1234.5),
+                format!("{}{}", currency_symbol(), grouped)
+            );
+            with_env_locale("POSIX", || {
+                assert_eq!(
+                    super::format_monetary(1234.5),
+                    super::format_monetary_manual(1234.5)
+                );
+                assert_ne!(super::format_monetary_manual(1234.5), grouped);
+            });
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn format_monetary_manual_special() {
+        with_env_locale("de_DE.UTF-8", || {
+            assert_eq!(super::format_monetary_manual(1234567.891), "1.234.567,89");
+            assert_eq!(super::format_monetary_manual(-1234.5), "-1.234,50");
+        });
+        with_env_locale("en_IN", || {
+            assert_eq!(super::format_monetary_manual(1234567.891), "12,34,567.89");
+            assert_eq!(super::format_monetary_manual(123.0), "123.00");
+            assert_eq!(super::format_monetary_manual(1234.0), "1,234.00");
+            assert_eq!(
+                super::format_monetary_manual(123456789.0),
+                "12,34,56,789.00"
+            );
+        });
+        with_env_locale("ja_JP.UTF-8", || {
+            assert_eq!(super::format_monetary_manual(1234.5), "1,234");
+        });
+    }
+
+    #[test]
+    fn currency_symbols() {
+        with_env_locale("POSIX", || {
+            assert_eq!(currency_symbol(), "");
+            assert_eq!(int_curr_symbol(), "");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(currency_symbol(), "$");
+            assert_eq!(int_curr_symbol(), "USD ");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn currency_symbols_special() {
+        with_env_locale("de_DE.UTF-8", || {
+            assert_eq!(currency_symbol(), "\u{20AC}");
+            assert_eq!(int_curr_symbol(), "EUR ");
+        });
+        with_env_locale("ja_JP.UTF-8", || {
+            assert_eq!(currency_symbol(), "\u{FFE5}");
+            assert_eq!(int_curr_symbol(), "JPY ");
+        });
+        with_env_locale("ja_JP", || {
+            assert_eq!(currency_symbol(), "\u{FFE5}");
+        });
+    }
+
+    #[test]
+    fn crncystr() {
+        with_env_locale("POSIX", || {
+            assert_eq!(
+                super::crncystr(),
+                (CurrencyPosition::Before, "".to_string())
+            );
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(
+                super::crncystr(),
+                (CurrencyPosition::Before, "$".to_string())
+            );
+        });
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(super::crncystr().1, currency_symbol());
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn crncystr_special() {
+        with_env_locale("de_DE.UTF-8", || {
+            assert_eq!(
+                super::crncystr(),
+                (CurrencyPosition::After, "\u{20AC}".to_string())
+            );
+        });
+        with_env_locale("sv_SE", || {
+            assert_eq!(
+                super::crncystr(),
+                (CurrencyPosition::After, "kr".to_string())
+            );
+        });
+        with_env_locale("ja_JP", || {
+            assert_eq!(
+                super::crncystr(),
+                (CurrencyPosition::Before, "\u{FFE5}".to_string())
+            );
+        });
+    }
+
+    #[test]
+    fn monetary_conventions() {
+        with_env_locale("POSIX", || {
+            assert_eq!(
+                super::monetary_conventions(),
+                MonetaryConventions {
+                    p_cs_precedes: None,
+                    p_sep_by_space: None,
+                    n_cs_precedes: None,
+                    n_sep_by_space: None,
+                    p_sign_posn: None,
+                    n_sign_posn: None,
+                    positive_sign: "".to_string(),
+                    negative_sign: "".to_string(),
+                    frac_digits: None,
+                    int_frac_digits: None,
+                }
+            );
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(
+                super::monetary_conventions(),
+                MonetaryConventions {
+                    p_cs_precedes: Some(true),
+                    p_sep_by_space: Some(0),
+                    n_cs_precedes: Some(true),
+                    n_sep_by_space: Some(0),
+                    p_sign_posn: Some(SignPosition::BeforeAll),
+                    n_sign_posn: Some(SignPosition::BeforeAll),
+                    positive_sign: "".to_string(),
+                    negative_sign: "-".to_string(),
+                    frac_digits: Some(2),
+                    int_frac_digits: Some(2),
+                }
+            );
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn monetary_conventions_special() {
+        with_env_locale("fr_CA.UTF-8", || {
+            let conventions = super::monetary_conventions();
+            assert_eq!(conventions.n_sign_posn, Some(SignPosition::Parentheses));
+            assert_eq!(conventions.p_sign_posn, Some(SignPosition::BeforeAll));
+            assert_eq!(conventions.n_cs_precedes, Some(false));
+            assert_eq!(conventions.n_sep_by_space, Some(1));
+        });
+        with_env_locale("ja_JP.UTF-8", || {
+            let conventions = super::monetary_conventions();
+            assert_eq!(conventions.n_sign_posn, Some(SignPosition::AfterCurrency));
+            assert_eq!(conventions.frac_digits, Some(0));
+        });
+    }
+
+    #[test]
+    fn sign_position() {
+        assert_eq!(SignPosition::from_raw(0), Some(SignPosition::Parentheses));
+        assert_eq!(SignPosition::from_raw(4), Some(SignPosition::AfterCurrency));
+        assert_eq!(SignPosition::from_raw(5), None);
+        assert_eq!(SignPosition::from_raw(i8::MAX), None);
+    }
+}
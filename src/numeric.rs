@@ -0,0 +1,725 @@
+//! Numeric and monetary formatting conventions of the current locale.
+
+use std::ffi::CStr;
+
+use libc::c_char;
+
+use errno::errno;
+
+use crate::util::locale_string;
+use crate::LocaleError;
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        pub fn localeconv_native(lc: *mut libc::lconv) -> libc::locale_t;
+    }
+}
+
+/// Formatting conventions of the current locale, copied from the C `struct lconv`.
+///
+/// The `String` fields are converted to UTF-8 from the codeset of the current locale.
+/// The `i8` fields are `i8::MAX` (`CHAR_MAX` in C) if the value is not available in the current locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lconv {
+    /// Decimal-point character used to format non-monetary quantities.
+    pub decimal_point: String,
+    /// Character used to separate groups of digits before the decimal point in non-monetary quantities.
+    pub thousands_sep: String,
+    /// Sizes of each group of digits in non-monetary quantities, starting at the decimal point.
+    ///
+    /// The last element is used repeatedly for the remaining digits.
+    /// An element of `CHAR_MAX` in C means that no further grouping is performed.
+    pub grouping: Vec<u8>,
+    /// International currency symbol, e.g. `"USD "`.
+    pub int_curr_symbol: String,
+    /// Local currency symbol, e.g. `"$"`.
+    pub currency_symbol: String,
+    /// Decimal-point character used to format monetary quantities.
+    pub mon_decimal_point: String,
+    /// Character used to separate groups of digits before the decimal point in monetary quantities.
+    pub mon_thousands_sep: String,
+    /// Same as `grouping` for monetary quantities.
+    pub mon_grouping: Vec<u8>,
+    /// String used to indicate a non-negative monetary quantity.
+    pub positive_sign: String,
+    /// String used to indicate a negative monetary quantity.
+    pub negative_sign: String,
+    /// Number of fractional digits in internationally formatted monetary quantities.
+    pub int_frac_digits: i8,
+    /// Number of fractional digits in locally formatted monetary quantities.
+    pub frac_digits: i8,
+    /// 1 if `currency_symbol` precedes a non-negative monetary quantity, 0 if it succeeds.
+    pub p_cs_precedes: i8,
+    /// Separation of `currency_symbol`, the sign string and a non-negative monetary quantity.
+    pub p_sep_by_space: i8,
+    /// 1 if `currency_symbol` precedes a negative monetary quantity, 0 if it succeeds.
+    pub n_cs_precedes: i8,
+    /// Separation of `currency_symbol`, the sign string and a negative monetary quantity.
+    pub n_sep_by_space: i8,
+    /// Positioning of `positive_sign` for a non-negative monetary quantity.
+    pub p_sign_posn: i8,
+    /// Positioning of `negative_sign` for a negative monetary quantity.
+    pub n_sign_posn: i8,
+}
+
+/// Returns the formatting conventions of the current locale.
+///
+/// The locale is specified by the environment (`LC_ALL`, `LC_NUMERIC`, `LC_MONETARY`, `LANG`).
+/// On glibc the values are read from the locale by `nl_langinfo_l`, so this is thread-safe.
+/// Elsewhere they are copied from the C `localeconv`, which returns a buffer shared by all threads,
+/// so the values may be mixed with those of another locale if another thread calls `localeconv` at the same time.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::numeric::localeconv;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(localeconv().thousands_sep, "");
+/// std::env::set_var("LC_ALL", "en_US");
+/// let lconv = localeconv();
+/// assert_eq!(lconv.decimal_point, ".");
+/// assert_eq!(lconv.thousands_sep, ",");
+/// assert_eq!(lconv.grouping, [3, 3]);
+/// ```
+pub fn localeconv() -> Lconv {
+    let mut lc = unsafe { std::mem::zeroed::<libc::lconv>() };
+    let locale = unsafe { c::localeconv_native(&mut lc) };
+    if locale.is_null() {
+        panic!("localeconv_native failed. error={}", errno());
+    }
+    let lconv = Lconv {
+        decimal_point: locale_string(lc.decimal_point, locale),
+        thousands_sep: locale_string(lc.thousands_sep, locale),
+        grouping: bytes(lc.grouping),
+        int_curr_symbol: locale_string(lc.int_curr_symbol, locale),
+        currency_symbol: locale_string(lc.currency_symbol, locale),
+        mon_decimal_point: locale_string(lc.mon_decimal_point, locale),
+        mon_thousands_sep: locale_string(lc.mon_thousands_sep, locale),
+        mon_grouping: bytes(lc.mon_grouping),
+        positive_sign: locale_string(lc.positive_sign, locale),
+        negative_sign: locale_string(lc.negative_sign, locale),
+        int_frac_digits: char_value(lc.int_frac_digits),
+        frac_digits: char_value(lc.frac_digits),
+        p_cs_precedes: char_value(lc.p_cs_precedes),
+        p_sep_by_space: char_value(lc.p_sep_by_space),
+        n_cs_precedes: char_value(lc.n_cs_precedes),
+        n_sep_by_space: char_value(lc.n_sep_by_space),
+        p_sign_posn: char_value(lc.p_sign_posn),
+        n_sign_posn: char_value(lc.n_sign_posn),
+    };
+    unsafe { libc::freelocale(locale) };
+    lconv
+}
+
+/// Grouping of digits in non-monetary quantities of a locale, decoded from `grouping` of [`Lconv`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grouping {
+    /// Sizes of each group of digits, starting at the decimal point.
+    ///
+    /// `None` (`CHAR_MAX` in C) means that no further grouping is performed.
+    /// Otherwise the last size is used repeatedly for the remaining digits.
+    pub groups: Vec<Option<u8>>,
+}
+
+impl Grouping {
+    /// Returns `true` if digits are not grouped at all.
+    pub fn is_empty(&self) -> bool {
+        self.group_size(0).is_none()
+    }
+
+    /// Returns the size of the `index`-th group counted from the decimal point,
+    /// or `None` if the digits of the group and beyond are not grouped.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::numeric::Grouping;
+    ///
+    /// let indian = Grouping { groups: vec![Some(3), Some(2)] };
+    /// assert_eq!(indian.group_size(0), Some(3));
+    /// assert_eq!(indian.group_size(1), Some(2));
+    /// assert_eq!(indian.group_size(5), Some(2));
+    /// let once = Grouping { groups: vec![Some(3), None] };
+    /// assert_eq!(once.group_size(1), None);
+    /// ```
+    pub fn group_size(&self, index: usize) -> Option<usize> {
+        let size = match self.groups.get(index) {
+            Some(size) => *size,
+            None => *self.groups.last()?,
+        };
+        match size {
+            Some(0) | None => None,
+            Some(size) => Some(size as usize),
+        }
+    }
+}
+
+/// Returns the grouping of digits in non-monetary quantities of the current locale.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::numeric::numeric_grouping;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert!(numeric_grouping().is_empty());
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(numeric_grouping().group_size(0), Some(3));
+/// assert_eq!(numeric_grouping().group_size(10), Some(3));
+/// ```
+pub fn numeric_grouping() -> Grouping {
+    decode_grouping(&localeconv().grouping)
+}
+
+fn decode_grouping(grouping: &[u8]) -> Grouping {
+    let mut groups = Vec::new();
+    for &size in grouping {
+        if size == c_char::MAX as u8 {
+            groups.push(None);
+            break;
+        }
+        groups.push(Some(size));
+    }
+    Grouping { groups }
+}
+
+/// Formats `n` inserting the thousands separator of the current locale according to its grouping.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::numeric::format_grouped;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(format_grouped(1234567), "1234567");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(format_grouped(1234567), "1,234,567");
+/// assert_eq!(format_grouped(-1234567), "-1,234,567");
+/// ```
+pub fn format_grouped(n: i64) -> String {
+    let lconv = localeconv();
+    let digits = group_digits(
+        &n.unsigned_abs().to_string(),
+        &lconv.thousands_sep,
+        &lconv.grouping,
+    );
+    if n < 0 {
+        format!("-{}", digits)
+    } else {
+        digits
+    }
+}
+
+/// Formats `value` rounded to `fraction_digits` digits after the decimal point,
+/// using the decimal point, the thousands separator and the grouping of the current locale.
+///
+/// A negative value is preceded by `-` unless it is rounded to zero, as [`crate::monetary::format_monetary_manual`].
+/// Infinity and NaN are formatted as `inf`, `-inf` and `NaN` without grouping.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::numeric::format_grouped_f64;
+///
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(format_grouped_f64(1234.5, 2), "1234.50");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(format_grouped_f64(1234.5, 2), "1,234.50");
+/// assert_eq!(format_grouped_f64(-1234567.891, 1), "-1,234,567.9");
+/// ```
+pub fn format_grouped_f64(value: f64, fraction_digits: usize) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let lconv = localeconv();
+    let formatted = format!("{:.*}", fraction_digits, value.abs());
+    let (integer, fraction) = match formatted.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (formatted.as_str(), None),
+    };
+    let mut result = String::new();
+    if value < 0.0 && formatted.bytes().any(|b| b != b'0' && b != b'.') {
+        result.push('-');
+    }
+    result.push_str(&group_digits(
+        integer,
+        &lconv.thousands_sep,
+        &lconv.grouping,
+    ));
+    if let Some(fraction) = fraction {
+        result.push_str(&lconv.decimal_point);
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// Parses `s` as a number written with the decimal point and the thousands separator of the current locale.
+///
+/// `s` may start with `-` or `+`. Thousands separators may be omitted, but if any is present,
+/// the digits before the decimal point must be grouped according to the grouping of the current locale.
+///
+/// # errors
+///
+/// Returns [`LocaleError::InvalidNumber`] if `s` is empty, contains a character other than digits,
+/// the separators and the sign, or contains a misplaced separator.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::numeric::parse_grouped;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(parse_grouped("1,234.5"), Ok(1234.5));
+/// assert_eq!(parse_grouped("-1234.5"), Ok(-1234.5));
+/// assert!(parse_grouped("12,34.5").is_err());
+/// ```
+pub fn parse_grouped(s: &str) -> Result<f64, LocaleError> {
+    let lconv = localeconv();
+    let (negative, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (integer, fraction) = match unsigned.split_once(lconv.decimal_point.as_str()) {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (unsigned, ""),
+    };
+    let digits = ungroup_digits(integer, &lconv)?;
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (digits.is_empty() && fraction.is_empty()) || !is_digits(&digits) || !is_digits(fraction) {
+        return Err(LocaleError::InvalidNumber);
+    }
+    let number = format!(
+        "{}{}.{}",
+        if negative { "-" } else { "" },
+        if digits.is_empty() { "0" } else { &digits },
+        fraction
+    );
+    number.parse().map_err(|_| LocaleError::InvalidNumber)
+}
+
+/// Parses `s` as an unsigned integer written with the thousands separator of the current locale.
+///
+/// `s` may start with `+`. Thousands separators may be omitted, but if any is present,
+/// the digits must be grouped according to the grouping of the current locale.
+///
+/// # errors
+///
+/// Returns [`LocaleError::InvalidNumber`] if `s` is empty, contains a character other than digits,
+/// the separators and the sign, contains a misplaced separator, or contains the decimal point.
+/// Returns [`LocaleError::NumberOutOfRange`] if the value does not fit in `u64`.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::numeric::parse_grouped_u64;
+///
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(parse_grouped_u64("1,000,000"), Ok(1000000));
+/// assert_eq!(parse_grouped_u64("1000000"), Ok(1000000));
+/// assert!(parse_grouped_u64("1,000.0").is_err());
+/// ```
+pub fn parse_grouped_u64(s: &str) -> Result<u64, LocaleError> {
+    let lconv = localeconv();
+    let unsigned = s.strip_prefix('+').unwrap_or(s);
+    if unsigned.contains(lconv.decimal_point.as_str()) {
+        return Err(LocaleError::InvalidNumber);
+    }
+    let digits = ungroup_digits(unsigned, &lconv)?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(LocaleError::InvalidNumber);
+    }
+    digits.parse().map_err(|_| LocaleError::NumberOutOfRange)
+}
+
+/// Removes the thousands separators of `lconv` from `integer`, checking that they are placed according to the grouping.
+fn ungroup_digits(integer: &str, lconv: &Lconv) -> Result<String, LocaleError> {
+    if lconv.thousands_sep.is_empty() {
+        return Ok(integer.to_string());
+    }
+    let digits = integer.replace(lconv.thousands_sep.as_str(), "");
+    if digits.len() != integer.len()
+        && group_digits(&digits, &lconv.thousands_sep, &lconv.grouping) != integer
+    {
+        return Err(LocaleError::InvalidNumber);
+    }
+    Ok(digits)
+}
+
+/// Inserts `sep` into the ASCII digit string `digits` according to `grouping` of `Lconv`.
+pub(crate) fn group_digits(digits: &str, sep: &str, grouping: &[u8]) -> String {
+    let mut groups = Vec::new();
+    let mut rest = digits;
+    let mut sizes = grouping.iter();
+    let mut size = sizes.next().copied();
+    while let Some(s) = size {
+        if s == 0 || s == c_char::MAX as u8 || rest.len() <= s as usize {
+            break;
+        }
+        let (head, tail) = rest.split_at(rest.len() - s as usize);
+        groups.push(tail);
+        rest = head;
+        if let Some(next) = sizes.next() {
+            size = Some(*next);
+        }
+    }
+    groups.push(rest);
+    groups.reverse();
+    groups.join(sep)
+}
+
+fn bytes(s: *const c_char) -> Vec<u8> {
+    unsafe { CStr::from_ptr(s) }.to_bytes().to_vec()
+}
+
+// `c_char` is `u8` on some targets.
+#[allow(clippy::unnecessary_cast)]
+fn char_value(c: c_char) -> i8 {
+    if c == c_char::MAX {
+        i8::MAX
+    } else {
+        c as i8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::with_env_locale;
+
+    #[test]
+    fn localeconv_posix() {
+        with_env_locale("POSIX", || {
+            let lconv = localeconv();
+            assert_eq!(lconv.decimal_point, ".");
+            assert_eq!(lconv.thousands_sep, "");
+            assert!(lconv.grouping.is_empty());
+            assert_eq!(lconv.currency_symbol, "");
+            assert_eq!(lconv.frac_digits, i8::MAX);
+            assert_eq!(lconv.p_cs_precedes, i8::MAX);
+        });
+    }
+
+    /// Returns the conventions of the global locale by the C `localeconv`.
+    fn c_localeconv() -> Lconv {
+        let lc = unsafe { &*libc::localeconv() };
+        let string = |s| unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned();
+        Lconv {
+            decimal_point: string(lc.decimal_point),
+            thousands_sep: string(lc.thousands_sep),
+            grouping: bytes(lc.grouping),
+            int_curr_symbol: string(lc.int_curr_symbol),
+            currency_symbol: string(lc.currency_symbol),
+            mon_decimal_point: string(lc.mon_decimal_point),
+            mon_thousands_sep: string(lc.mon_thousands_sep),
+            mon_grouping: bytes(lc.mon_grouping),
+            positive_sign: string(lc.positive_sign),
+            negative_sign: string(lc.negative_sign),
+            int_frac_digits: char_value(lc.int_frac_digits),
+            frac_digits: char_value(lc.frac_digits),
+            p_cs_precedes: char_value(lc.p_cs_precedes),
+            p_sep_by_space: char_value(lc.p_sep_by_space),
+            n_cs_precedes: char_value(lc.n_cs_precedes),
+            n_sep_by_space: char_value(lc.n_sep_by_space),
+            p_sign_posn: char_value(lc.p_sign_posn),
+            n_sign_posn: char_value(lc.n_sign_posn),
+        }
+    }
+
+    #[test]
+    fn localeconv_same_as_c() {
+        // The strings of these locales are ASCII, so they need no conversion.
+        for locale in ["POSIX", "en_US.UTF-8"] {
+            with_env_locale(locale, || {
+                assert_eq!(localeconv(), c_localeconv(), "{}", locale);
+            });
+        }
+    }
+
+    #[test]
+    fn localeconv_i18n() {
+        with_env_locale("en_US", || {
+            let lconv = localeconv();
+            assert_eq!(lconv.decimal_point, ".");
+            assert_eq!(lconv.thousands_sep, ",");
+            assert_eq!(lconv.grouping, [3, 3]);
+            assert_eq!(lconv.int_curr_symbol, "USD ");
+            assert_eq!(lconv.currency_symbol, "$");
+            assert_eq!(lconv.mon_decimal_point, ".");
+            assert_eq!(lconv.negative_sign, "-");
+            assert_eq!(lconv.frac_digits, 2);
+            assert_eq!(lconv.p_cs_precedes, 1);
+        });
+    }
+
+    #[test]
+    fn format_grouped() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::format_grouped(1234567), "1234567");
+            assert_eq!(super::format_grouped(-1234567), "-1234567");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_grouped(0), "0");
+            assert_eq!(super::format_grouped(123), "123");
+            assert_eq!(super::format_grouped(1234), "1,234");
+            assert_eq!(super::format_grouped(123456), "123,456");
+            assert_eq!(super::format_grouped(1234567), "1,234,567");
+            assert_eq!(super::format_grouped(-1234567), "-1,234,567");
+            assert_eq!(
+                super::format_grouped(i64::MIN),
+                "-9,223,372,036,854,775,808"
+            );
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn format_grouped_special() {
+        with_env_locale("de_DE", || {
+            assert_eq!(super::format_grouped(1234567), "1.234.567");
+        });
+        with_env_locale("en_IN", || {
+            assert_eq!(super::format_grouped(1234567), "12,34,567");
+            assert_eq!(super::format_grouped(-123456789), "-12,34,56,789");
+        });
+    }
+
+    #[test]
+    fn format_grouped_f64() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::format_grouped_f64(1234567.25, 2), "1234567.25");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_grouped_f64(1234.5, 2), "1,234.50");
+            assert_eq!(super::format_grouped_f64(12.345, 0), "12");
+            assert_eq!(super::format_grouped_f64(999.996, 2), "1,000.00");
+            assert_eq!(super::format_grouped_f64(-1234.5, 1), "-1,234.5");
+            assert_eq!(super::format_grouped_f64(0.0, 3), "0.000");
+            assert_eq!(super::format_grouped_f64(f64::INFINITY, 2), "inf");
+            assert_eq!(super::format_grouped_f64(f64::NAN, 2), "NaN");
+        });
+    }
+
+    #[test]
+    fn format_grouped_f64_no_grouping() {
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_grouped_f64(123.456, 2), "123.46");
+            assert_eq!(super::format_grouped_f64(-0.5, 2), "-0.50");
+        });
+    }
+
+    #[test]
+    fn format_grouped_f64_negative_zero() {
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_grouped_f64(-0.001, 2), "0.00");
+            assert_eq!(super::format_grouped_f64(-0.4, 0), "0");
+            assert_eq!(super::format_grouped_f64(-0.0, 1), "0.0");
+            assert_eq!(super::format_grouped_f64(-0.005, 3), "-0.005");
+            assert_eq!(
+                super::format_grouped_f64(-0.001, 2),
+                crate::monetary::format_monetary_manual(-0.001)
+            );
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn format_grouped_f64_special() {
+        with_env_locale("de_DE", || {
+            assert_eq!(super::format_grouped_f64(1234.5, 2), "1.234,50");
+            assert_eq!(super::format_grouped_f64(-1234567.891, 2), "-1.234.567,89");
+            assert_eq!(super::format_grouped_f64(12.5, 0), "12");
+        });
+    }
+
+    #[test]
+    fn parse_grouped() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::parse_grouped("1234.5"), Ok(1234.5));
+            assert_eq!(
+                super::parse_grouped("1,234.5"),
+                Err(LocaleError::InvalidNumber)
+            );
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::parse_grouped("1,234.50"), Ok(1234.5));
+            assert_eq!(super::parse_grouped("1,234,567"), Ok(1234567.0));
+            assert_eq!(super::parse_grouped("1234567"), Ok(1234567.0));
+            assert_eq!(super::parse_grouped("-1,234.5"), Ok(-1234.5));
+            assert_eq!(super::parse_grouped("+12.5"), Ok(12.5));
+            assert_eq!(super::parse_grouped(".5"), Ok(0.5));
+            assert_eq!(super::parse_grouped("5."), Ok(5.0));
+        });
+    }
+
+    #[test]
+    fn parse_grouped_invalid() {
+        with_env_locale("en_US", || {
+            for s in [
+                "", "-", ".", "12,34.5", "1,2345", ",123", "123,", "1,,234", "1.2.3", "1.2,3",
+                "1e5", " 12", "--1", "abc",
+            ] {
+                assert_eq!(
+                    super::parse_grouped(s),
+                    Err(LocaleError::InvalidNumber),
+                    "{}",
+                    s
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn parse_grouped_u64() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::parse_grouped_u64("1000000"), Ok(1000000));
+            assert_eq!(
+                super::parse_grouped_u64("1,000,000"),
+                Err(LocaleError::InvalidNumber)
+            );
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::parse_grouped_u64("1,000,000"), Ok(1000000));
+            assert_eq!(super::parse_grouped_u64("1000000"), Ok(1000000));
+            assert_eq!(super::parse_grouped_u64("+1,234"), Ok(1234));
+            assert_eq!(super::parse_grouped_u64("0"), Ok(0));
+            assert_eq!(
+                super::parse_grouped_u64("18,446,744,073,709,551,615"),
+                Ok(u64::MAX)
+            );
+            assert_eq!(
+                super::parse_grouped_u64("18,446,744,073,709,551,616"),
+                Err(LocaleError::NumberOutOfRange)
+            );
+            assert_eq!(
+                super::parse_grouped_u64("99999999999999999999999"),
+                Err(LocaleError::NumberOutOfRange)
+            );
+        });
+    }
+
+    #[test]
+    fn parse_grouped_u64_invalid() {
+        with_env_locale("en_US", || {
+            for s in [
+                "", "+", "-1", "1,000.0", "1.5", "5.", ".5", "12,34", "1,2345", ",123", "123,",
+                "1,,234", " 12", "++1", "1e5", "abc",
+            ] {
+                assert_eq!(
+                    super::parse_grouped_u64(s),
+                    Err(LocaleError::InvalidNumber),
+                    "{}",
+                    s
+                );
+            }
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn parse_grouped_special() {
+        with_env_locale("de_DE", || {
+            assert_eq!(super::parse_grouped("1.234,50"), Ok(1234.5));
+            assert_eq!(super::parse_grouped("-1.234.567,5"), Ok(-1234567.5));
+            assert_eq!(
+                super::parse_grouped("1,234.50"),
+                Err(LocaleError::InvalidNumber)
+            );
+            assert_eq!(super::parse_grouped_u64("1.000.000"), Ok(1000000));
+            assert_eq!(
+                super::parse_grouped_u64("1.000,5"),
+                Err(LocaleError::InvalidNumber)
+            );
+            assert_eq!(
+                super::parse_grouped_u64("1,000"),
+                Err(LocaleError::InvalidNumber)
+            );
+        });
+        with_env_locale("en_IN", || {
+            assert_eq!(super::parse_grouped("12,34,567"), Ok(1234567.0));
+            assert_eq!(super::parse_grouped_u64("10,00,000"), Ok(1000000));
+            assert_eq!(
+                super::parse_grouped("1,234,567"),
+                Err(LocaleError::InvalidNumber)
+            );
+        });
+    }
+
+    #[test]
+    fn numeric_grouping() {
+        with_env_locale("POSIX", || {
+            let grouping = super::numeric_grouping();
+            assert_eq!(grouping.groups, []);
+            assert!(grouping.is_empty());
+            assert_eq!(grouping.group_size(0), None);
+        });
+        with_env_locale("en_US", || {
+            let grouping = super::numeric_grouping();
+            assert_eq!(grouping.groups, [Some(3), Some(3)]);
+            assert!(!grouping.is_empty());
+            assert_eq!(grouping.group_size(0), Some(3));
+            assert_eq!(grouping.group_size(1), Some(3));
+            assert_eq!(grouping.group_size(100), Some(3));
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn numeric_grouping_special() {
+        with_env_locale("en_IN", || {
+            let grouping = super::numeric_grouping();
+            assert_eq!(grouping.groups, [Some(3), Some(2)]);
+            assert_eq!(grouping.group_size(0), Some(3));
+            assert_eq!(grouping.group_size(1), Some(2));
+            assert_eq!(grouping.group_size(2), Some(2));
+        });
+    }
+
+    #[test]
+    fn decode_grouping() {
+        assert_eq!(super::decode_grouping(&[]).groups, []);
+        assert_eq!(super::decode_grouping(&[3]).groups, [Some(3)]);
+        assert_eq!(super::decode_grouping(&[3, 2]).groups, [Some(3), Some(2)]);
+        let once = super::decode_grouping(&[3, c_char::MAX as u8, 2]);
+        assert_eq!(once.groups, [Some(3), None]);
+        assert_eq!(once.group_size(0), Some(3));
+        assert_eq!(once.group_size(1), None);
+        assert_eq!(once.group_size(2), None);
+        assert!(super::decode_grouping(&[c_char::MAX as u8]).is_empty());
+    }
+
+    #[test]
+    fn group_digits() {
+        assert_eq!(super::group_digits("1234567", ",", &[]), "1234567");
+        assert_eq!(super::group_digits("1234567", ",", &[3]), "1,234,567");
+        assert_eq!(super::group_digits("1234567", ",", &[3, 2]), "12,34,567");
+        assert_eq!(
+            super::group_digits("1234567", ",", &[3, c_char::MAX as u8]),
+            "1234,567"
+        );
+        assert_eq!(super::group_digits("123", ",", &[3]), "123");
+    }
+
+    #[test]
+    #[ignore]
+    fn localeconv_special() {
+        with_env_locale("de_DE", || {
+            let lconv = localeconv();
+            assert_eq!(lconv.decimal_point, ",");
+            assert_eq!(lconv.thousands_sep, ".");
+            assert_eq!(lconv.int_curr_symbol, "EUR ");
+        });
+        with_env_locale("de_DE.UTF-8", || {
+            assert_eq!(localeconv().currency_symbol, "\u{20AC}");
+        });
+    }
+}
@@ -0,0 +1,538 @@
+//! Date and time formatting dependent on `LC_TIME`.
+
+use std::ffi::CString;
+
+use crate::util::grow_string;
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        pub fn strftime_native(
+            dest: *mut libc::c_char,
+            n: libc::size_t,
+            format: *const libc::c_char,
+            tm: *const libc::tm,
+        ) -> libc::ssize_t;
+    }
+}
+
+/// Broken-down time corresponding to `struct tm`.
+///
+/// The fields have the same meanings and ranges as those of `struct tm`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Tm {
+    /// Seconds after the minute (0-60).
+    pub tm_sec: i32,
+    /// Minutes after the hour (0-59).
+    pub tm_min: i32,
+    /// Hours since midnight (0-23).
+    pub tm_hour: i32,
+    /// Day of the month (1-31).
+    pub tm_mday: i32,
+    /// Months since January (0-11).
+    pub tm_mon: i32,
+    /// Years since 1900.
+    pub tm_year: i32,
+    /// Days since Sunday (0-6).
+    pub tm_wday: i32,
+    /// Days since January 1 (0-365).
+    pub tm_yday: i32,
+    /// Positive if daylight saving time is in effect, 0 if not, negative if unknown.
+    pub tm_isdst: i32,
+}
+
+impl Tm {
+    /// Returns a builder of `Tm` for 1970-01-01 00:00:00.
+    ///
+    /// # examples
+    ///
+    /// ```
+    /// use rust_locale::time::{format_time, Tm};
+    ///
+    /// let tm = Tm::builder().year(2024).month(3).day(15).build();
+    /// assert_eq!(tm.tm_wday, 5);
+    /// assert_eq!(tm.tm_yday, 74);
+    /// std::env::set_var("LC_ALL", "en_US");
+    /// assert_eq!(format_time("%A %x", &tm), "Friday 03/15/2024");
+    /// ```
+    pub fn builder() -> TmBuilder {
+        TmBuilder::default()
+    }
+
+    fn to_raw(self) -> libc::tm {
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        tm.tm_sec = self.tm_sec;
+        tm.tm_min = self.tm_min;
+        tm.tm_hour = self.tm_hour;
+        tm.tm_mday = self.tm_mday;
+        tm.tm_mon = self.tm_mon;
+        tm.tm_year = self.tm_year;
+        tm.tm_wday = self.tm_wday;
+        tm.tm_yday = self.tm_yday;
+        tm.tm_isdst = self.tm_isdst;
+        tm
+    }
+}
+
+/// Builder of [`Tm`], returned by [`Tm::builder`].
+///
+/// The month and the year are given as the calendar values, e.g. `month(1)` and `year(2024)` for January 2024,
+/// unlike `tm_mon` and `tm_year`.
+/// The values are not normalized, so out-of-range values are kept as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TmBuilder {
+    year: i32,
+    month: i32,
+    day: i32,
+    hour: i32,
+    minute: i32,
+    second: i32,
+    weekday: Option<i32>,
+    year_day: Option<i32>,
+    isdst: i32,
+}
+
+impl TmBuilder {
+    /// Sets the year, e.g. 2024.
+    pub fn year(mut self, year: i32) -> TmBuilder {
+        self.year = year;
+        self
+    }
+
+    /// Sets the month (1-12).
+    pub fn month(mut self, month: i32) -> TmBuilder {
+        self.month = month;
+        self
+    }
+
+    /// Sets the day of the month (1-31).
+    pub fn day(mut self, day: i32) -> TmBuilder {
+        self.day = day;
+        self
+    }
+
+    /// Sets the hours since midnight (0-23).
+    pub fn hour(mut self, hour: i32) -> TmBuilder {
+        self.hour = hour;
+        self
+    }
+
+    /// Sets the minutes after the hour (0-59).
+    pub fn minute(mut self, minute: i32) -> TmBuilder {
+        self.minute = minute;
+        self
+    }
+
+    /// Sets the seconds after the minute (0-60).
+    pub fn second(mut self, second: i32) -> TmBuilder {
+        self.second = second;
+        self
+    }
+
+    /// Sets the days since Sunday (0-6).
+    ///
+    /// If not set, it is computed from the date.
+    pub fn weekday(mut self, weekday: i32) -> TmBuilder {
+        self.weekday = Some(weekday);
+        self
+    }
+
+    /// Sets the days since January 1 (0-365).
+    ///
+    /// If not set, it is computed from the date.
+    pub fn year_day(mut self, year_day: i32) -> TmBuilder {
+        self.year_day = Some(year_day);
+        self
+    }
+
+    /// Sets `tm_isdst`: positive if daylight saving time is in effect, 0 if not, negative if unknown.
+    pub fn isdst(mut self, isdst: i32) -> TmBuilder {
+        self.isdst = isdst;
+        self
+    }
+
+    /// Returns the `Tm` of the date and time set to `self`.
+    ///
+    /// The days since Sunday and since January 1 are computed in the proleptic Gregorian calendar unless set explicitly.
+    pub fn build(self) -> Tm {
+        Tm {
+            tm_sec: self.second,
+            tm_min: self.minute,
+            tm_hour: self.hour,
+            tm_mday: self.day,
+            tm_mon: self.month - 1,
+            tm_year: self.year - 1900,
+            tm_wday: self
+                .weekday
+                .unwrap_or_else(|| weekday(self.year, self.month, self.day)),
+            tm_yday: self
+                .year_day
+                .unwrap_or_else(|| year_day(self.year, self.month, self.day)),
+            tm_isdst: self.isdst,
+        }
+    }
+}
+
+impl Default for TmBuilder {
+    fn default() -> Self {
+        TmBuilder {
+            year: 1970,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            weekday: None,
+            year_day: None,
+            isdst: 0,
+        }
+    }
+}
+
+/// Returns the days since March 1, 0000 of the date in the proleptic Gregorian calendar.
+fn days_from_march(year: i32, month: i32, day: i32) -> i64 {
+    // January and February are the 11th and 12th months of the previous year.
+    let (year, month) = if month <= 2 {
+        (i64::from(year) - 1, i64::from(month) + 9)
+    } else {
+        (i64::from(year), i64::from(month) - 3)
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * month + 2) / 5 + i64::from(day) - 1;
+    era * 146097 + year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year
+}
+
+/// Returns the days since Sunday of the date.
+fn weekday(year: i32, month: i32, day: i32) -> i32 {
+    // 0000-03-01 is a Wednesday.
+    (days_from_march(year, month, day) + 3).rem_euclid(7) as i32
+}
+
+/// Returns the days since January 1 of the date.
+fn year_day(year: i32, month: i32, day: i32) -> i32 {
+    (days_from_march(year, month, day) - days_from_march(year, 1, 1)) as i32
+}
+
+/// Formats `tm` according to `fmt` as `strftime` in the current locale.
+///
+/// The locale is specified by the environment (`LC_ALL`, `LC_TIME`, `LANG`).
+/// Conversion specifications such as `%A` (the full weekday name) and `%x` (the preferred date representation)
+/// are replaced according to the current locale.
+/// The modifiers `E` and `O` are passed to `strftime` as they are,
+/// so `%EY` gives the year in the era of the locale, e.g. "令和06年" in `ja_JP.UTF-8`,
+/// and `%Od` gives the day of the month in the alternative digits of the locale.
+/// If the locale defines no era or alternative digits, they fall back to the unmodified conversions,
+/// e.g. `%EY` is the same as `%Y`.
+///
+/// # panics
+///
+/// Panics if `fmt` contains a NUL character, or if the result is longer than 64 KiB.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::time::{format_time, Tm};
+///
+/// let tm = Tm {
+///     tm_mday: 1,
+///     tm_mon: 0,
+///     tm_year: 124,
+///     tm_wday: 1,
+///     ..Tm::default()
+/// };
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(format_time("%A %x", &tm), "Monday 01/01/24");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(format_time("%A %x", &tm), "Monday 01/01/2024");
+/// ```
+pub fn format_time(fmt: &str, tm: &Tm) -> String {
+    let format = CString::new(fmt).unwrap();
+    let tm = tm.to_raw();
+    grow_string("strftime_native", |dest, n| unsafe {
+        c::strftime_native(dest, n, format.as_ptr(), &tm)
+    })
+}
+
+/// Formats the date of `tm` in the preferred representation of the current locale.
+///
+/// The format is [`crate::langinfo::date_format`] (`D_FMT`), e.g. `%m/%d/%Y` in `en_US` and `%d.%m.%Y` in `de_DE`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::time::{format_date, Tm};
+///
+/// let tm = Tm::builder().year(2024).month(3).day(15).build();
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(format_date(&tm), "03/15/24");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(format_date(&tm), "03/15/2024");
+/// ```
+pub fn format_date(tm: &Tm) -> String {
+    // `%x` is replaced by `D_FMT` in `strftime`, which also works for a format in a codeset other than UTF-8.
+    format_time("%x", tm)
+}
+
+/// Formats the time of day of `tm` in the preferred representation of the current locale.
+///
+/// The format is [`crate::langinfo::time_format`] (`T_FMT`), e.g. `%H:%M:%S` in `POSIX` and `%r` in `en_US`.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::time::{format_time_of_day, Tm};
+///
+/// let tm = Tm::builder().hour(13).minute(5).second(9).build();
+/// std::env::set_var("LC_ALL", "POSIX");
+/// assert_eq!(format_time_of_day(&tm), "13:05:09");
+/// std::env::set_var("LC_ALL", "en_US");
+/// assert_eq!(format_time_of_day(&tm), "01:05:09 PM");
+/// ```
+pub fn format_time_of_day(tm: &Tm) -> String {
+    // `%X` is replaced by `T_FMT` as `%x` in `format_date`.
+    format_time("%X", tm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::with_env_locale;
+
+    fn new_year_2024() -> Tm {
+        Tm {
+            tm_sec: 9,
+            tm_min: 5,
+            tm_hour: 13,
+            tm_mday: 1,
+            tm_mon: 0,
+            tm_year: 124,
+            tm_wday: 1,
+            tm_yday: 0,
+            tm_isdst: 0,
+        }
+    }
+
+    #[test]
+    fn builder() {
+        assert_eq!(
+            Tm::builder()
+                .year(2024)
+                .month(1)
+                .day(1)
+                .hour(13)
+                .minute(5)
+                .second(9)
+                .build(),
+            new_year_2024()
+        );
+        let epoch = Tm::builder().build();
+        assert_eq!((epoch.tm_year, epoch.tm_mon, epoch.tm_mday), (70, 0, 1));
+        assert_eq!((epoch.tm_wday, epoch.tm_yday), (4, 0));
+        let explicit = Tm::builder().weekday(0).year_day(10).isdst(1).build();
+        assert_eq!(
+            (explicit.tm_wday, explicit.tm_yday, explicit.tm_isdst),
+            (0, 10, 1)
+        );
+    }
+
+    #[test]
+    fn builder_computed_days() {
+        let days = |year, month, day| {
+            let tm = Tm::builder().year(year).month(month).day(day).build();
+            (tm.tm_wday, tm.tm_yday)
+        };
+        assert_eq!(days(2000, 2, 29), (2, 59));
+        assert_eq!(days(2000, 3, 1), (3, 60));
+        assert_eq!(days(1900, 3, 1), (4, 59));
+        assert_eq!(days(2023, 12, 31), (0, 364));
+        assert_eq!(days(2024, 12, 31), (2, 365));
+        assert_eq!(days(1969, 12, 31), (3, 364));
+        assert_eq!(days(1600, 1, 1), (6, 0));
+        assert_eq!(days(-1, 12, 31), (5, 364));
+    }
+
+    #[test]
+    fn builder_i18n() {
+        let tm = Tm::builder().year(2024).month(7).day(4).build();
+        with_env_locale("POSIX", || {
+            assert_eq!(super::format_time("%A %j", &tm), "Thursday 186");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_time("%A %x", &tm), "Thursday 07/04/2024");
+            assert_eq!(super::format_time("%a %U", &tm), "Thu 26");
+        });
+    }
+
+    #[test]
+    fn format_time() {
+        with_env_locale("POSIX", || {
+            let tm = new_year_2024();
+            assert_eq!(
+                super::format_time("%Y-%m-%d %H:%M:%S", &tm),
+                "2024-01-01 13:05:09"
+            );
+            assert_eq!(super::format_time("%A %B", &tm), "Monday January");
+            assert_eq!(super::format_time("%x %X", &tm), "01/01/24 13:05:09");
+            assert_eq!(super::format_time("", &tm), "");
+            assert_eq!(super::format_time("%%", &tm), "%");
+            assert_eq!(super::format_time(" %A ", &tm), " Monday ");
+            let long = "a".repeat(0xFFFE);
+            assert_eq!(super::format_time(&long, &tm), long);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "strftime_native failed")]
+    fn format_time_too_long() {
+        with_env_locale("POSIX", || {
+            super::format_time(&"a".repeat(0x10000), &new_year_2024());
+        });
+    }
+
+    #[test]
+    fn format_time_i18n() {
+        let tm = new_year_2024();
+        with_env_locale("POSIX", || {
+            assert_eq!(super::format_time("%X", &tm), "13:05:09");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_time("%X", &tm), "01:05:09 PM");
+            assert_eq!(super::format_time("%x", &tm), "01/01/2024");
+        });
+    }
+
+    #[test]
+    fn format_time_modifiers() {
+        let tm = new_year_2024();
+        for locale in ["POSIX", "en_US"] {
+            with_env_locale(locale, || {
+                assert_eq!(super::format_time("%EY %EC", &tm), "2024 20", "{}", locale);
+                assert_eq!(
+                    super::format_time("%Od %Om %OH %Oy", &tm),
+                    "01 01 13 24",
+                    "{}",
+                    locale
+                );
+                assert_eq!(
+                    super::format_time("%Ex", &tm),
+                    super::format_time("%x", &tm)
+                );
+            });
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn format_time_modifiers_special() {
+        let tm = new_year_2024();
+        with_env_locale("ja_JP.UTF-8", || {
+            assert_eq!(super::format_time("%EY", &tm), "\u{4EE4}\u{548C}06\u{5E74}");
+            assert_eq!(super::format_time("%EC %Ey", &tm), "\u{4EE4}\u{548C} 06");
+            assert_eq!(
+                super::format_time("%Od %OH", &tm),
+                "\u{4E00} \u{5341}\u{4E09}"
+            );
+            assert_eq!(super::format_time("%d", &tm), "01");
+        });
+        with_env_locale("fa_IR", || {
+            assert_eq!(super::format_time("%Od", &tm), "\u{06F0}\u{06F1}");
+            assert_eq!(super::format_time("%OH", &tm), "\u{06F1}\u{06F3}");
+            assert_eq!(super::format_time("%EY", &tm), "2024");
+        });
+    }
+
+    #[test]
+    fn format_date() {
+        let tm = new_year_2024();
+        for locale in ["POSIX", "en_US"] {
+            with_env_locale(locale, || {
+                let date_format = crate::langinfo::date_format();
+                assert_eq!(
+                    super::format_date(&tm),
+                    super::format_time(&date_format, &tm),
+                    "{}",
+                    locale
+                );
+                let time_format = crate::langinfo::time_format();
+                assert_eq!(
+                    super::format_time_of_day(&tm),
+                    super::format_time(&time_format, &tm),
+                    "{}",
+                    locale
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn format_date_i18n() {
+        let tm = new_year_2024();
+        with_env_locale("POSIX", || {
+            assert_eq!(super::format_date(&tm), "01/01/24");
+            assert_eq!(super::format_time_of_day(&tm), "13:05:09");
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::format_date(&tm), "01/01/2024");
+            assert_eq!(super::format_time_of_day(&tm), "01:05:09 PM");
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn format_date_special() {
+        let tm = new_year_2024();
+        with_env_locale("en_US", || {
+            let english = super::format_date(&tm);
+            with_env_locale("de_DE", || {
+                let german = super::format_date(&tm);
+                assert_eq!(english, "01/01/2024");
+                assert_eq!(german, "01.01.2024");
+                assert_ne!(english, german);
+                assert_eq!(super::format_time_of_day(&tm), "13:05:09");
+            });
+            with_env_locale("ja_JP", || {
+                assert_eq!(super::format_date(&tm), "2024\u{5E74}01\u{6708}01\u{65E5}");
+                assert_eq!(
+                    super::format_time_of_day(&tm),
+                    "13\u{6642}05\u{5206}09\u{79D2}"
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn format_time_long() {
+        with_env_locale("POSIX", || {
+            let tm = new_year_2024();
+            let fmt = "%A ".repeat(100);
+            assert_eq!(super::format_time(&fmt, &tm), "Monday ".repeat(100));
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn format_time_special() {
+        let tm = new_year_2024();
+        with_env_locale("en_US", || {
+            let english = super::format_time("%A", &tm);
+            with_env_locale("fr_FR", || {
+                let french = super::format_time("%A", &tm);
+                assert_eq!(english, "Monday");
+                assert_eq!(french, "lundi");
+                assert_ne!(english, french);
+            });
+            with_env_locale("de_DE", || {
+                assert_eq!(super::format_time("%A %x", &tm), "Montag 01.01.2024");
+                assert_eq!(super::format_time("%p", &tm), "");
+                assert_eq!(super::format_time("%A%p", &tm), "Montag");
+            });
+            with_env_locale("ja_JP.UTF-8", || {
+                assert_eq!(
+                    super::format_time("%A %x", &tm),
+                    "\u{6708}\u{66DC}\u{65E5} 2024\u{5E74}01\u{6708}01\u{65E5}"
+                );
+            });
+        });
+    }
+}
@@ -0,0 +1,204 @@
+//! Helpers shared by the modules.
+
+use libc::c_char;
+
+use errno::errno;
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        pub fn mbstoutf8(
+            dest: *mut libc::c_char,
+            n: libc::size_t,
+            locale: libc::locale_t,
+            src: *const libc::c_char,
+        ) -> libc::ssize_t;
+    }
+}
+
+/// Copies the NUL-terminated string `s` encoded in the codeset of `locale` into a `String`.
+///
+/// `locale` must contain `LC_CTYPE`.
+pub(crate) fn locale_string(s: *const c_char, locale: libc::locale_t) -> String {
+    grow_string("mbstoutf8", |dest, n| unsafe {
+        c::mbstoutf8(dest, n, locale, s)
+    })
+}
+
+/// Calls `f` with a growing buffer until the UTF-8 string written by `f` fits in it.
+///
+/// `f` takes the buffer and its size, and returns the length of the whole string excluding NUL,
+/// or a negative status on failure, on which this function panics with the message containing `name`.
+pub(crate) fn grow_string<F>(name: &str, mut f: F) -> String
+where
+    F: FnMut(*mut c_char, libc::size_t) -> libc::ssize_t,
+{
+    let mut buf = vec![0u8; 64];
+    loop {
+        let length = f(buf.as_mut_ptr() as *mut c_char, buf.len());
+        if length < 0 {
+            panic!("{} failed. status={}, error={}", name, length, errno());
+        }
+        let length = length as usize;
+        if length < buf.len() {
+            buf.truncate(length);
+            return String::from_utf8(buf).unwrap();
+        }
+        buf.resize(length + 1, 0);
+    }
+}
+
+/// Set while a thread is running a test in [`with_env_vars`].
+///
+/// `Mutex::new` is not `const` in Rust 1.60, so this is a spin lock.
+#[cfg(test)]
+static ENV_LOCKED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[cfg(test)]
+thread_local! {
+    /// The number of nested [`with_env_vars`] calls in this thread, which holds [`ENV_LOCKED`] if it is not 0.
+    static ENV_LOCK_DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Calls `f` with the environment variable `LC_ALL` set to `name`, as [`with_env_vars`].
+///
+/// `LC_ALL` is used instead of `LANG` or `LC_CTYPE` since it takes precedence over the other variables,
+/// so `name` is the locale of every category in `f`.
+#[cfg(test)]
+pub(crate) fn with_env_locale<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    with_env_vars(&[("LC_ALL", Some(name))], f)
+}
+
+/// Calls `f` with each environment variable in `vars` set to its value, or removed if the value is `None`,
+/// and the global locale set from the environment by `setlocale(LC_ALL, "")`.
+///
+/// The previous variables and global locale are restored when `f` returns or panics.
+/// The calls from multiple threads are serialized, so every test changing the environment must go through this.
+/// The calls may be nested in a thread.
+/// `setlocale` failing for a missing locale is ignored so that `f` can test the failure.
+#[cfg(test)]
+pub(crate) fn with_env_vars<T>(vars: &[(&str, Option<&str>)], f: impl FnOnce() -> T) -> T {
+    use std::ffi::{CStr, CString, OsString};
+    use std::sync::atomic::Ordering;
+
+    struct EnvLock;
+
+    impl EnvLock {
+        fn new() -> EnvLock {
+            if ENV_LOCK_DEPTH.with(|depth| depth.get()) == 0 {
+                while ENV_LOCKED
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_err()
+                {
+                    std::thread::yield_now();
+                }
+            }
+            ENV_LOCK_DEPTH.with(|depth| depth.set(depth.get() + 1));
+            EnvLock
+        }
+    }
+
+    impl Drop for EnvLock {
+        fn drop(&mut self) {
+            let depth = ENV_LOCK_DEPTH.with(|depth| {
+                depth.set(depth.get() - 1);
+                depth.get()
+            });
+            if depth == 0 {
+                ENV_LOCKED.store(false, Ordering::Release);
+            }
+        }
+    }
+
+    struct Restore {
+        vars: Vec<(String, Option<OsString>)>,
+        global: CString,
+    }
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            for (name, value) in self.vars.drain(..).rev() {
+                match value {
+                    Some(value) => std::env::set_var(name, value),
+                    None => std::env::remove_var(name),
+                }
+            }
+            unsafe { libc::setlocale(libc::LC_ALL, self.global.as_ptr()) };
+        }
+    }
+
+    // Declared first so that the lock is released after restoring.
+    let _lock = EnvLock::new();
+    let global = unsafe { libc::setlocale(libc::LC_ALL, std::ptr::null()) };
+    let _restore = Restore {
+        vars: vars
+            .iter()
+            .map(|(name, _)| (name.to_string(), std::env::var_os(name)))
+            .collect(),
+        global: unsafe { CStr::from_ptr(global) }.to_owned(),
+    };
+    for (name, value) in vars {
+        match value {
+            Some(value) => std::env::set_var(name, value),
+            None => std::env::remove_var(name),
+        }
+    }
+    let empty = CString::new("").unwrap();
+    unsafe { libc::setlocale(libc::LC_ALL, empty.as_ptr()) };
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    fn global() -> String {
+        unsafe { std::ffi::CStr::from_ptr(libc::setlocale(libc::LC_ALL, std::ptr::null())) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn with_env_locale() {
+        super::with_env_locale("POSIX", || {
+            let previous = global();
+            let inner = super::with_env_locale("en_US.UTF-8", || {
+                assert_eq!(std::env::var("LC_ALL").unwrap(), "en_US.UTF-8");
+                global()
+            });
+            assert_eq!(inner, "en_US.UTF-8");
+            assert_eq!(std::env::var("LC_ALL").unwrap(), "POSIX");
+            assert_eq!(global(), previous);
+        });
+    }
+
+    #[test]
+    fn with_env_vars() {
+        super::with_env_vars(
+            &[("LC_ALL", None), ("LC_CTYPE", None), ("LANG", None)],
+            || {
+                super::with_env_vars(&[("LC_CTYPE", Some("en_US.UTF-8"))], || {
+                    assert!(std::env::var_os("LC_ALL").is_none());
+                    assert_eq!(std::env::var("LC_CTYPE").unwrap(), "en_US.UTF-8");
+                    assert!(global().contains("LC_CTYPE=en_US.UTF-8"));
+                });
+                assert!(std::env::var_os("LC_CTYPE").is_none());
+                super::with_env_locale("xx_XX", || {
+                    assert_eq!(std::env::var("LC_ALL").unwrap(), "xx_XX");
+                });
+                assert!(std::env::var_os("LC_ALL").is_none());
+            },
+        );
+    }
+
+    #[test]
+    fn with_env_locale_panic() {
+        super::with_env_locale("POSIX", || {
+            let result = std::panic::catch_unwind(|| {
+                super::with_env_locale("en_US.UTF-8", || panic!("test"));
+            });
+            assert!(result.is_err());
+            assert_eq!(std::env::var("LC_ALL").unwrap(), "POSIX");
+        });
+        super::with_env_locale("C", || {});
+    }
+}
@@ -0,0 +1,167 @@
+//! Display width of characters dependent on `LC_CTYPE`.
+//!
+//! The width is the number of columns a character occupies on a terminal, as reported by `wcwidth`.
+
+use errno::errno;
+
+use crate::ctype::{utf8_bytes, utf8towc};
+
+mod c {
+    #[link(name = "rustlocale", kind = "static")]
+    extern "C" {
+        pub fn wcwidth_native(ch: libc::wchar_t) -> libc::c_int;
+        pub fn wcswidth_native(
+            utf8_bytes: *const libc::c_char,
+            length: libc::size_t,
+        ) -> libc::c_int;
+    }
+}
+
+/// Returns the number of columns needed to display `c` in the current locale.
+///
+/// Returns `None` if `c` is not printable in the current locale.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::width::char_width;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert_eq!(char_width('a'), Some(1));
+/// assert_eq!(char_width('\u{4E16}'), Some(2));
+/// assert_eq!(char_width('\u{0301}'), Some(0));
+/// assert_eq!(char_width('\u{0001}'), None);
+/// ```
+pub fn char_width(c: char) -> Option<usize> {
+    let wc = utf8towc(&utf8_bytes(&c)).unwrap();
+    match unsafe { c::wcwidth_native(wc) } {
+        -1 => None,
+        width if width >= 0 => Some(width as usize),
+        _ => panic!("wcwidth_native failed. error={}", errno()),
+    }
+}
+
+/// Returns the number of columns needed to display `s` in the current locale.
+///
+/// Returns `None` if `s` contains a character which is not printable in the current locale.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::width::str_width;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert_eq!(str_width("a\u{4E16}\u{754C}"), Some(5));
+/// assert_eq!(str_width("a\tb"), None);
+/// ```
+pub fn str_width(s: &str) -> Option<usize> {
+    s.chars().map(char_width).sum()
+}
+
+/// Returns the number of columns needed to display `s` in the current locale by a single call of `wcswidth`.
+///
+/// Same as [`str_width`] except that `s` is measured up to the first NUL character.
+/// This is faster for long strings because `s` is converted to a wide string at once
+/// instead of calling `wcwidth` for each character.
+///
+/// # panics
+///
+/// Panics if the locale specified by the environment cannot be loaded.
+///
+/// # examples
+///
+/// ```
+/// use rust_locale::width::str_width_native;
+///
+/// std::env::set_var("LC_ALL", "en_US.UTF-8");
+/// assert_eq!(str_width_native("a\u{4E16}\u{754C}"), Some(5));
+/// assert_eq!(str_width_native("a\tb"), None);
+/// ```
+pub fn str_width_native(s: &str) -> Option<usize> {
+    match unsafe { c::wcswidth_native(s.as_ptr() as *const libc::c_char, s.len()) } {
+        -1 => None,
+        width if width >= 0 => Some(width as usize),
+        _ => panic!("wcswidth_native failed. error={}", errno()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::with_env_locale;
+
+    #[test]
+    fn char_width() {
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(super::char_width('a'), Some(1));
+            assert_eq!(super::char_width('\u{00E9}'), Some(1));
+            assert_eq!(super::char_width('\u{4E16}'), Some(2));
+            assert_eq!(super::char_width('\u{FF21}'), Some(2));
+            assert_eq!(super::char_width('\u{0301}'), Some(0));
+            assert_eq!(super::char_width('\u{0001}'), None);
+            assert_eq!(super::char_width('\u{007F}'), None);
+        });
+    }
+
+    #[test]
+    fn char_width_i18n() {
+        with_env_locale("POSIX", || {
+            assert_eq!(super::char_width('a'), Some(1));
+            assert_eq!(super::char_width('\u{00E9}'), None);
+            assert_eq!(super::char_width('\u{4E16}'), None);
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::char_width('\u{00E9}'), Some(1));
+            assert_eq!(super::char_width('\u{4E16}'), None);
+        });
+    }
+
+    #[test]
+    fn str_width() {
+        with_env_locale("en_US.UTF-8", || {
+            assert_eq!(super::str_width(""), Some(0));
+            assert_eq!(super::str_width("abc"), Some(3));
+            assert_eq!(super::str_width("e\u{0301}"), Some(1));
+            assert_eq!(super::str_width("\u{4E16}\u{754C}"), Some(4));
+            assert_eq!(super::str_width("a\nb"), None);
+        });
+    }
+
+    #[test]
+    fn str_width_native() {
+        with_env_locale("en_US.UTF-8", || {
+            for s in [
+                "",
+                "abc",
+                "e\u{0301}",
+                "a\u{4E16}b\u{754C}c",
+                "\u{FF21}\u{FF22} ascii \u{65E5}\u{672C}",
+            ] {
+                assert_eq!(super::str_width_native(s), super::str_width(s), "{}", s);
+            }
+            assert_eq!(super::str_width_native("a\u{4E16}b\u{754C}c"), Some(7));
+            assert_eq!(super::str_width_native("a\u{0007}b"), None);
+            assert_eq!(super::str_width("a\u{0007}b"), None);
+            assert_eq!(super::str_width_native("ab\0\u{0007}"), Some(2));
+        });
+    }
+
+    #[test]
+    fn str_width_native_i18n() {
+        let s = "a\u{00E9}";
+        with_env_locale("POSIX", || {
+            assert_eq!(super::str_width_native(s), None);
+        });
+        with_env_locale("en_US", || {
+            assert_eq!(super::str_width_native(s), Some(2));
+            assert_eq!(super::str_width_native("a\u{4E16}"), None);
+        });
+    }
+}